@@ -0,0 +1,158 @@
+use crate::task::Task;
+use chrono::{Months, NaiveDate};
+
+// A due date this many days or more before the task was added is suspicious
+const DUE_BEFORE_ADDED_DAYS: i64 = 365;
+// Dates this many years or more after today are suspicious
+const FUTURE_YEARS: u32 = 20;
+
+// One of the dates a task has, named by its column in the todos table
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateField {
+    Due,
+    Start,
+    Scheduled,
+    FollowUp,
+    Completed,
+    Added,
+}
+
+impl DateField {
+    const ALL: [DateField; 6] = [
+        DateField::Due,
+        DateField::Start,
+        DateField::Scheduled,
+        DateField::FollowUp,
+        DateField::Completed,
+        DateField::Added,
+    ];
+
+    pub fn column(self) -> &'static str {
+        match self {
+            DateField::Due => "due_date",
+            DateField::Start => "start_date",
+            DateField::Scheduled => "scheduled_date",
+            DateField::FollowUp => "follow_up",
+            DateField::Completed => "completion_date",
+            DateField::Added => "created_at",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DateField::Due => "due date",
+            DateField::Start => "start date",
+            DateField::Scheduled => "scheduled date",
+            DateField::FollowUp => "follow-up date",
+            DateField::Completed => "completion date",
+            DateField::Added => "date added",
+        }
+    }
+
+    pub fn get(self, task: &Task) -> Option<NaiveDate> {
+        match self {
+            DateField::Due => task.due_date,
+            DateField::Start => task.start_date,
+            DateField::Scheduled => task.scheduled_date,
+            DateField::FollowUp => task.follow_up,
+            DateField::Completed => task.completion_date,
+            DateField::Added => task.created_at,
+        }
+    }
+
+    pub fn set(self, task: &mut Task, date: Option<NaiveDate>) {
+        match self {
+            DateField::Due => task.due_date = date,
+            DateField::Start => task.start_date = date,
+            DateField::Scheduled => task.scheduled_date = date,
+            DateField::FollowUp => task.follow_up = date,
+            DateField::Completed => task.completion_date = date,
+            DateField::Added => task.created_at = date,
+        }
+    }
+}
+
+// A date of a task that looks wrong, and the field a fix would change
+#[derive(Debug, PartialEq)]
+pub struct DateIssue {
+    pub field: DateField,
+    pub problem: String,
+}
+
+/// The dates of `task` that are likely mistakes, typically left by a bad
+/// import: a due date a year or more before the task was added, a
+/// completion date before it was added, and any date decades after `today`.
+pub fn check_dates(task: &Task, today: NaiveDate) -> Vec<DateIssue> {
+    let mut issues = Vec::new();
+    if let (Some(due), Some(added)) = (task.due_date, task.created_at) {
+        let days = (added - due).num_days();
+        if days >= DUE_BEFORE_ADDED_DAYS {
+            issues.push(DateIssue {
+                field: DateField::Due,
+                problem: format!(
+                    "due on {}, {} days before it was added on {}",
+                    due, days, added
+                ),
+            });
+        }
+    }
+    if let (Some(completed), Some(added)) = (task.completion_date, task.created_at) {
+        if completed < added {
+            issues.push(DateIssue {
+                field: DateField::Added,
+                problem: format!(
+                    "added on {}, after it was completed on {}",
+                    added, completed
+                ),
+            });
+        }
+    }
+
+    let far = today
+        .checked_add_months(Months::new(FUTURE_YEARS * 12))
+        .unwrap_or(NaiveDate::MAX);
+    for field in DateField::ALL {
+        if let Some(date) = field.get(task).filter(|d| *d >= far) {
+            issues.push(DateIssue {
+                field,
+                problem: format!(
+                    "{} is {}, {} years or more from now",
+                    field.label(),
+                    date,
+                    FUTURE_YEARS
+                ),
+            });
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_dates() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let date = |text: &str| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok();
+        let mut task = Task::new("Renew passport", "Home".to_string(), None, vec![], 0);
+        task.created_at = date("2025-01-10");
+        task.due_date = date("2025-02-01");
+        assert!(check_dates(&task, today).is_empty());
+
+        task.due_date = date("1925-02-01");
+        task.completion_date = date("2024-12-31");
+        task.follow_up = date("2099-01-01");
+        let fields: Vec<DateField> = check_dates(&task, today)
+            .iter()
+            .map(|issue| issue.field)
+            .collect();
+        assert_eq!(
+            fields,
+            [DateField::Due, DateField::Added, DateField::FollowUp]
+        );
+
+        task.due_date = date("2024-02-01");
+        assert_eq!(check_dates(&task, today)[0].field, DateField::Added);
+    }
+}
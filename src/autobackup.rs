@@ -0,0 +1,129 @@
+use crate::error::TodoError;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const UNIT_NAME: &str = "yawmak-backup";
+// Marks the crontab line so reinstalling replaces it instead of adding another
+const CRON_MARKER: &str = "# yawmak-autobackup";
+
+pub struct AutoBackup;
+
+impl AutoBackup {
+    /// Schedules `yawmak export --preset <preset>` to run `every` hour, day or
+    /// week. Uses a systemd user timer when systemd is running, or the user's
+    /// crontab otherwise (or when `use_cron` is set). Returns a short
+    /// description of what was installed.
+    pub fn install(preset: &str, every: &str, use_cron: bool) -> Result<String, TodoError> {
+        let exe = env::current_exe()?;
+        if !use_cron && Path::new("/run/systemd/system").exists() {
+            Self::install_systemd(&exe, preset, every)
+        } else {
+            Self::install_cron(&exe, preset, every)
+        }
+    }
+
+    // Describes the installed job, if there is one
+    pub fn installed() -> Option<String> {
+        let timer = Self::systemd_dir().join(format!("{}.timer", UNIT_NAME));
+        if timer.exists() {
+            return Some(format!("systemd user timer {}", timer.display()));
+        }
+        Self::crontab()
+            .lines()
+            .find(|line| line.contains(CRON_MARKER))
+            .map(|line| format!("crontab entry: {}", line))
+    }
+
+    fn install_systemd(exe: &Path, preset: &str, every: &str) -> Result<String, TodoError> {
+        let dir = Self::systemd_dir();
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join(format!("{}.service", UNIT_NAME)),
+            format!(
+                "[Unit]\nDescription=yawmak export preset {1}\n\n\
+                 [Service]\nType=oneshot\nExecStart=\"{0}\" export --preset \"{1}\"\n",
+                exe.display(),
+                preset
+            ),
+        )?;
+        let timer = dir.join(format!("{}.timer", UNIT_NAME));
+        fs::write(
+            &timer,
+            format!(
+                "[Unit]\nDescription=Run the yawmak {0} export {1}\n\n\
+                 [Timer]\nOnCalendar={1}\nPersistent=true\n\n\
+                 [Install]\nWantedBy=timers.target\n",
+                preset, every
+            ),
+        )?;
+
+        Self::run("systemctl", &["--user", "daemon-reload"])?;
+        Self::run(
+            "systemctl",
+            &["--user", "enable", "--now", &format!("{}.timer", UNIT_NAME)],
+        )?;
+        Ok(format!("systemd user timer {}", timer.display()))
+    }
+
+    fn install_cron(exe: &Path, preset: &str, every: &str) -> Result<String, TodoError> {
+        let entry = format!(
+            "@{} '{}' export --preset '{}' {}",
+            every,
+            exe.display(),
+            preset,
+            CRON_MARKER
+        );
+        let mut lines: Vec<String> = Self::crontab()
+            .lines()
+            .filter(|line| !line.contains(CRON_MARKER))
+            .map(String::from)
+            .collect();
+        lines.push(entry.clone());
+
+        let mut child = Command::new("crontab")
+            .arg("-")
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| TodoError::Custom(format!("Could not run crontab: {}", e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            writeln!(stdin, "{}", lines.join("\n"))?;
+        }
+        if !child.wait()?.success() {
+            return Err(TodoError::Custom("crontab rejected the new entry.".into()));
+        }
+        Ok(format!("crontab entry: {}", entry))
+    }
+
+    // The current user's crontab, or nothing when they have none
+    fn crontab() -> String {
+        Command::new("crontab")
+            .arg("-l")
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+            .unwrap_or_default()
+    }
+
+    fn systemd_dir() -> PathBuf {
+        let config_home = env::var("XDG_CONFIG_HOME")
+            .unwrap_or_else(|_| format!("{}/.config", env::var("HOME").unwrap_or_default()));
+        PathBuf::from(config_home).join("systemd/user")
+    }
+
+    fn run(program: &str, args: &[&str]) -> Result<(), TodoError> {
+        if Command::new(program).args(args).status()?.success() {
+            Ok(())
+        } else {
+            Err(TodoError::Custom(format!(
+                "`{} {}` failed.",
+                program,
+                args.join(" ")
+            )))
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::dates;
+use crate::task::references;
+use chrono::NaiveDate;
+
+// Category names recognised even before a category of that name exists
+const CATEGORY_HINTS: &[&str] = &["work", "personal", "home", "errands", "health", "finance"];
+
+// Leading phrases that carry no meaning for the task itself
+const FILLER_PREFIXES: &[&str] = &[
+    "remind me to ",
+    "remember to ",
+    "don't forget to ",
+    "i need to ",
+    "i have to ",
+    "todo: ",
+];
+
+// A task as understood from a free-form sentence
+#[derive(Debug, PartialEq)]
+pub struct Capture {
+    pub name: String,
+    pub due_date: Option<NaiveDate>,
+    pub priority: i32,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Reads a sentence like "remind me to renew passport next month, high
+/// priority, personal" with simple heuristics. Comma-separated clauses can
+/// hold a priority ("high priority", "urgent", "low priority") or a category
+/// (one of `categories` or a common name such as "work"). Relative dates
+/// ("tomorrow", "friday", "in 3 days", "next month", "end of month"),
+/// ISO dates and #tags are picked out of the text wherever they appear.
+pub fn interpret(text: &str, today: NaiveDate, categories: &[String]) -> Capture {
+    let mut text = text.trim().to_string();
+    for prefix in FILLER_PREFIXES {
+        if text.to_lowercase().starts_with(prefix) {
+            text = text[prefix.len()..].to_string();
+            break;
+        }
+    }
+
+    let mut priority = 0;
+    let mut category = None;
+    let mut kept_clauses = Vec::new();
+    for clause in text.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let lower = clause.to_lowercase();
+        if let Some(p) = priority_of(&lower) {
+            priority = p;
+        } else if let Some(c) = categories.iter().find(|c| c.to_lowercase() == lower) {
+            category = Some(c.clone());
+        } else if CATEGORY_HINTS.contains(&lower.as_str()) {
+            category = Some(capitalize(&lower));
+        } else {
+            kept_clauses.push(clause.to_string());
+        }
+    }
+
+    let mut words: Vec<String> = kept_clauses
+        .join(", ")
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    let tags = take_tags(&mut words);
+    let due_date = take_date(&mut words, today);
+
+    Capture {
+        name: capitalize(words.join(" ").trim_end_matches([',', '.'])),
+        due_date,
+        priority,
+        category,
+        tags,
+    }
+}
+
+fn priority_of(clause: &str) -> Option<i32> {
+    match clause {
+        "urgent" | "asap" | "important" | "high priority" | "top priority" => Some(3),
+        "medium priority" | "normal priority" => Some(2),
+        "low priority" | "someday" => Some(1),
+        _ => None,
+    }
+}
+
+// Takes out the #tags, leaving #<id> task references in the name
+fn take_tags(words: &mut Vec<String>) -> Vec<String> {
+    let is_tag = |w: &String| w.starts_with('#') && references(w).is_empty();
+    let tags = words
+        .iter()
+        .filter(|w| is_tag(w))
+        .map(|w| w[1..].trim_end_matches([',', '.']).to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    words.retain(|w| !is_tag(w));
+    tags
+}
+
+// Finds the first date phrase, removes it (and a leading "by", "on" or "due") and returns its date
+fn take_date(words: &mut Vec<String>, today: NaiveDate) -> Option<NaiveDate> {
+    let lower: Vec<String> = words
+        .iter()
+        .map(|w| w.trim_end_matches([',', '.']).to_lowercase())
+        .collect();
+
+    for start in 0..lower.len() {
+        let Some((date, len)) = dates::phrase_at(&lower[start..], today) else {
+            continue;
+        };
+        let mut from = start;
+        if from > 0 && matches!(lower[from - 1].as_str(), "by" | "on" | "due") {
+            from -= 1;
+        }
+        words.drain(from..start + len);
+        return Some(date);
+    }
+    None
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 5).unwrap()
+    }
+
+    #[test]
+    fn test_interpret_sentence() {
+        let capture = interpret(
+            "remind me to renew passport next month, high priority, personal",
+            today(),
+            &[],
+        );
+        assert_eq!(
+            capture,
+            Capture {
+                name: "Renew passport".to_string(),
+                due_date: NaiveDate::from_ymd_opt(2025, 4, 1),
+                priority: 3,
+                category: Some("Personal".to_string()),
+                tags: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_interpret_dates_tags_and_known_categories() {
+        let categories = vec!["Garden".to_string()];
+        let capture = interpret(
+            "Plant tulips by friday #spring, garden",
+            today(),
+            &categories,
+        );
+        assert_eq!(capture.name, "Plant tulips");
+        assert_eq!(capture.due_date, NaiveDate::from_ymd_opt(2025, 3, 7));
+        assert_eq!(capture.tags, vec!["spring"]);
+        assert_eq!(capture.category, Some("Garden".to_string()));
+
+        let capture = interpret("call the bank in 2 weeks", today(), &[]);
+        assert_eq!(capture.name, "Call the bank");
+        assert_eq!(capture.due_date, NaiveDate::from_ymd_opt(2025, 3, 19));
+    }
+
+    #[test]
+    fn test_interpret_keeps_task_references() {
+        let capture = interpret("call the bank in 2 weeks about #14 #money", today(), &[]);
+        assert_eq!(capture.name, "Call the bank about #14");
+        assert_eq!(capture.tags, vec!["money"]);
+        assert_eq!(capture.due_date, NaiveDate::from_ymd_opt(2025, 3, 19));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::theme;
+
+// Longer bar labels are cut short so they fit beside the bars
+const LABEL_CHARS: usize = 22;
+
+/// A horizontal bar chart as an SVG image, one labelled bar per value,
+/// scaled so the largest value fills the width. Used for `report --svg` and
+/// inline in the pages of `export site`.
+pub fn bar_chart(title: &str, bars: &[(String, i64)]) -> String {
+    const LABEL: i64 = 150;
+    const WIDTH: i64 = 250;
+    const ROW: i64 = 24;
+    let max = bars.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+    let mut svg = vec![
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\" font-size=\"12\">",
+            LABEL + WIDTH + 50,
+            ROW * (bars.len() as i64 + 1)
+        ),
+        format!(
+            "<text x=\"0\" y=\"16\" font-weight=\"bold\">{}</text>",
+            escape(title)
+        ),
+    ];
+    for (i, (label, count)) in bars.iter().enumerate() {
+        let y = ROW * (i as i64 + 1);
+        let width = count.max(&0) * WIDTH / max;
+        svg.push(format!(
+            "<text x=\"0\" y=\"{}\">{}</text><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\
+             <text x=\"{}\" y=\"{}\">{}</text>",
+            y + 16,
+            escape(&shorten(label)),
+            LABEL,
+            y + 4,
+            width,
+            ROW - 8,
+            theme::chart_bar_color(),
+            LABEL + width + 5,
+            y + 16,
+            count
+        ));
+    }
+    svg.push("</svg>".to_string());
+    svg.join("\n")
+}
+
+/// Text made safe to put in SVG or HTML, in element content or attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn shorten(label: &str) -> String {
+    if label.chars().count() <= LABEL_CHARS {
+        label.to_string()
+    } else {
+        let cut: String = label.chars().take(LABEL_CHARS - 1).collect();
+        format!("{}…", cut.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_chart_scales_to_the_largest_value() {
+        let svg = bar_chart(
+            "Open <tasks>",
+            &[
+                ("Work".to_string(), 4),
+                ("Home".to_string(), 2),
+                ("A rather long category name".to_string(), 0),
+            ],
+        );
+        assert!(svg.contains("Open &lt;tasks&gt;"));
+        assert!(svg.contains("width=\"250\""));
+        assert!(svg.contains("width=\"125\""));
+        assert!(svg.contains(">A rather long categor…<"));
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert!(svg.contains(&format!("fill=\"{}\"", theme::chart_bar_color())));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<b>\"Tom & Jerry's\"</b>"),
+            "&lt;b&gt;&quot;Tom &amp; Jerry&#39;s&quot;&lt;/b&gt;"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+/// How names are put in order when sorting tasks, categories and tags.
+/// `unicode` reads them the way people do: case and accents are ignored, so
+/// "émile" sorts with "Emile", and Arabic diacritics, tatweel and the
+/// hamza forms of alef don't move a word away from its plain spelling.
+/// `binary` compares the raw bytes.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Collation {
+    #[default]
+    Unicode,
+    Binary,
+}
+
+impl Collation {
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            Collation::Binary => a.cmp(b),
+            // Names that only differ in case or accents still get a fixed order
+            Collation::Unicode => fold(a)
+                .cmp(&fold(b))
+                .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+                .then_with(|| a.cmp(b)),
+        }
+    }
+
+    pub fn sort<T>(&self, items: &mut [T], key: impl Fn(&T) -> &str) {
+        items.sort_by(|a, b| self.compare(key(a), key(b)));
+    }
+}
+
+// The text as it sorts: lowercase, with accents and Arabic marks taken off
+fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match c {
+            // Arabic harakat, superscript alef and tatweel
+            '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}' => {}
+            'آ' | 'أ' | 'إ' | 'ٱ' => folded.push('ا'),
+            'ى' => folded.push('ي'),
+            'ß' => folded.push_str("ss"),
+            'æ' => folded.push_str("ae"),
+            'œ' => folded.push_str("oe"),
+            'þ' => folded.push_str("th"),
+            _ => folded.push(base_letter(c)),
+        }
+    }
+    folded
+}
+
+// The Latin letter an accented one is written on
+fn base_letter(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' | 'ð' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => 's',
+        'ţ' | 'ť' | 'ŧ' | 'ț' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unicode_ignores_case_and_accents() {
+        let mut names = vec!["zebra", "Éclair", "apple", "eclair", "Ölkanne", "Oboe"];
+        Collation::Unicode.sort(&mut names, |n| n);
+        assert_eq!(
+            names,
+            vec!["apple", "eclair", "Éclair", "Oboe", "Ölkanne", "zebra"]
+        );
+
+        Collation::Binary.sort(&mut names, |n| n);
+        assert_eq!(
+            names,
+            vec!["Oboe", "apple", "eclair", "zebra", "Éclair", "Ölkanne"]
+        );
+    }
+
+    #[test]
+    fn test_unicode_reads_arabic_without_marks() {
+        // "Ahmad" with a hamza and with a fatha sorts like the plain spelling
+        let mut names = vec!["بيت", "أحمد", "احمد", "اَحمد"];
+        Collation::Unicode.sort(&mut names, |n| n);
+        assert_eq!(fold(names[0]), fold(names[1]));
+        assert_eq!(fold(names[1]), fold(names[2]));
+        assert_eq!(names[3], "بيت");
+    }
+}
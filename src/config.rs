@@ -1,18 +1,233 @@
+use crate::collation::Collation;
+use crate::organize::CategoryHint;
+use crate::rules::{AutoTag, Rule};
+use crate::theme::Theme;
+use crate::urgency::Urgency;
+use crate::workflow::Workflow;
+use chrono::{NaiveDate, Weekday};
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::env;
+use std::fs;
 use std::path::PathBuf;
 
 pub struct Config {
     db_path: PathBuf,
+    settings: Settings,
+}
+
+// Optional settings read from ~/.yawmak/config.toml
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Settings {
+    // Maximum number of scheduled tasks per day used by `plan`
+    pub daily_capacity: usize,
+    // Days after which pending tasks are flagged as stale in `list --age`
+    pub stale_after_days: i64,
+    // Maximum number of pending tasks that can be pinned for `focus`
+    pub max_pinned: usize,
+    // Days ahead of a due date that `list` starts printing a reminder
+    pub reminder_lead_days: i64,
+    // Rolls unfinished tasks scheduled before this week over on the first run of a new week
+    pub auto_rollover: bool,
+    // Named exports run with `export --preset NAME`
+    pub export_presets: BTreeMap<String, ExportPreset>,
+    // Step templates started with `workflow start NAME TITLE`
+    pub workflows: BTreeMap<String, Workflow>,
+    // Rules applied by `rules run`
+    pub rules: Vec<Rule>,
+    // Applies the rules every time `list` runs
+    pub run_rules_before_list: bool,
+    // Tags added to new tasks whose names mention a keyword
+    pub auto_tags: Vec<AutoTag>,
+    // Categories `organize` suggests from keywords in task names, tried in order
+    pub organize: Vec<CategoryHint>,
+    // Custom fields tasks can carry, like ["client", "ticket"], set with `--set client=ACME`
+    pub attributes: Vec<String>,
+    // Recorded as the creator and completer of tasks when set
+    pub identity: Option<Identity>,
+    // How long finished, archived and trashed tasks are kept, applied by `maintain`
+    pub retention: Retention,
+    // How task, category and tag names are put in order
+    pub collation: Collation,
+    // The day weeks start on when numbering them, like "monday" or "sunday"
+    pub week_start: Weekday,
+    // The colours used by tables, the interactive list and charts
+    pub theme: Theme,
+    // Weights of the urgency score shown by `list --urgency`
+    pub urgency: Urgency,
+}
+
+// Each rule is off when left out
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Retention {
+    // Archives tasks completed more than this many days ago
+    pub archive_done_after_days: Option<i64>,
+    // Permanently deletes tasks archived more than this many days ago
+    pub purge_archived_after_days: Option<i64>,
+    // Permanently deletes tasks trashed more than this many days ago
+    pub purge_trash_after_days: Option<i64>,
+}
+
+// Who is using this copy of yawmak, so a shared database shows who did what
+#[derive(Deserialize, Debug, Clone)]
+pub struct Identity {
+    pub name: String,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+impl Identity {
+    // How the identity is stored on tasks, e.g. "Alice <alice@example.com>"
+    pub fn label(&self) -> String {
+        match &self.email {
+            Some(email) => format!("{} <{}>", self.name, email),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Whether a stored label belongs to `who`, given as a name, an email
+    /// address or the whole label. Case is ignored.
+    pub fn refers_to(label: &str, who: &str) -> bool {
+        let label = label.to_lowercase();
+        let who = who.trim().to_lowercase();
+        let (name, email) = match label.split_once(" <") {
+            Some((name, email)) => (name, email.trim_end_matches('>')),
+            None => (label.as_str(), ""),
+        };
+        label == who || name == who || email == who
+    }
+}
+
+// A saved export: which tasks and columns to write, in what format, and where
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExportPreset {
+    pub format: String,
+    // May contain {date}, {year}, {month}, {day} and {week} placeholders and a leading ~
+    pub path: String,
+    // One of "all", "pending", "done", "archived" or "trashed", as for `export --scope`
+    #[serde(default = "default_filter")]
+    pub filter: String,
+    // Every column when empty
+    #[serde(default)]
+    pub columns: Vec<String>,
+    // Columns written empty, plus "notes" and "urls", as for `export --redact`
+    #[serde(default)]
+    pub redact: Vec<String>,
+}
+
+fn default_filter() -> String {
+    "all".to_string()
+}
+
+impl ExportPreset {
+    pub fn resolve_path(&self, date: NaiveDate) -> String {
+        let path = self
+            .path
+            .replace("{date}", &date.format("%Y-%m-%d").to_string())
+            .replace("{year}", &date.format("%Y").to_string())
+            .replace("{month}", &date.format("%m").to_string())
+            .replace("{day}", &date.format("%d").to_string())
+            .replace("{week}", &date.format("%V").to_string());
+        match path.strip_prefix("~/") {
+            Some(rest) => format!("{}/{}", env::var("HOME").unwrap_or_default(), rest),
+            None => path,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            daily_capacity: 5,
+            stale_after_days: 30,
+            max_pinned: 3,
+            reminder_lead_days: 2,
+            auto_rollover: false,
+            export_presets: BTreeMap::new(),
+            workflows: BTreeMap::new(),
+            rules: Vec::new(),
+            run_rules_before_list: false,
+            auto_tags: Vec::new(),
+            organize: Vec::new(),
+            attributes: Vec::new(),
+            identity: None,
+            retention: Retention::default(),
+            collation: Collation::default(),
+            week_start: Weekday::Mon,
+            theme: Theme::default(),
+            urgency: Urgency::default(),
+        }
+    }
 }
 
 impl Config {
     pub fn new() -> Self {
         let home_dir = env::var("HOME").unwrap();
         let db_path = PathBuf::from(format!("{}/.yawmak/db", home_dir));
-        Config { db_path }
+        let config_path = PathBuf::from(format!("{}/.yawmak/config.toml", home_dir));
+        let settings = Self::load_settings(&config_path);
+        Config { db_path, settings }
     }
 
     pub fn get_db_path(&self) -> &PathBuf {
         &self.db_path
     }
+
+    pub fn get_settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    fn load_settings(path: &PathBuf) -> Settings {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return Settings::default(),
+        };
+
+        toml::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!(
+                "Ignoring {} because it could not be parsed: {}",
+                path.display(),
+                e
+            );
+            Settings::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_path_placeholders() {
+        let preset = ExportPreset {
+            format: "parquet".to_string(),
+            path: "/backups/{year}/todos-{date}-w{week}.parquet".to_string(),
+            filter: default_filter(),
+            columns: vec![],
+            redact: vec![],
+        };
+        let date = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        assert_eq!(
+            preset.resolve_path(date),
+            "/backups/2025/todos-2025-03-05-w10.parquet"
+        );
+    }
+
+    #[test]
+    fn test_identity_label_matching() {
+        let alice = Identity {
+            name: "Alice".to_string(),
+            email: Some("alice@example.com".to_string()),
+        };
+        let label = alice.label();
+        assert_eq!(label, "Alice <alice@example.com>");
+        assert!(Identity::refers_to(&label, "alice"));
+        assert!(Identity::refers_to(&label, "Alice@Example.com"));
+        assert!(Identity::refers_to(&label, "Alice <alice@example.com>"));
+        assert!(!Identity::refers_to(&label, "bob"));
+        assert!(Identity::refers_to("Bob", "bob"));
+    }
 }
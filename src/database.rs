@@ -1,39 +1,364 @@
+use crate::audit::DateField;
 use crate::error::TodoError;
-use crate::task::Task;
-use chrono::{Duration, NaiveDate};
-use duckdb::params;
+use crate::goal::Goal;
+use crate::history;
+use crate::recurrence::Recurrence;
+use crate::task::{references, Task};
+use crate::task_template::TaskTemplate;
+use chrono::{Duration, Local, NaiveDate, Weekday};
 use duckdb::types::ValueRef;
-use duckdb::{Connection, OptionalExt};
+use duckdb::{params, params_from_iter, ToSql};
+use duckdb::{Connection, OptionalExt, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub struct Database {
     conn: Connection,
 }
 
+// Open task count of a category next to its work-in-progress limit
+pub struct CategoryLoad {
+    pub name: String,
+    pub open_tasks: i64,
+    pub wip_limit: Option<i64>,
+    pub icon: Option<String>,
+}
+
+impl CategoryLoad {
+    pub fn is_over_limit(&self) -> bool {
+        self.wip_limit.is_some_and(|limit| self.open_tasks > limit)
+    }
+}
+
+// Aggregate counts for one category, shown at the top of `project`
+pub struct ProjectCounts {
+    pub open: i64,
+    pub done: i64,
+    pub overdue: i64,
+    pub waiting: i64,
+    pub done_last_week: i64,
+    pub wip_limit: Option<i64>,
+}
+
+// How long finished tasks in one group took, in days from creation to completion
+pub struct CompletionTimes {
+    // None for the row covering every group
+    pub group: Option<String>,
+    pub tasks: i64,
+    pub median: f64,
+    pub p90: f64,
+    // Share of the group's tasks with a due date that were finished after it
+    pub late: Option<f64>,
+}
+
+// A note left on a task, attributed to the configured identity
+#[derive(Serialize, Deserialize)]
+pub struct Comment {
+    pub author: Option<String>,
+    pub created_at: String,
+    pub body: String,
+}
+
+// A private, multi-line note kept with a task
+#[derive(Serialize, Deserialize)]
+pub struct Note {
+    pub id: i32,
+    pub created_at: String,
+    pub body: String,
+}
+
+// A recorded change to a task, with what it changed, as listed by `history`
+pub struct HistoryEntry {
+    pub recorded_at: String,
+    pub todo_id: i32,
+    pub name: String,
+    pub action: String,
+    pub changes: Vec<String>,
+}
+
+// A timestamped remark appended to a task with `annotate`
+#[derive(Serialize, Deserialize)]
+pub struct Annotation {
+    pub created_at: String,
+    pub body: String,
+}
+
+// A task as it was before an operation changed it, stored as JSON in the journal
+#[derive(Serialize, Deserialize)]
+struct TaskSnapshot {
+    task: Task,
+    comments: Vec<Comment>,
+    // Journals written before notes, annotations, tracked time or reminders existed have none
+    #[serde(default)]
+    notes: Vec<Note>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    #[serde(default)]
+    time_entries: Vec<TimeEntry>,
+    #[serde(default)]
+    reminders: Vec<i64>,
+}
+
+// A stretch of time tracked on a task, kept in a `TaskSnapshot`
+#[derive(Serialize, Deserialize)]
+struct TimeEntry {
+    started_at: String,
+    ended_at: Option<String>,
+    pomodoro: bool,
+}
+
+// A cross-tab of task counts from `report pivot`
+pub struct PivotTable {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+// The finished pomodoros on one task, for `report pomodoro`
+pub struct PomodoroCount {
+    pub id: i32,
+    pub name: String,
+    pub pomodoros: i64,
+    pub minutes: i64,
+}
+
+// A task's estimate against the time tracked on it, for `report accuracy`
+pub struct EstimateAccuracy {
+    pub id: i32,
+    pub name: String,
+    pub done: bool,
+    pub estimate: i64,
+    pub tracked: i64,
+}
+
+// How the last export of a preset went, for `autobackup status`
+pub struct ExportRun {
+    pub preset: String,
+    pub ran_at: String,
+    pub ok: bool,
+    // The file written, or the error when the export failed
+    pub detail: String,
+}
+
+// A command line that changes the database, recorded before it runs so `last` and `replay`
+// can tell when a run was cut short
+pub struct Operation {
+    pub id: i64,
+    pub args: Vec<String>,
+    pub started_at: String,
+    // "running" until it finishes as "done" or "failed"; "replayed" once `replay` has run it again
+    pub status: String,
+}
+
+impl Operation {
+    // The command as it could be typed again, quoting arguments with spaces
+    pub fn command_line(&self) -> String {
+        let args = self.args.iter().map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) {
+                format!("\"{}\"", arg)
+            } else {
+                arg.to_string()
+            }
+        });
+        std::iter::once("yawmak".to_string())
+            .chain(args)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+// What `mark_tasks_done` did: the tasks it found, and the next instances of the recurring ones
+#[derive(Default)]
+pub struct Completion {
+    pub done: Vec<i32>,
+    pub next: Vec<Task>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImportOutcome {
+    Inserted,
+    Updated,
+    Skipped,
+    Failed,
+}
+
+// What happened to one row of an imported file, numbered from 1
+pub struct ImportRowResult {
+    pub row: i64,
+    pub id: Option<i32>,
+    pub outcome: ImportOutcome,
+    pub reason: Option<String>,
+}
+
+pub struct ImportReport {
+    pub rows: Vec<ImportRowResult>,
+    // Columns in the file that `todos` does not have
+    pub ignored_columns: Vec<String>,
+    // The scope the file was exported with, when the format records it
+    pub scope: Option<String>,
+}
+
+impl ImportReport {
+    pub fn count(&self, outcome: ImportOutcome) -> usize {
+        self.rows.iter().filter(|r| r.outcome == outcome).count()
+    }
+}
+
+// Columns added after the initial schema, applied to existing databases on startup
+const MIGRATIONS: &[&str] = &[
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS scheduled_date DATE",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS created_at DATE DEFAULT CURRENT_DATE",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS pinned BOOLEAN DEFAULT false",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS context TEXT",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS waiting_for TEXT",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS follow_up DATE",
+    "ALTER TABLE categories ADD COLUMN IF NOT EXISTS wip_limit INTEGER",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS created_by TEXT",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS completed_by TEXT",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS parent_id INTEGER",
+    "ALTER TABLE archive ADD COLUMN IF NOT EXISTS parent_id INTEGER",
+    "ALTER TABLE trash ADD COLUMN IF NOT EXISTS parent_id INTEGER",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS repeat TEXT",
+    "ALTER TABLE archive ADD COLUMN IF NOT EXISTS repeat TEXT",
+    "ALTER TABLE trash ADD COLUMN IF NOT EXISTS repeat TEXT",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS start_date DATE",
+    "ALTER TABLE archive ADD COLUMN IF NOT EXISTS start_date DATE",
+    "ALTER TABLE trash ADD COLUMN IF NOT EXISTS start_date DATE",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS icon TEXT",
+    "ALTER TABLE archive ADD COLUMN IF NOT EXISTS icon TEXT",
+    "ALTER TABLE trash ADD COLUMN IF NOT EXISTS icon TEXT",
+    "ALTER TABLE categories ADD COLUMN IF NOT EXISTS icon TEXT",
+    "ALTER TABLE time_entries ADD COLUMN IF NOT EXISTS pomodoro BOOLEAN DEFAULT false",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS estimate INTEGER",
+    "ALTER TABLE archive ADD COLUMN IF NOT EXISTS estimate INTEGER",
+    "ALTER TABLE trash ADD COLUMN IF NOT EXISTS estimate INTEGER",
+    "ALTER TABLE todos ADD COLUMN IF NOT EXISTS assignee TEXT",
+    "ALTER TABLE archive ADD COLUMN IF NOT EXISTS assignee TEXT",
+    "ALTER TABLE trash ADD COLUMN IF NOT EXISTS assignee TEXT",
+    "ALTER TABLE categories ADD COLUMN IF NOT EXISTS checklist TEXT",
+];
+
+// The layout of exported tasks, written to every row as `schema_version`.
+// Bump it when a change to `todos` would stop older exports importing as they
+// are, and add the statements that upgrade them to EXPORT_UPGRADES.
+const EXPORT_SCHEMA_VERSION: i64 = 2;
+
+// What `--redact` takes besides column names
+const REDACT_WORDS: &[&str] = &["name", "notes", "urls"];
+
+// The date and time layout of text exports
+const DATE_LAYOUT: &str = "DATEFORMAT '%Y-%m-%d', TIMESTAMPFORMAT '%Y-%m-%d %H:%M:%S'";
+
+// Statements that bring the staged rows of an export up one version, where
+// the first entry upgrades version 1 to 2. Version 1 files, written before
+// exports were versioned, already have the columns `todos` has today.
+const EXPORT_UPGRADES: &[&[&str]] = &[&[]];
+
+// Words that `anonymize` builds placeholder task names from
+const PLACEHOLDER_VERBS: &[&str] = &[
+    "Review",
+    "Draft",
+    "Update",
+    "Plan",
+    "Check",
+    "Send",
+    "Prepare",
+    "Organize",
+    "Fix",
+    "Book",
+    "Call about",
+    "Clean",
+    "Order",
+    "Finish",
+    "Schedule",
+    "Sort out",
+];
+const PLACEHOLDER_ADJECTIVES: &[&str] = &[
+    "quarterly",
+    "new",
+    "weekly",
+    "shared",
+    "old",
+    "monthly",
+    "team",
+    "annual",
+    "spare",
+    "main",
+    "next",
+    "small",
+    "draft",
+    "final",
+    "kitchen",
+    "project",
+];
+const PLACEHOLDER_NOUNS: &[&str] = &[
+    "report",
+    "budget",
+    "notes",
+    "invoice",
+    "slides",
+    "garden",
+    "meeting",
+    "backlog",
+    "car",
+    "website",
+    "contract",
+    "shelves",
+    "newsletter",
+    "receipts",
+    "roadmap",
+    "tickets",
+];
+
+// How many operations `undo` can walk back through
+const JOURNAL_LIMIT: i64 = 100;
+
+// How many command lines `last` keeps
+const OPERATION_LIMIT: i64 = 100;
+
+const TASK_COLUMNS: &str = "id, task, done, due_date, completion_date, priority, scheduled_date, \
+     created_at, pinned, context, waiting_for, follow_up, created_by, completed_by, parent_id, repeat, start_date, icon, estimate, assignee";
+
 impl Database {
     // Import and export
-    pub fn import_from_json(&self, file_path: &str, strategy: &str) -> Result<(), TodoError> {
-        let command = match strategy {
-            "skip" => format!("INSERT OR IGNORE INTO todos SELECT * FROM read_json_auto('{}')", file_path),
-            "remove" => format!("COPY todos (task, done, due_date, completion_date, priority) FROM '{}' (FORMAT 'json')", file_path),
-            "upsert" => format!("INSERT OR REPLACE INTO todos SELECT * FROM read_json_auto('{}')", file_path),
-            _ => return Err(TodoError::Custom("Unsupported strategy".into())),
-        };
-        self.conn.execute(&command, []).map_err(TodoError::from)?;
-        Ok(())
+    pub fn import_from_json(
+        &self,
+        file_path: &str,
+        strategy: &str,
+        strict: bool,
+    ) -> Result<ImportReport, TodoError> {
+        self.import_rows(
+            &format!("read_json_auto('{}')", file_path),
+            strategy,
+            strict,
+        )
     }
 
-    pub fn import_from_parquet(&self, file_path: &str, strategy: &str) -> Result<(), TodoError> {
-        let command = match strategy {
-            "skip" => format!("INSERT OR IGNORE INTO todos SELECT * FROM read_parquet('{}')", file_path),
-            "remove" => format!("COPY todos (task, done, due_date, completion_date, priority) FROM '{}' (FORMAT 'parquet')", file_path),
-            "upsert" => format!("INSERT OR REPLACE INTO todos SELECT * FROM read_parquet('{}')", file_path),
-            _ => return Err(TodoError::Custom("Unsupported strategy".into())),
-        };
-        self.conn.execute(&command, []).map_err(TodoError::from)?;
-        Ok(())
+    pub fn import_from_parquet(
+        &self,
+        file_path: &str,
+        strategy: &str,
+        strict: bool,
+    ) -> Result<ImportReport, TodoError> {
+        let mut report =
+            self.import_rows(&format!("read_parquet('{}')", file_path), strategy, strict)?;
+        report.scope = self
+            .conn
+            .query_row(
+                "SELECT decode(value) FROM parquet_kv_metadata(?1) WHERE decode(key) = 'scope'",
+                [file_path],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(TodoError::from)?;
+        Ok(report)
     }
 
-    pub fn import_from_excel(&self, file_path: &str, strategy: &str) -> Result<(), TodoError> {
+    pub fn import_from_excel(
+        &self,
+        file_path: &str,
+        strategy: &str,
+        strict: bool,
+    ) -> Result<ImportReport, TodoError> {
         self.conn
             .execute("INSTALL spatial;", [])
             .map_err(TodoError::from)?;
@@ -43,63 +368,532 @@ impl Database {
 
         let sheet_name = file_path.strip_suffix(".xlsx").unwrap_or(file_path);
 
-        let command = match strategy {
-            "skip" => format!("INSERT OR IGNORE INTO todos SELECT * FROM st_read('{}', layer='{}')", file_path, sheet_name),
-            "remove" => format!("INSERT INTO todos (task, done, due_date, completion_date, priority) SELECT task, done, due_date, completion_date, priority FROM st_read('{}', layer='{}')", file_path, sheet_name),
-            "upsert" => format!("INSERT OR REPLACE INTO todos SELECT * FROM st_read('{}', layer='{}')", file_path, sheet_name),
-            _ => return Err(TodoError::Custom("Unsupported strategy".into())),
-        };
-        self.conn.execute(&command, []).map_err(TodoError::from)?;
-        Ok(())
+        self.import_rows(
+            &format!("st_read('{}', layer='{}')", file_path, sheet_name),
+            strategy,
+            strict,
+        )
     }
 
-    pub fn import_from_csv(&self, file_path: &str, strategy: &str) -> Result<(), TodoError> {
-        let command = match strategy {
-            "skip" => format!("INSERT OR IGNORE INTO todos SELECT * FROM read_csv_auto('{}')", file_path),
-            "remove" => format!("COPY todos (task, done, due_date, completion_date, priority) FROM '{}' (FORMAT 'csv')", file_path),
-            "upsert" => format!("INSERT OR REPLACE INTO todos SELECT * FROM read_csv_auto('{}')", file_path),
-            _ => return Err(TodoError::Custom("Unsupported strategy".into())),
-        };
-        self.conn.execute(&command, []).map_err(TodoError::from)?;
-        Ok(())
+    pub fn import_from_csv(
+        &self,
+        file_path: &str,
+        strategy: &str,
+        strict: bool,
+    ) -> Result<ImportReport, TodoError> {
+        self.import_rows(&format!("read_csv_auto('{}')", file_path), strategy, strict)
     }
 
-    pub fn export_to_json(&self, file_path: &str) -> Result<(), TodoError> {
+    // Stages the rows of `source` in a temporary table, decides what happens to each
+    // of them under `strategy` and then writes the accepted rows to `todos`. In strict
+    // mode the first problem aborts the import and leaves `todos` as it was.
+    fn import_rows(
+        &self,
+        source: &str,
+        strategy: &str,
+        strict: bool,
+    ) -> Result<ImportReport, TodoError> {
+        if !matches!(strategy, "skip" | "remove" | "upsert") {
+            return Err(TodoError::Custom("Unsupported strategy".into()));
+        }
+
         self.conn
             .execute(
-                &format!("COPY todos TO '{}' (FORMAT 'json')", file_path),
+                &format!(
+                    "CREATE OR REPLACE TEMP TABLE import_rows AS \
+                     SELECT row_number() OVER () AS import_row, * FROM {}",
+                    source
+                ),
                 [],
             )
             .map_err(TodoError::from)?;
+        let apply = || {
+            self.upgrade_import()
+                .and_then(|_| self.apply_import(strategy, strict))
+        };
+        // A strict import is all or nothing, so a row failing to write undoes the rows before it
+        let result = if strict {
+            self.in_transaction(apply)
+        } else {
+            apply()
+        };
+        self.conn
+            .execute("DROP TABLE IF EXISTS import_rows", [])
+            .map_err(TodoError::from)?;
+        result
+    }
+
+    // Upgrades the staged rows of an export made by an older yawmak to the
+    // current layout, and drops the schema_version column they came with
+    fn upgrade_import(&self) -> Result<(), TodoError> {
+        let versioned = self
+            .table_schema("import_rows")?
+            .iter()
+            .any(|(c, _)| c == "schema_version");
+        let version: i64 = if versioned {
+            self.conn
+                .query_row(
+                    "SELECT COALESCE(MAX(TRY_CAST(schema_version AS BIGINT)), 1) FROM import_rows",
+                    [],
+                    |row| row.get(0),
+                )
+                .map_err(TodoError::from)?
+        } else {
+            1
+        };
+        if version > EXPORT_SCHEMA_VERSION {
+            return Err(TodoError::Custom(format!(
+                "The file was exported by a newer yawmak (schema version {}). Please upgrade yawmak to import it.",
+                version
+            )));
+        }
+
+        for statements in &EXPORT_UPGRADES[(version.max(1) - 1) as usize..] {
+            for statement in *statements {
+                self.conn.execute(statement, []).map_err(TodoError::from)?;
+            }
+        }
+        if versioned {
+            self.conn
+                .execute("ALTER TABLE import_rows DROP COLUMN schema_version", [])
+                .map_err(TodoError::from)?;
+        }
         Ok(())
     }
 
-    pub fn export_to_parquet(&self, file_path: &str) -> Result<(), TodoError> {
-        self.conn
-            .execute(
-                &format!("COPY todos TO '{}' (FORMAT 'parquet')", file_path),
-                [],
-            )
+    fn apply_import(&self, strategy: &str, strict: bool) -> Result<ImportReport, TodoError> {
+        let file_schema: Vec<(String, String)> = self
+            .table_schema("import_rows")?
+            .into_iter()
+            .filter(|(c, _)| c != "import_row")
+            .collect();
+        let file_columns: Vec<String> = file_schema.iter().map(|(c, _)| c.clone()).collect();
+        if !file_columns.iter().any(|c| c == "task") {
+            return Err(TodoError::Custom(
+                "The file has no 'task' column, so there is nothing to import.".into(),
+            ));
+        }
+
+        let todo_schema = self.table_schema("todos")?;
+        let (columns, ignored_columns): (Vec<String>, Vec<String>) = file_columns
+            .into_iter()
+            .partition(|c| todo_schema.iter().any(|(name, _)| name == c));
+        if strict {
+            self.validate_import_schema(&file_schema, &todo_schema)?;
+        }
+        // `remove` has always imported rows as new tasks with fresh IDs
+        let use_ids = strategy != "remove" && columns.iter().any(|c| c == "id");
+        let id_expr = if use_ids { "id" } else { "NULL" };
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT import_row, TRY_CAST({0} AS INTEGER), \
+                        {0} IS NOT NULL AND TRY_CAST({0} AS INTEGER) IS NULL, \
+                        task IS NULL OR trim(CAST(task AS TEXT)) = '', \
+                        TRY_CAST({0} AS INTEGER) IN (SELECT id FROM todos) \
+                 FROM import_rows ORDER BY import_row",
+                id_expr
+            ))
+            .map_err(TodoError::from)?;
+        let staged = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Option<i32>>(1)?,
+                    row.get::<_, bool>(2)?,
+                    row.get::<_, bool>(3)?,
+                    row.get::<_, Option<bool>>(4)?.unwrap_or(false),
+                ))
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
             .map_err(TodoError::from)?;
+
+        let mut seen_ids = HashSet::new();
+        let mut rows = Vec::new();
+        for (row, id, bad_id, missing_task, exists) in staged {
+            let (outcome, reason) = if missing_task {
+                (
+                    ImportOutcome::Failed,
+                    Some("missing task description".to_string()),
+                )
+            } else if bad_id {
+                (
+                    ImportOutcome::Failed,
+                    Some("id is not a whole number".to_string()),
+                )
+            } else if id.is_some_and(|id| !seen_ids.insert(id)) {
+                (
+                    ImportOutcome::Failed,
+                    Some("same id as an earlier row in the file".to_string()),
+                )
+            } else if exists && strategy == "skip" {
+                (
+                    ImportOutcome::Skipped,
+                    Some("a task with this id already exists".to_string()),
+                )
+            } else if exists {
+                (ImportOutcome::Updated, None)
+            } else {
+                (ImportOutcome::Inserted, None)
+            };
+            if strict && outcome == ImportOutcome::Failed {
+                return Err(TodoError::Custom(format!(
+                    "Row {}: {}. Nothing was imported.",
+                    row,
+                    reason.unwrap_or_default()
+                )));
+            }
+            rows.push(ImportRowResult {
+                row,
+                id,
+                outcome,
+                reason,
+            });
+        }
+
+        let all_columns = columns.join(", ");
+        let new_task_columns = columns
+            .iter()
+            .filter(|c| *c != "id")
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", ");
+        for (outcome, with_id, insert) in [
+            (ImportOutcome::Inserted, true, "INSERT"),
+            (ImportOutcome::Inserted, false, "INSERT"),
+            (ImportOutcome::Updated, true, "INSERT OR REPLACE"),
+        ] {
+            let target_columns = if with_id {
+                &all_columns
+            } else {
+                &new_task_columns
+            };
+            let selected: Vec<usize> = rows
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.outcome == outcome && r.id.is_some() == with_id)
+                .map(|(i, _)| i)
+                .collect();
+            if selected.is_empty() {
+                continue;
+            }
+            let sql = |row_numbers: &[i64]| {
+                format!(
+                    "{} INTO todos ({1}) SELECT {1} FROM import_rows WHERE import_row IN ({2})",
+                    insert,
+                    target_columns,
+                    row_numbers
+                        .iter()
+                        .map(|n| n.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+
+            let row_numbers: Vec<i64> = selected.iter().map(|&i| rows[i].row).collect();
+            match self.conn.execute(&sql(&row_numbers), []) {
+                Ok(_) => continue,
+                Err(e) if strict => {
+                    let message = e.to_string();
+                    return Err(TodoError::Custom(format!(
+                        "{}. Nothing was imported.",
+                        message.lines().next().unwrap_or_default()
+                    )));
+                }
+                Err(_) => {}
+            }
+            // Retry one row at a time so a single bad value only fails its own row
+            for i in selected {
+                if let Err(e) = self.conn.execute(&sql(&[rows[i].row]), []) {
+                    let message = e.to_string();
+                    rows[i].outcome = ImportOutcome::Failed;
+                    rows[i].reason = Some(message.lines().next().unwrap_or_default().to_string());
+                }
+            }
+        }
+
+        Ok(ImportReport {
+            rows,
+            ignored_columns,
+            scope: None,
+        })
+    }
+
+    // Checks every column of the staged file against the type `todos` expects and
+    // reports the first value that does not fit
+    fn validate_import_schema(
+        &self,
+        file_schema: &[(String, String)],
+        todo_schema: &[(String, String)],
+    ) -> Result<(), TodoError> {
+        for (column, found) in file_schema {
+            let expected = match todo_schema.iter().find(|(name, _)| name == column) {
+                Some((_, expected)) => expected,
+                None => {
+                    return Err(TodoError::Custom(format!(
+                        "Unexpected column '{}'; the todo list has no such column. Nothing was imported.",
+                        column
+                    )))
+                }
+            };
+            if found == expected {
+                continue;
+            }
+
+            let bad_row: Option<i64> = self
+                .conn
+                .query_row(
+                    &format!(
+                        "SELECT import_row FROM import_rows \
+                         WHERE \"{0}\" IS NOT NULL AND TRY_CAST(\"{0}\" AS {1}) IS NULL \
+                         ORDER BY import_row LIMIT 1",
+                        column, expected
+                    ),
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(TodoError::from)?;
+            if let Some(row) = bad_row {
+                return Err(TodoError::Custom(format!(
+                    "Column '{}' expected {}, found {} at row {}. Nothing was imported.",
+                    column, expected, found, row
+                )));
+            }
+        }
         Ok(())
     }
 
-    pub fn export_to_excel(&self, file_path: &str) -> Result<(), TodoError> {
-        self.conn
-            .execute(
-                &format!(
-                    "COPY (SELECT * FROM todos) TO '{}' WITH (FORMAT GDAL, DRIVER 'xlsx')",
-                    file_path
-                ),
-                [],
-            )
+    fn table_schema(&self, table: &str) -> Result<Vec<(String, String)>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT column_name, column_type FROM (DESCRIBE {})",
+                table
+            ))
             .map_err(TodoError::from)?;
-        Ok(())
+        let columns = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(columns)
+    }
+
+    /// Exports the chosen columns of the tasks in `scope`, every column when
+    /// `columns` is empty, redacted as `redact_select` describes. `name` can
+    /// stand for the `task` column. Each row is stamped with the export schema
+    /// version so later imports can upgrade it.
+    pub fn export_filtered(
+        &self,
+        file_path: &str,
+        format: &str,
+        scope: &str,
+        columns: &[String],
+        redact: &[String],
+    ) -> Result<(), TodoError> {
+        let known = self.table_schema("todos")?;
+        let columns: Vec<String> = columns
+            .iter()
+            .map(|c| if c == "name" { "task" } else { c }.to_string())
+            .collect();
+        if let Some(unknown) = columns
+            .iter()
+            .find(|c| !known.iter().any(|(name, _)| name == *c))
+        {
+            return Err(TodoError::Custom(format!("Unknown column '{}'.", unknown)));
+        }
+        check_redact(redact, &known)?;
+
+        let chosen: Vec<(String, String)> = if columns.is_empty() {
+            known
+        } else {
+            columns
+                .iter()
+                .filter_map(|c| known.iter().find(|(name, _)| name == c).cloned())
+                .collect()
+        };
+        let source = scope_source(scope)?;
+
+        self.copy_to(
+            &format!(
+                "SELECT {}, {} AS schema_version FROM {} AS todos ORDER BY todos.id",
+                redact_select(&chosen, redact).join(", "),
+                EXPORT_SCHEMA_VERSION,
+                source
+            ),
+            file_path,
+            format,
+            scope,
+        )
+    }
+
+    // Exports the comments on the tasks in `scope`, oldest first per task
+    pub fn export_comments(
+        &self,
+        file_path: &str,
+        format: &str,
+        scope: &str,
+        redact: &[String],
+    ) -> Result<(), TodoError> {
+        let columns = [
+            ("todo_id", "INTEGER"),
+            ("task", "VARCHAR"),
+            ("author", "VARCHAR"),
+            ("created_at", "TIMESTAMP"),
+            ("body", "VARCHAR"),
+        ];
+        self.export_text(
+            "SELECT c.todo_id, t.task, c.author, c.created_at, c.body, c.rowid AS position \
+             FROM comments c JOIN {} t ON t.id = c.todo_id",
+            &columns,
+            file_path,
+            format,
+            scope,
+            redact,
+        )
+    }
+
+    // Exports the annotations on the tasks in `scope`, oldest first per task
+    pub fn export_annotations(
+        &self,
+        file_path: &str,
+        format: &str,
+        scope: &str,
+        redact: &[String],
+    ) -> Result<(), TodoError> {
+        let columns = [
+            ("todo_id", "INTEGER"),
+            ("task", "VARCHAR"),
+            ("created_at", "TIMESTAMP"),
+            ("body", "VARCHAR"),
+        ];
+        self.export_text(
+            "SELECT a.todo_id, t.task, a.created_at, a.body, a.rowid AS position \
+             FROM annotations a JOIN {} t ON t.id = a.todo_id",
+            &columns,
+            file_path,
+            format,
+            scope,
+            redact,
+        )
+    }
+
+    // Exports the rows of `query`, whose {} is replaced by the tasks in `scope`,
+    // as `columns`, oldest first per task
+    fn export_text(
+        &self,
+        query: &str,
+        columns: &[(&str, &str)],
+        file_path: &str,
+        format: &str,
+        scope: &str,
+        redact: &[String],
+    ) -> Result<(), TodoError> {
+        let columns: Vec<(String, String)> = columns
+            .iter()
+            .map(|(name, column_type)| (name.to_string(), column_type.to_string()))
+            .collect();
+        let mut known = self.table_schema("todos")?;
+        known.extend(columns.iter().cloned());
+        check_redact(redact, &known)?;
+
+        self.copy_to(
+            &format!(
+                "SELECT {} FROM ({}) AS entries \
+                 ORDER BY entries.todo_id, entries.created_at, entries.position",
+                redact_select(&columns, redact).join(", "),
+                query.replace("{}", &scope_source(scope)?)
+            ),
+            file_path,
+            format,
+            scope,
+        )
+    }
+
+    // The tasks in `scope` as an export would write them, for `export site`
+    pub fn export_tasks(&self, scope: &str, redact: &[String]) -> Result<Vec<Task>, TodoError> {
+        let known = self.table_schema("todos")?;
+        check_redact(redact, &known)?;
+        let columns: Vec<(String, String)> = TASK_COLUMNS
+            .split(',')
+            .filter_map(|c| known.iter().find(|(name, _)| name == c.trim()).cloned())
+            .collect();
+
+        // A task can't be without these, so redacted ones are read back as blank
+        let required: Vec<String> = columns
+            .iter()
+            .map(|(name, _)| match name.as_str() {
+                "id" => "COALESCE(id, 0)".to_string(),
+                "task" => "COALESCE(task, '')".to_string(),
+                "done" => "COALESCE(done, false)".to_string(),
+                "priority" => "COALESCE(priority, 0)".to_string(),
+                _ => name.clone(),
+            })
+            .collect();
+
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {}, category, tags FROM \
+                 (SELECT {}, category, tags, todos.id AS position FROM {} AS todos) \
+                 ORDER BY position",
+                required.join(", "),
+                redact_select(&columns, redact).join(", "),
+                scope_source(scope)?
+            ))
+            .map_err(TodoError::from)?;
+        let tasks = stmt
+            .query_map([], |row| {
+                let mut task = task_from_row(row)?;
+                task.category = row.get(20)?;
+                task.tags = row
+                    .get::<_, Option<String>>(21)?
+                    .map(|tags| {
+                        tags.split(',')
+                            .filter(|t| !t.is_empty())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(task)
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(tasks)
     }
 
-    pub fn export_to_csv(&self, file_path: &str) -> Result<(), TodoError> {
+    // Parquet files also record the export scope in their key/value metadata.
+    // Dates and times are written in one fixed layout, so repeated exports of
+    // the same rows give the same file.
+    fn copy_to(
+        &self,
+        query: &str,
+        file_path: &str,
+        format: &str,
+        scope: &str,
+    ) -> Result<(), TodoError> {
+        let options = match format {
+            "json" => format!("(FORMAT 'json', {})", DATE_LAYOUT),
+            "parquet" => format!("(FORMAT 'parquet', KV_METADATA {{scope: '{}'}})", scope),
+            "xlsx" => "WITH (FORMAT GDAL, DRIVER 'xlsx')".to_string(),
+            "csv" => format!("(FORMAT 'csv', HEADER true, {})", DATE_LAYOUT),
+            _ => {
+                return Err(TodoError::Custom(
+                    "Unsupported format. Please use json, parquet, xlsx, or csv.".into(),
+                ))
+            }
+        };
         self.conn
-            .execute(&format!("COPY todos TO '{}' (FORMAT 'csv')", file_path), [])
+            .execute(
+                &format!("COPY ({}) TO '{}' {}", query, file_path, options),
+                [],
+            )
             .map_err(TodoError::from)?;
         Ok(())
     }
@@ -179,100 +973,388 @@ impl Database {
         )
         .map_err(TodoError::from)?;
 
-        Ok(Database { conn })
-    }
-
-    pub fn add_task(&self, task: Task) -> Result<(), TodoError> {
-        let sql = "INSERT INTO todos (task, due_date, priority) VALUES (?1, ?2, ?3) RETURNING id";
-        let due_date_str = task.due_date.map(|d| d.format("%Y-%m-%d").to_string());
-        let last_id: i32 = self
-            .conn
-            .query_row(
-                sql,
-                params![&task.name, due_date_str.as_deref(), &task.priority],
-                |row| row.get(0),
-            )
+        conn.execute("CREATE SEQUENCE IF NOT EXISTS goal_id_seq", [])
             .map_err(TodoError::from)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS goals (
+                id INTEGER DEFAULT nextval('goal_id_seq') PRIMARY KEY,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                target INTEGER NOT NULL,
+                period TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
 
-        if let Some(ref category) = task.category {
-            self.add_category(category)?;
-            let category_id = self.get_category_id(category)?;
-            self.conn
-                .execute(
-                    "INSERT INTO todo_categories (todo_id, category_id) VALUES (?1, ?2)",
-                    &[&last_id, &category_id],
-                )
-                .map_err(TodoError::from)?;
-        }
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS templates (
+                name TEXT PRIMARY KEY,
+                pattern TEXT NOT NULL,
+                category TEXT,
+                tags TEXT,
+                priority INTEGER NOT NULL,
+                due_in INTEGER
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
 
-        // Insert each tag separately
-        for tag in &task.tags {
-            self.add_tag(tag)?;
-            let tag_id = self.get_tag_id(tag)?;
-            self.conn
-                .execute(
-                    "INSERT INTO todo_tags (todo_id, tag_id) VALUES (?1, ?2)",
-                    &[&last_id, &tag_id],
-                )
-                .map_err(TodoError::from)?;
-        }
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS state (
+                key TEXT PRIMARY KEY,
+                value TEXT
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
 
-        Ok(())
-    }
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS rule_runs (
+                rule TEXT NOT NULL,
+                todo_id INTEGER NOT NULL,
+                applied_on DATE NOT NULL,
+                PRIMARY KEY (rule, todo_id)
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
 
-    pub fn get_tasks(&self, done_only: Option<bool>) -> Result<Vec<Task>, TodoError> {
-        let query = match done_only {
-            Some(true) => {
-                "SELECT id, task, done, due_date, completion_date, priority FROM todos WHERE done = 1"
-            }
-            Some(false) => {
-                "SELECT id, task, done, due_date, completion_date, priority FROM todos WHERE done = 0"
-            }
-            None => "SELECT id, task, done, due_date, completion_date, priority FROM todos",
-        };
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS export_runs (
+                preset TEXT NOT NULL,
+                ran_at TIMESTAMP NOT NULL,
+                ok BOOLEAN NOT NULL,
+                detail TEXT
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
 
-        let mut stmt = self.conn.prepare(query).map_err(TodoError::from)?;
-        let rows = stmt
-            .query_map([], |row| {
-                let id: i32 = row.get(0)?;
-                let task: String = row.get(1)?;
-                let done: bool = row.get(2)?;
-                let due_date = match row.get_ref(3)? {
-                    ValueRef::Date32(ref date32) => Some(
-                        NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
-                            + Duration::days(*date32 as i64),
-                    ),
-                    _ => None,
-                };
-                let completion_date = match row.get_ref(4)? {
-                    ValueRef::Date32(ref date32) => Some(
-                        NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
-                            + Duration::days(*date32 as i64),
-                    ),
-                    _ => None,
-                };
-                let priority: i32 = row.get(5)?;
+        // Completed tasks moved out of `todos` by `archive` and deleted ones kept by `trash`,
+        // with their category and comma-separated tags
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS archive (
+                id INTEGER PRIMARY KEY,
+                task TEXT NOT NULL,
+                done BOOLEAN NOT NULL,
+                due_date DATE,
+                completion_date DATE,
+                priority INTEGER,
+                scheduled_date DATE,
+                created_at DATE,
+                pinned BOOLEAN,
+                context TEXT,
+                waiting_for TEXT,
+                follow_up DATE,
+                created_by TEXT,
+                completed_by TEXT,
+                category TEXT,
+                tags TEXT,
+                archived_at DATE NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS trash (
+                id INTEGER PRIMARY KEY,
+                task TEXT NOT NULL,
+                done BOOLEAN NOT NULL,
+                due_date DATE,
+                completion_date DATE,
+                priority INTEGER,
+                scheduled_date DATE,
+                created_at DATE,
+                pinned BOOLEAN,
+                context TEXT,
+                waiting_for TEXT,
+                follow_up DATE,
+                created_by TEXT,
+                completed_by TEXT,
+                category TEXT,
+                tags TEXT,
+                deleted_at DATE NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
 
-                // Handle errors properly by mapping them to TodoError
-                let category = self.get_task_category(id).unwrap_or_else(|_| None);
-                let tags = self.get_task_tags(id).unwrap_or_else(|_| vec![]);
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS journal (
+                op_id INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                todo_id INTEGER NOT NULL,
+                before TEXT,
+                recorded_at TIMESTAMP NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
 
-                Ok(Task {
-                    id,
-                    name: task,
-                    category,
-                    tags,
-                    done,
-                    due_date,
-                    completion_date,
-                    priority,
-                })
-            })
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS comments (
+                todo_id INTEGER NOT NULL,
+                author TEXT,
+                created_at TIMESTAMP NOT NULL,
+                body TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        conn.execute("CREATE SEQUENCE IF NOT EXISTS note_id_seq", [])
+            .map_err(TodoError::from)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS notes (
+                id INTEGER DEFAULT nextval('note_id_seq') PRIMARY KEY,
+                todo_id INTEGER NOT NULL,
+                created_at TIMESTAMP NOT NULL,
+                body TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        // Annotations are only ever added, never edited or removed on their own
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS annotations (
+                todo_id INTEGER NOT NULL,
+                created_at TIMESTAMP NOT NULL,
+                body TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        // Pairs of related tasks, stored once with the lower ID first
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS relations (
+                todo_id INTEGER NOT NULL,
+                related_id INTEGER NOT NULL,
+                PRIMARY KEY (todo_id, related_id)
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        // Tasks marked done together, one row per task, so a group costs a row per task
+        // rather than one for every pair in it
+        conn.execute("CREATE SEQUENCE IF NOT EXISTS completion_group_seq", [])
+            .map_err(TodoError::from)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS completion_groups (
+                group_id INTEGER NOT NULL,
+                todo_id INTEGER NOT NULL,
+                PRIMARY KEY (group_id, todo_id)
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        // Days before a task's due date that `reminders` brings it up
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS reminders (
+                todo_id INTEGER NOT NULL,
+                days_before INTEGER NOT NULL,
+                PRIMARY KEY (todo_id, days_before)
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        // Values of the custom fields named under `attributes` in the config
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_attributes (
+                todo_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (todo_id, name)
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        // Time spent on tasks; the running timer is the entry with no end
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS time_entries (
+                todo_id INTEGER NOT NULL,
+                started_at TIMESTAMP NOT NULL,
+                ended_at TIMESTAMP
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        // Every journaled change, kept after the journal drops it and after the task is gone
+        conn.execute("CREATE SEQUENCE IF NOT EXISTS history_id_seq", [])
+            .map_err(TodoError::from)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS history (
+                id INTEGER DEFAULT nextval('history_id_seq') PRIMARY KEY,
+                todo_id INTEGER NOT NULL,
+                action TEXT NOT NULL,
+                before TEXT,
+                recorded_at TIMESTAMP NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        conn.execute("CREATE SEQUENCE IF NOT EXISTS operation_id_seq", [])
+            .map_err(TodoError::from)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS operations (
+                id INTEGER DEFAULT nextval('operation_id_seq') PRIMARY KEY,
+                args TEXT NOT NULL,
+                started_at TIMESTAMP NOT NULL,
+                status TEXT NOT NULL
+            )",
+            [],
+        )
+        .map_err(TodoError::from)?;
+
+        for migration in MIGRATIONS {
+            conn.execute(migration, []).map_err(TodoError::from)?;
+        }
+
+        Ok(Database { conn })
+    }
+
+    // Returns the new task's ID
+    pub fn add_task(&self, task: Task) -> Result<i32, TodoError> {
+        self.check_references(&task.name)?;
+        if let Some(parent_id) = task.parent_id {
+            if self.get_task(parent_id)?.is_none() {
+                return Err(TodoError::Custom(format!(
+                    "No task found with ID {} to add a subtask to.",
+                    parent_id
+                )));
+            }
+        }
+        let sql = "INSERT INTO todos (task, due_date, priority, context, created_by, parent_id, repeat, start_date, icon, estimate, assignee) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11) RETURNING id";
+        let due_date_str = task.due_date.map(|d| d.format("%Y-%m-%d").to_string());
+        let start_date_str = task.start_date.map(|d| d.format("%Y-%m-%d").to_string());
+        let last_id: i32 = self
+            .conn
+            .query_row(
+                sql,
+                params![
+                    &task.name,
+                    due_date_str.as_deref(),
+                    &task.priority,
+                    task.context.as_deref(),
+                    task.created_by.as_deref(),
+                    task.parent_id,
+                    task.repeat.as_deref(),
+                    start_date_str.as_deref(),
+                    task.icon.as_deref(),
+                    task.estimate,
+                    task.assignee.as_deref()
+                ],
+                |row| row.get(0),
+            )
             .map_err(TodoError::from)?;
 
+        self.link_category_and_tags(last_id, task.category.as_deref(), &task.tags)?;
+        for (name, value) in &task.attributes {
+            self.set_attribute(last_id, name, Some(value))?;
+        }
+        Ok(last_id)
+    }
+
+    fn link_category_and_tags(
+        &self,
+        id: i32,
+        category: Option<&str>,
+        tags: &[String],
+    ) -> Result<(), TodoError> {
+        if let Some(category) = category {
+            self.add_category(category)?;
+            let category_id = self.get_category_id(category)?;
+            self.conn
+                .execute(
+                    "INSERT INTO todo_categories (todo_id, category_id) VALUES (?1, ?2)",
+                    [&id, &category_id],
+                )
+                .map_err(TodoError::from)?;
+        }
+
+        // Insert each tag separately, once even when it is listed twice
+        let mut linked = HashSet::new();
+        for tag in tags.iter().filter(|t| linked.insert(t.as_str())) {
+            self.add_tag(tag)?;
+            let tag_id = self.get_tag_id(tag)?;
+            self.conn
+                .execute(
+                    "INSERT INTO todo_tags (todo_id, tag_id) VALUES (?1, ?2)",
+                    [&id, &tag_id],
+                )
+                .map_err(TodoError::from)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_tasks(&self, done_only: Option<bool>) -> Result<Vec<Task>, TodoError> {
+        match done_only {
+            Some(true) => self.tasks_where("done = 1"),
+            Some(false) => self.tasks_where("done = 0"),
+            None => self.tasks_where("true"),
+        }
+    }
+
+    // Refuses a task name that refers to a task that doesn't exist
+    pub fn check_references(&self, name: &str) -> Result<(), TodoError> {
+        for id in references(name) {
+            if self.get_task(id)?.is_none() {
+                return Err(TodoError::Custom(format!(
+                    "The name refers to task #{}, which does not exist.",
+                    id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // Tasks whose names refer to task `id` as #<id>
+    pub fn get_backlinks(&self, id: i32) -> Result<Vec<Task>, TodoError> {
+        let mut tasks = self.tasks_where(&format!("task LIKE '%#{}%' ORDER BY id", id))?;
+        tasks.retain(|task| references(&task.name).contains(&id));
+        Ok(tasks)
+    }
+
+    /// The subtasks of a task, and theirs in turn, in ID order
+    pub fn get_subtasks(&self, id: i32) -> Result<Vec<Task>, TodoError> {
+        self.tasks_where(&format!(
+            "id IN (WITH RECURSIVE below(id) AS ( \
+                 SELECT id FROM todos WHERE parent_id = {0} \
+                 UNION SELECT t.id FROM todos t JOIN below b ON t.parent_id = b.id) \
+             SELECT id FROM below) AND id <> {0} ORDER BY id",
+            id
+        ))
+    }
+
+    pub fn get_task(&self, id: i32) -> Result<Option<Task>, TodoError> {
+        Ok(self.tasks_where(&format!("id = {}", id))?.pop())
+    }
+
+    fn tasks_where(&self, condition: &str) -> Result<Vec<Task>, TodoError> {
+        let query = format!("SELECT {} FROM todos WHERE {}", TASK_COLUMNS, condition);
+        let mut stmt = self.conn.prepare(&query).map_err(TodoError::from)?;
+        let rows = stmt.query_map([], task_from_row).map_err(TodoError::from)?;
+
         let mut tasks = Vec::new();
         for row in rows {
-            tasks.push(row.map_err(TodoError::from)?);
+            let mut task = row.map_err(TodoError::from)?;
+            // Handle errors properly by mapping them to TodoError
+            task.category = self.get_task_category(task.id).unwrap_or(None);
+            task.tags = self.get_task_tags(task.id).unwrap_or_default();
+            task.attributes = self.get_task_attributes(task.id).unwrap_or_default();
+            if let Some(category) = &task.category {
+                task.category_icon = self.get_category_icon(category).unwrap_or(None);
+            }
+            tasks.push(task);
         }
         Ok(tasks)
     }
@@ -302,81 +1384,1940 @@ impl Database {
         Ok(tags)
     }
 
-    pub fn mark_task_done(&self, id: i32) -> Result<(), TodoError> {
-        let sql = "UPDATE todos SET done = 1, completion_date = CURRENT_DATE WHERE id = ?1";
-        self.conn.execute(sql, &[&id]).map_err(TodoError::from)?;
+    pub fn get_task_attributes(&self, task_id: i32) -> Result<BTreeMap<String, String>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, value FROM task_attributes WHERE todo_id = ?1")
+            .map_err(TodoError::from)?;
+        let attributes = stmt
+            .query_map([task_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(TodoError::from)?
+            .collect::<Result<BTreeMap<_, _>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(attributes)
+    }
+
+    // Sets one custom field of a task in the todo list, or clears it when `value` is None
+    pub fn set_attribute(&self, id: i32, name: &str, value: Option<&str>) -> Result<(), TodoError> {
+        self.conn
+            .execute(
+                "DELETE FROM task_attributes WHERE todo_id = ?1 AND name = ?2",
+                params![id, name],
+            )
+            .map_err(TodoError::from)?;
+        if let Some(value) = value {
+            self.conn
+                .execute(
+                    "INSERT INTO task_attributes (todo_id, name, value) \
+                     SELECT ?1, ?2, ?3 WHERE EXISTS (SELECT 1 FROM todos WHERE id = ?1)",
+                    params![id, name, value],
+                )
+                .map_err(TodoError::from)?;
+        }
         Ok(())
     }
 
-    pub fn update_task(
+    /// Marks the tasks done, recording `by` as their completer. Completing a
+    /// pending task that repeats adds its next instance, with the same
+    /// details and the due date moved on by the task's rule.
+    fn mark_tasks_done(&self, ids: &[i32], by: Option<&str>) -> Result<Completion, TodoError> {
+        let sql = "UPDATE todos SET done = 1, completion_date = CURRENT_DATE, completed_by = ?2 WHERE id = ?1";
+        let today = Local::now().date_naive();
+        let mut completion = Completion::default();
+        for id in ids {
+            let Some(task) = self.get_task(*id)? else {
+                continue;
+            };
+            self.conn
+                .execute(sql, params![id, by])
+                .map_err(TodoError::from)?;
+            completion.done.push(*id);
+
+            let rule = task.repeat.as_deref().and_then(Recurrence::parse);
+            if let (Some(rule), false) = (rule, task.done) {
+                let mut next = Task::new(
+                    &task.name,
+                    task.category
+                        .clone()
+                        .unwrap_or_else(|| "General".to_string()),
+                    None,
+                    task.tags.clone(),
+                    task.priority,
+                );
+                let next_due = rule.next_due(task.due_date, today);
+                // Completing the task again after taking it back must not add a second instance
+                let pending: i64 = self
+                    .conn
+                    .query_row(
+                        "SELECT COUNT(*) FROM todos WHERE NOT done AND id <> ?1 AND task = ?2 \
+                         AND repeat = ?3 AND due_date = CAST(?4 AS DATE)",
+                        params![
+                            id,
+                            task.name,
+                            task.repeat,
+                            next_due.format("%Y-%m-%d").to_string()
+                        ],
+                        |row| row.get(0),
+                    )
+                    .map_err(TodoError::from)?;
+                if pending > 0 {
+                    continue;
+                }
+                next.due_date = Some(next_due);
+                // A start date keeps its distance from the due date
+                next.start_date = task.start_date.map(|start| match task.due_date {
+                    Some(due) => start + (next_due - due),
+                    None => rule.next_due(Some(start), today),
+                });
+                next.context = task.context.clone();
+                next.icon = task.icon.clone();
+                next.estimate = task.estimate;
+                next.assignee = task.assignee.clone();
+                next.attributes = task.attributes.clone();
+                next.created_by = task.created_by.clone();
+                next.parent_id = task.parent_id;
+                next.repeat = task.repeat.clone();
+                next.id = self.add_task(next.clone())?;
+                completion.next.push(next);
+            }
+        }
+        Ok(completion)
+    }
+
+    /// Marks the tasks done the way `done` does, journaled so one `undo`
+    /// takes it back: the next instances of recurring tasks get their
+    /// category's checklist and are journaled along with it.
+    pub fn complete_tasks(&self, ids: &[i32], by: Option<&str>) -> Result<Completion, TodoError> {
+        self.journal("done", ids)?;
+        let completion = self.mark_tasks_done(ids, by)?;
+        let mut next = Vec::new();
+        for task in &completion.next {
+            next.push(task.id);
+            next.extend(self.add_checklist(task.id)?);
+        }
+        self.journal_added(&next)?;
+        Ok(completion)
+    }
+
+    // Marks a done task pending again, journaled so `undo` can take it back
+    pub fn reopen_task(&self, id: i32) -> Result<(), TodoError> {
+        self.journal("update", &[id])?;
+        self.update_task(id, None, None, None, vec![], None, None, true)
+    }
+
+    pub fn set_start_date(&self, id: i32, date: Option<NaiveDate>) -> Result<bool, TodoError> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE todos SET start_date = CAST(?2 AS DATE) WHERE id = ?1",
+                params![id, date.map(|d| d.format("%Y-%m-%d").to_string())],
+            )
+            .map_err(TodoError::from)?;
+        Ok(changed > 0)
+    }
+
+    // Sets or clears one of the task's dates, as `audit dates --fix` does
+    pub fn set_date(
         &self,
         id: i32,
-        new_task: Option<String>,
-        new_due_date: Option<String>,
-        new_category: Option<String>,
-        new_tags: Vec<String>,
-        new_priority: Option<i32>,
-        mark_undone: bool,
-    ) -> Result<(), TodoError> {
-        let mut updates = vec![];
+        field: DateField,
+        date: Option<NaiveDate>,
+    ) -> Result<bool, TodoError> {
+        let changed = self
+            .conn
+            .execute(
+                &format!(
+                    "UPDATE todos SET {} = CAST(?2 AS DATE) WHERE id = ?1",
+                    field.column()
+                ),
+                params![id, date.map(|d| d.format("%Y-%m-%d").to_string())],
+            )
+            .map_err(TodoError::from)?;
+        Ok(changed > 0)
+    }
+
+    pub fn set_assignee(&self, id: i32, who: Option<&str>) -> Result<bool, TodoError> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE todos SET assignee = ?2 WHERE id = ?1",
+                params![id, who],
+            )
+            .map_err(TodoError::from)?;
+        Ok(changed > 0)
+    }
+
+    pub fn set_estimate(&self, id: i32, minutes: Option<i64>) -> Result<bool, TodoError> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE todos SET estimate = ?2 WHERE id = ?1",
+                params![id, minutes],
+            )
+            .map_err(TodoError::from)?;
+        Ok(changed > 0)
+    }
+
+    pub fn set_icon(&self, id: i32, icon: Option<&str>) -> Result<bool, TodoError> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE todos SET icon = ?2 WHERE id = ?1",
+                params![id, icon],
+            )
+            .map_err(TodoError::from)?;
+        Ok(changed > 0)
+    }
+
+    pub fn set_repeat(&self, id: i32, rule: Option<&str>) -> Result<bool, TodoError> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE todos SET repeat = ?2 WHERE id = ?1",
+                params![id, rule],
+            )
+            .map_err(TodoError::from)?;
+        Ok(changed > 0)
+    }
+
+    // Deletes the tasks along with their category and tag links, custom fields, comments, notes, annotations, tracked time, reminders, rule history and relations
+    pub fn delete_tasks(&self, ids: &[i32]) -> Result<Vec<i32>, TodoError> {
+        let deleted = self.remove_tasks(
+            ids,
+            &[
+                "todo_categories",
+                "todo_tags",
+                "task_attributes",
+                "comments",
+                "notes",
+                "annotations",
+                "time_entries",
+                "reminders",
+                "rule_runs",
+                "completion_groups",
+            ],
+            |_| Ok(()),
+        )?;
+        for id in &deleted {
+            self.conn
+                .execute(
+                    "DELETE FROM relations WHERE todo_id = ?1 OR related_id = ?1",
+                    [id],
+                )
+                .map_err(TodoError::from)?;
+        }
+        Ok(deleted)
+    }
+
+    /// Remembers the tasks as completed together, such as in one `done`
+    /// command or `interactive` session, so each is related to the others.
+    pub fn group_completed(&self, ids: &[i32]) -> Result<(), TodoError> {
+        if ids.len() < 2 {
+            return Ok(());
+        }
+        let group_id: i64 = self
+            .conn
+            .query_row("SELECT nextval('completion_group_seq')", [], |row| {
+                row.get(0)
+            })
+            .map_err(TodoError::from)?;
+        for id in ids {
+            self.conn
+                .execute(
+                    "INSERT INTO completion_groups (group_id, todo_id) VALUES (?1, ?2)",
+                    params![group_id, id],
+                )
+                .map_err(TodoError::from)?;
+        }
+        Ok(())
+    }
+
+    // Forgets the latest group the tasks were completed in, when undoing that
+    // completion; a group that also holds other tasks is older and stays
+    fn ungroup_completed(&self, ids: &[i32]) -> Result<(), TodoError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let listed = ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.conn
+            .execute(
+                &format!(
+                    "DELETE FROM completion_groups WHERE group_id = (\
+                         SELECT MAX(group_id) FROM completion_groups WHERE todo_id IN ({0})) \
+                     AND NOT EXISTS (SELECT 1 FROM completion_groups g \
+                         WHERE g.group_id = completion_groups.group_id AND g.todo_id NOT IN ({0}))",
+                    listed
+                ),
+                [],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    /// Relates every pair of the given tasks. Pairs that are already related
+    /// are left alone.
+    pub fn relate_tasks(&self, ids: &[i32]) -> Result<(), TodoError> {
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                if a != b {
+                    self.conn
+                        .execute(
+                            "INSERT OR IGNORE INTO relations (todo_id, related_id) VALUES (?1, ?2)",
+                            [a.min(b), a.max(b)],
+                        )
+                        .map_err(TodoError::from)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_related_tasks(&self, id: i32) -> Result<Vec<Task>, TodoError> {
+        self.tasks_where(&format!(
+            "id IN (SELECT related_id FROM relations WHERE todo_id = {0} \
+             UNION SELECT todo_id FROM relations WHERE related_id = {0} \
+             UNION SELECT todo_id FROM completion_groups WHERE todo_id <> {0} AND group_id IN \
+                 (SELECT group_id FROM completion_groups WHERE todo_id = {0})) ORDER BY id",
+            id
+        ))
+    }
+
+    /// Writes a copy of the database to `path` with everything personal
+    /// replaced: task names, annotations and custom field values become
+    /// placeholder text keeping any `#<id>` references, notes and comments
+    /// become placeholder text, categories, tags and templates get numbered
+    /// names, and contexts and people become "Context 12" or "Person 7".
+    /// IDs, dates, priorities, links and counts are kept, and the undo
+    /// journal and history, which hold copies of the old text, are left out.
+    pub fn anonymize_to(&self, path: &str) -> Result<(), TodoError> {
+        if std::path::Path::new(path).exists() {
+            return Err(TodoError::Custom(format!(
+                "{} already exists. Please choose a new file for the copy.",
+                path
+            )));
+        }
+        let quoted = path.replace('\'', "''");
+        self.conn
+            .execute_batch(&format!(
+                "ATTACH '{0}' AS demo; COPY FROM DATABASE {1} TO demo;",
+                quoted,
+                self.database_name()?
+            ))
+            .map_err(TodoError::from)?;
+
+        let phrase = |key: &str| placeholder_sql(key);
+        // Placeholder text followed by the `#<id>` references the original had
+        let scrubbed = |column: &str, key: &str| {
+            format!(
+                "{1} || COALESCE(' ' || NULLIF(array_to_string(\
+                     regexp_extract_all({0}, '#[0-9]+\\b'), ' '), ''), '')",
+                column,
+                phrase(key)
+            )
+        };
+        let numbered = |column: &str, label: &str| {
+            format!(
+                "CASE WHEN {0} IS NULL THEN NULL ELSE '{1} ' || (hash({0}) % 1000) END",
+                column, label
+            )
+        };
+        let mut statements = vec![
+            "CREATE TEMP TABLE category_map AS SELECT name AS old, \
+                 CASE WHEN name = 'General' THEN name \
+                 ELSE 'Category ' || row_number() OVER (ORDER BY id) END AS new \
+             FROM demo.categories"
+                .to_string(),
+            "CREATE TEMP TABLE tag_map AS SELECT name AS old, \
+                 'tag' || row_number() OVER (ORDER BY id) AS new FROM demo.tags"
+                .to_string(),
+            // DuckDB won't update rows that foreign keys point at, so the links step aside
+            "CREATE TEMP TABLE category_links AS SELECT * FROM demo.todo_categories".to_string(),
+            "CREATE TEMP TABLE tag_links AS SELECT * FROM demo.todo_tags".to_string(),
+            "DELETE FROM demo.todo_categories".to_string(),
+            "DELETE FROM demo.todo_tags".to_string(),
+            "UPDATE demo.categories c SET name = m.new FROM category_map m WHERE c.name = m.old"
+                .to_string(),
+            "UPDATE demo.tags t SET name = m.new FROM tag_map m WHERE t.name = m.old".to_string(),
+            "INSERT INTO demo.todo_categories SELECT * FROM category_links".to_string(),
+            "INSERT INTO demo.todo_tags SELECT * FROM tag_links".to_string(),
+            "UPDATE demo.goals g SET name = m.new FROM category_map m \
+             WHERE g.kind = 'category' AND g.name = m.old"
+                .to_string(),
+            "UPDATE demo.goals g SET name = m.new FROM tag_map m \
+             WHERE g.kind = 'tag' AND g.name = m.old"
+                .to_string(),
+            format!(
+                "UPDATE demo.templates SET name = 'template' || (hash(name) % 1000), pattern = {}",
+                phrase("hash(pattern)")
+            ),
+        ];
+        for table in ["todos", "archive", "trash"] {
+            statements.push(format!(
+                "UPDATE demo.{0} SET task = {1}, \
+                 context = {2}, waiting_for = {3}, created_by = {4}, completed_by = {5}, \
+                 assignee = {6}",
+                table,
+                scrubbed("task", "id"),
+                numbered("context", "Context"),
+                numbered("waiting_for", "Person"),
+                numbered("created_by", "Person"),
+                numbered("completed_by", "Person"),
+                numbered("assignee", "Person")
+            ));
+        }
+        for table in ["archive", "trash", "templates"] {
+            statements.push(format!(
+                "UPDATE demo.{0} SET category = COALESCE(\
+                     (SELECT m.new FROM category_map m WHERE m.old = {0}.category), \
+                     CASE WHEN category IS NULL THEN NULL ELSE 'Category' END), \
+                 tags = (SELECT string_agg(COALESCE(m.new, 'tag'), ',') \
+                     FROM unnest(string_split({0}.tags, ',')) AS u(name) \
+                     LEFT JOIN tag_map m ON m.old = u.name WHERE u.name <> '')",
+                table
+            ));
+        }
+        statements.extend([
+            format!("UPDATE demo.notes SET body = {} || '.'", phrase("id")),
+            format!(
+                "UPDATE demo.annotations SET body = {}",
+                scrubbed("body", "hash(body)")
+            ),
+            format!(
+                "UPDATE demo.task_attributes SET value = {}",
+                scrubbed("value", "hash(value)")
+            ),
+            format!(
+                "UPDATE demo.comments SET body = {} || '.', author = {}",
+                phrase("hash(body)"),
+                numbered("author", "Person")
+            ),
+            "UPDATE demo.state SET value = NULL WHERE key = 'active_context'".to_string(),
+            "UPDATE demo.export_runs SET detail = NULL".to_string(),
+            "DELETE FROM demo.journal".to_string(),
+            "DELETE FROM demo.history".to_string(),
+            "DELETE FROM demo.operations".to_string(),
+        ]);
+
+        let result = statements
+            .iter()
+            .try_for_each(|statement| self.conn.execute(statement, []).map(|_| ()));
+        for table in ["category_map", "tag_map", "category_links", "tag_links"] {
+            self.conn
+                .execute(&format!("DROP TABLE IF EXISTS {}", table), [])
+                .map_err(TodoError::from)?;
+        }
+        self.conn
+            .execute("DETACH demo", [])
+            .map_err(TodoError::from)?;
+        // A copy that was only partly anonymized must not be left behind
+        if result.is_err() {
+            let _ = std::fs::remove_file(path);
+            let _ = std::fs::remove_file(format!("{}.wal", path));
+        }
+        result.map_err(TodoError::from)
+    }
+
+    // The catalog name DuckDB gave the open database, taken from its file name
+    fn database_name(&self) -> Result<String, TodoError> {
+        self.conn
+            .query_row("SELECT current_database()", [], |row| row.get(0))
+            .map_err(TodoError::from)
+    }
+
+    // Time tracking
+
+    // Starts timing a task; returns false when no task has the given ID
+    pub fn start_timer(&self, todo_id: i32) -> Result<bool, TodoError> {
+        let started = self
+            .conn
+            .execute(
+                "INSERT INTO time_entries (todo_id, started_at) \
+                 SELECT id, CAST(?2 AS TIMESTAMP) FROM todos WHERE id = ?1",
+                params![todo_id, now_timestamp()],
+            )
+            .map_err(TodoError::from)?;
+        Ok(started > 0)
+    }
+
+    // Stops the running timer, returning its task and how many minutes it ran
+    pub fn stop_timer(&self) -> Result<Option<(i32, i64)>, TodoError> {
+        let running = self.running_timer()?;
+        if running.is_some() {
+            self.conn
+                .execute(
+                    "UPDATE time_entries SET ended_at = CAST(?1 AS TIMESTAMP) WHERE ended_at IS NULL",
+                    [now_timestamp()],
+                )
+                .map_err(TodoError::from)?;
+        }
+        Ok(running)
+    }
+
+    // The task the timer is running on, with the minutes it has run so far
+    pub fn running_timer(&self) -> Result<Option<(i32, i64)>, TodoError> {
+        self.conn
+            .query_row(
+                "SELECT todo_id, CAST(round((epoch(CAST(?1 AS TIMESTAMP)) - epoch(started_at)) / 60) AS BIGINT) \
+                 FROM time_entries WHERE ended_at IS NULL",
+                [now_timestamp()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(TodoError::from)
+    }
+
+    // Records time spent on a task as ending now, marked as a pomodoro when it was one;
+    // returns false when no task has the given ID
+    pub fn log_time(&self, todo_id: i32, minutes: i64, pomodoro: bool) -> Result<bool, TodoError> {
+        let ended_at = Local::now().naive_local();
+        let started_at = Duration::try_minutes(minutes)
+            .and_then(|length| ended_at.checked_sub_signed(length))
+            .ok_or_else(|| TodoError::Custom(format!("{} minutes is too long to log.", minutes)))?;
+        let logged = self
+            .conn
+            .execute(
+                "INSERT INTO time_entries (todo_id, started_at, ended_at, pomodoro) \
+                 SELECT id, CAST(?2 AS TIMESTAMP), CAST(?3 AS TIMESTAMP), ?4 FROM todos WHERE id = ?1",
+                params![
+                    todo_id,
+                    started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    ended_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    pomodoro
+                ],
+            )
+            .map_err(TodoError::from)?;
+        Ok(logged > 0)
+    }
+
+    // All the minutes tracked on a task, counting a timer still running on it
+    pub fn tracked_minutes(&self, todo_id: i32) -> Result<i64, TodoError> {
+        self.conn
+            .query_row(
+                "SELECT CAST(COALESCE(round(SUM(epoch(COALESCE(ended_at, CAST(?2 AS TIMESTAMP))) \
+                 - epoch(started_at)) / 60), 0) AS BIGINT) FROM time_entries WHERE todo_id = ?1",
+                params![todo_id, now_timestamp()],
+                |row| row.get(0),
+            )
+            .map_err(TodoError::from)
+    }
+
+    // Estimated tasks that have time tracked on them, including archived tasks
+    pub fn estimate_accuracy(&self) -> Result<Vec<EstimateAccuracy>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "WITH estimated AS (
+                    SELECT id, task, done, estimate FROM todos WHERE estimate IS NOT NULL
+                    UNION ALL
+                    SELECT id, task, done, estimate FROM archive WHERE estimate IS NOT NULL
+                 ),
+                 tracked AS (
+                    SELECT todo_id, round(SUM(epoch(COALESCE(ended_at, CAST(?1 AS TIMESTAMP))) - epoch(started_at)) / 60) AS minutes
+                    FROM time_entries GROUP BY todo_id
+                 )
+                 SELECT e.id, e.task, e.done, e.estimate, CAST(t.minutes AS BIGINT)
+                 FROM estimated e JOIN tracked t ON t.todo_id = e.id
+                 WHERE t.minutes > 0
+                 ORDER BY e.id",
+            )
+            .map_err(TodoError::from)?;
+        let rows = stmt
+            .query_map([now_timestamp()], |row| {
+                Ok(EstimateAccuracy {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    done: row.get(2)?,
+                    estimate: row.get(3)?,
+                    tracked: row.get(4)?,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(rows)
+    }
+
+    // Tasks with finished pomodoros, most first, including archived tasks
+    pub fn pomodoro_counts(&self) -> Result<Vec<PomodoroCount>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT e.todo_id, COALESCE(t.task, a.task, ''), COUNT(*),
+                        CAST(round(SUM(epoch(e.ended_at) - epoch(e.started_at)) / 60) AS BIGINT)
+                 FROM time_entries e
+                 LEFT JOIN todos t ON t.id = e.todo_id
+                 LEFT JOIN archive a ON a.id = e.todo_id
+                 WHERE e.pomodoro
+                 GROUP BY ALL
+                 ORDER BY COUNT(*) DESC, e.todo_id",
+            )
+            .map_err(TodoError::from)?;
+        let counts = stmt
+            .query_map([], |row| {
+                Ok(PomodoroCount {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    pomodoros: row.get(2)?,
+                    minutes: row.get(3)?,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(counts)
+    }
+
+    pub fn add_reminder(&self, id: i32, days_before: i64) -> Result<(), TodoError> {
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO reminders (todo_id, days_before) VALUES (?1, ?2)",
+                params![id, days_before],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    // Removes every reminder of a task, returning how many there were
+    pub fn clear_reminders(&self, id: i32) -> Result<usize, TodoError> {
+        self.conn
+            .execute("DELETE FROM reminders WHERE todo_id = ?1", [id])
+            .map_err(TodoError::from)
+    }
+
+    // A task's reminders as days before its due date, furthest ahead first
+    pub fn get_reminders(&self, id: i32) -> Result<Vec<i64>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT days_before FROM reminders WHERE todo_id = ?1 ORDER BY days_before DESC",
+            )
+            .map_err(TodoError::from)?;
+        let days = stmt
+            .query_map([id], |row| row.get(0))
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(days)
+    }
+
+    // Pending tasks with a reminder falling on `day`, soonest due first
+    pub fn reminders_on(&self, day: NaiveDate) -> Result<Vec<Task>, TodoError> {
+        self.tasks_where(&format!(
+            "done = false AND id IN (SELECT todo_id FROM reminders \
+             WHERE todos.due_date - days_before = DATE '{}') ORDER BY due_date, id",
+            day.format("%Y-%m-%d")
+        ))
+    }
+
+    // Every pair of related tasks, lower ID first
+    pub fn get_relations(&self) -> Result<Vec<(i32, i32)>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                // Each task of a completion group is joined to the next one in it
+                "SELECT todo_id, related_id FROM relations \
+                 UNION SELECT a.todo_id, MIN(b.todo_id) FROM completion_groups a \
+                     JOIN completion_groups b ON b.group_id = a.group_id AND b.todo_id > a.todo_id \
+                     GROUP BY a.group_id, a.todo_id \
+                 ORDER BY 1, 2",
+            )
+            .map_err(TodoError::from)?;
+        let pairs = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(pairs)
+    }
+
+    /// Moves completed tasks, finished before `before` if given, into the
+    /// archive table with their category and tags. Comments stay where they
+    /// are, since archived tasks keep their IDs. Returns how many were moved.
+    pub fn archive_tasks(&self, before: Option<NaiveDate>) -> Result<usize, TodoError> {
+        let tasks = self.tasks_where(&archivable_condition(before))?;
+        // Undoing an older change to an archived task would bring back a second copy
+        let moved = self.shelve_tasks(
+            "archive",
+            "archived_at",
+            &tasks,
+            &["todo_categories", "todo_tags", "rule_runs", "journal"],
+        )?;
+        Ok(moved.len())
+    }
+
+    // The tasks completed before `before` that are still in the main list
+    pub fn done_before(&self, before: NaiveDate) -> Result<Vec<Task>, TodoError> {
+        self.tasks_where(&archivable_condition(Some(before)))
+    }
+
+    // How many tasks `archive_tasks` would move for the same `before`
+    pub fn count_archivable(&self, before: Option<NaiveDate>) -> Result<usize, TodoError> {
+        Ok(self.tasks_where(&archivable_condition(before))?.len())
+    }
+
+    // Archived tasks, most recently completed first
+    pub fn get_archived_tasks(&self) -> Result<Vec<Task>, TodoError> {
+        self.shelved_tasks("archive", "completion_date DESC, id")
+    }
+
+    /// Moves the tasks to the trash with their category and tags, returning
+    /// the IDs that existed. Comments and the undo journal are kept so the
+    /// tasks can come back intact.
+    pub fn trash_tasks(&self, ids: &[i32]) -> Result<Vec<i32>, TodoError> {
+        let mut tasks = Vec::new();
+        for &id in ids {
+            tasks.extend(self.get_task(id)?);
+        }
+        self.shelve_tasks(
+            "trash",
+            "deleted_at",
+            &tasks,
+            &["todo_categories", "todo_tags", "rule_runs"],
+        )
+    }
+
+    // Trashed tasks, most recently deleted first
+    pub fn get_trashed_tasks(&self) -> Result<Vec<Task>, TodoError> {
+        self.shelved_tasks("trash", "deleted_at DESC, id")
+    }
+
+    // Moves trashed tasks back into the task list; returns the IDs that were in the trash
+    pub fn restore_from_trash(&self, ids: &[i32]) -> Result<Vec<i32>, TodoError> {
+        let trashed = self.get_trashed_tasks()?;
+        let mut restored = Vec::new();
+        for task in trashed.into_iter().filter(|t| ids.contains(&t.id)) {
+            restored.push(task.id);
+            let snapshot = self.snapshot(task)?;
+            self.restore_task(snapshot)?;
+        }
+        Ok(restored)
+    }
+
+    // Permanently deletes everything in the trash, with the comments, notes and tracked time; returns how many tasks went
+    pub fn empty_trash(&self) -> Result<usize, TodoError> {
+        self.purge_shelved("trash", None, false)
+    }
+
+    /// Permanently deletes the tasks put in `table` (archive or trash)
+    /// before `before`, or all of them, along with their custom fields,
+    /// comments, notes, annotations, tracked time, reminders and relations.
+    /// With `dry_run` nothing is deleted. Returns how many tasks were, or would be, deleted.
+    pub fn purge_shelved(
+        &self,
+        table: &str,
+        before: Option<NaiveDate>,
+        dry_run: bool,
+    ) -> Result<usize, TodoError> {
+        let stamp_column = if table == "archive" {
+            "archived_at"
+        } else {
+            "deleted_at"
+        };
+        let condition = match before {
+            Some(date) => format!("{} < DATE '{}'", stamp_column, date.format("%Y-%m-%d")),
+            None => "true".to_string(),
+        };
+        let shelved = format!("SELECT id FROM {} WHERE {}", table, condition);
+        if dry_run {
+            let count: i64 = self
+                .conn
+                .query_row(&format!("SELECT COUNT(*) FROM ({})", shelved), [], |row| {
+                    row.get(0)
+                })
+                .map_err(TodoError::from)?;
+            return Ok(count as usize);
+        }
+
+        for link_table in [
+            "task_attributes",
+            "comments",
+            "notes",
+            "annotations",
+            "time_entries",
+            "reminders",
+            "completion_groups",
+        ] {
+            self.conn
+                .execute(
+                    &format!("DELETE FROM {} WHERE todo_id IN ({})", link_table, shelved),
+                    [],
+                )
+                .map_err(TodoError::from)?;
+        }
+        self.conn
+            .execute(
+                &format!(
+                    "DELETE FROM relations WHERE todo_id IN ({0}) OR related_id IN ({0})",
+                    shelved
+                ),
+                [],
+            )
+            .map_err(TodoError::from)?;
+        let purged = self
+            .conn
+            .execute(&format!("DELETE FROM {} WHERE {}", table, condition), [])
+            .map_err(TodoError::from)?;
+        Ok(purged)
+    }
+
+    // Copies the tasks into `table` (archive or trash), stamped with today's date, and removes them
+    fn shelve_tasks(
+        &self,
+        table: &str,
+        stamp_column: &str,
+        tasks: &[Task],
+        link_tables: &[&str],
+    ) -> Result<Vec<i32>, TodoError> {
+        let ids: Vec<i32> = tasks.iter().map(|t| t.id).collect();
+        self.remove_tasks(&ids, link_tables, |conn| {
+            for task in tasks {
+                conn.execute(
+                    &format!(
+                        "INSERT INTO {} ({}, category, tags, {}) \
+                         SELECT {}, ?2, ?3, CAST(?4 AS DATE) FROM todos WHERE id = ?1",
+                        table, TASK_COLUMNS, stamp_column, TASK_COLUMNS
+                    ),
+                    params![
+                        task.id,
+                        task.category,
+                        task.tags.join(","),
+                        Local::now().format("%Y-%m-%d").to_string()
+                    ],
+                )
+                .map_err(TodoError::from)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn shelved_tasks(&self, table: &str, order: &str) -> Result<Vec<Task>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {}, category, tags FROM {} ORDER BY {}",
+                TASK_COLUMNS, table, order
+            ))
+            .map_err(TodoError::from)?;
+        let mut tasks = stmt
+            .query_map([], |row| {
+                let mut task = task_from_row(row)?;
+                task.category = row.get(20)?;
+                task.tags = row
+                    .get::<_, Option<String>>(21)?
+                    .map(|tags| {
+                        tags.split(',')
+                            .filter(|t| !t.is_empty())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(task)
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        // Custom fields stay in place while a task is shelved
+        for task in &mut tasks {
+            task.attributes = self.get_task_attributes(task.id)?;
+        }
+        Ok(tasks)
+    }
+
+    /// Removes the tasks' rows in `link_tables` and then the tasks, returning
+    /// the IDs that existed. `first` runs in the same transaction as the
+    /// link deletes, so work like copying the tasks elsewhere commits with
+    /// them. DuckDB checks foreign keys against committed data, so the links
+    /// are removed and committed in one transaction before the tasks go in
+    /// a second one.
+    fn remove_tasks(
+        &self,
+        ids: &[i32],
+        link_tables: &[&str],
+        first: impl FnOnce(&Connection) -> Result<(), TodoError>,
+    ) -> Result<Vec<i32>, TodoError> {
+        let tx = self.conn.unchecked_transaction().map_err(TodoError::from)?;
+        first(&tx)?;
+        for id in ids {
+            for table in link_tables {
+                tx.execute(&format!("DELETE FROM {} WHERE todo_id = ?1", table), [id])
+                    .map_err(TodoError::from)?;
+            }
+        }
+        tx.commit().map_err(TodoError::from)?;
+
+        let tx = self.conn.unchecked_transaction().map_err(TodoError::from)?;
+        let mut deleted = Vec::new();
+        for id in ids {
+            if tx
+                .execute("DELETE FROM todos WHERE id = ?1", [id])
+                .map_err(TodoError::from)?
+                > 0
+            {
+                deleted.push(*id);
+            }
+        }
+        tx.commit().map_err(TodoError::from)?;
+        Ok(deleted)
+    }
+
+    // Returns false when no task has the given ID
+    pub fn set_pinned(&self, id: i32, pinned: bool) -> Result<bool, TodoError> {
+        let sql = "UPDATE todos SET pinned = ?1 WHERE id = ?2";
+        let updated = self
+            .conn
+            .execute(sql, params![pinned, id])
+            .map_err(TodoError::from)?;
+        Ok(updated > 0)
+    }
+
+    pub fn count_pinned(&self) -> Result<usize, TodoError> {
+        let count: i64 = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM todos WHERE pinned AND done = 0",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(TodoError::from)?;
+        Ok(count as usize)
+    }
+
+    // Returns false when no task has the given ID
+    pub fn set_waiting(
+        &self,
+        id: i32,
+        waiting_for: Option<&str>,
+        follow_up: Option<NaiveDate>,
+    ) -> Result<bool, TodoError> {
+        let sql = "UPDATE todos SET waiting_for = ?1, follow_up = ?2 WHERE id = ?3";
+        let follow_up_str = follow_up.map(|d| d.format("%Y-%m-%d").to_string());
+        let updated = self
+            .conn
+            .execute(sql, params![waiting_for, follow_up_str, id])
+            .map_err(TodoError::from)?;
+        Ok(updated > 0)
+    }
+
+    pub fn add_task_tag(&self, id: i32, tag: &str) -> Result<(), TodoError> {
+        self.add_tag(tag)?;
+        let tag_id = self.get_tag_id(tag)?;
+        self.conn
+            .execute(
+                "INSERT INTO todo_tags (todo_id, tag_id) SELECT ?1, ?2 WHERE NOT EXISTS \
+                 (SELECT 1 FROM todo_tags WHERE todo_id = ?1 AND tag_id = ?2)",
+                [&id, &tag_id],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    pub fn remove_task_tag(&self, id: i32, tag: &str) -> Result<(), TodoError> {
+        self.conn
+            .execute(
+                "DELETE FROM todo_tags WHERE todo_id = ?1 AND tag_id IN (SELECT id FROM tags WHERE name = ?2)",
+                params![id, tag],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    /// Adds `text` to the end of the task's name, or to the start with
+    /// `prepend`, separated by a space. Returns false when no task has the
+    /// given ID.
+    pub fn extend_task_name(&self, id: i32, text: &str, prepend: bool) -> Result<bool, TodoError> {
+        self.check_references(text)?;
+        let sql = if prepend {
+            "UPDATE todos SET task = ?1 || ' ' || task WHERE id = ?2"
+        } else {
+            "UPDATE todos SET task = task || ' ' || ?1 WHERE id = ?2"
+        };
+        let updated = self
+            .conn
+            .execute(sql, params![text, id])
+            .map_err(TodoError::from)?;
+        Ok(updated > 0)
+    }
+
+    // Clears the task's due date and context, which `update_task` can only set
+    pub fn clear_due_date_and_context(
+        &self,
+        id: i32,
+        due_date: bool,
+        context: bool,
+    ) -> Result<(), TodoError> {
+        let mut columns = vec![];
+        if due_date {
+            columns.push("due_date = NULL");
+        }
+        if context {
+            columns.push("context = NULL");
+        }
+        if !columns.is_empty() {
+            let sql = format!("UPDATE todos SET {} WHERE id = ?1", columns.join(", "));
+            self.conn.execute(&sql, [id]).map_err(TodoError::from)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_scheduled_date(&self, id: i32, date: NaiveDate) -> Result<(), TodoError> {
+        let sql = "UPDATE todos SET scheduled_date = ?1 WHERE id = ?2";
+        self.conn
+            .execute(sql, params![date.format("%Y-%m-%d").to_string(), id])
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_task(
+        &self,
+        id: i32,
+        new_task: Option<String>,
+        new_due_date: Option<String>,
+        new_category: Option<String>,
+        new_tags: Vec<String>,
+        new_priority: Option<i32>,
+        new_context: Option<String>,
+        mark_undone: bool,
+    ) -> Result<(), TodoError> {
+        let mut updates = vec![];
+        // Bound after the ID, so the first value set is ?2
+        let mut values: Vec<Box<dyn ToSql>> = vec![Box::new(id)];
+
+        if let Some(task) = new_task {
+            self.check_references(&task)?;
+            values.push(Box::new(task));
+            updates.push(format!("task = ?{}", values.len()));
+        }
+        if let Some(due_date) = new_due_date {
+            values.push(Box::new(due_date));
+            updates.push(format!("due_date = ?{}", values.len()));
+        }
+        if let Some(priority) = new_priority {
+            values.push(Box::new(priority));
+            updates.push(format!("priority = ?{}", values.len()));
+        }
+        if let Some(context) = new_context {
+            values.push(Box::new(context));
+            updates.push(format!("context = ?{}", values.len()));
+        }
+        if mark_undone {
+            updates.push("done = 0".to_string());
+            updates.push("completion_date = NULL".to_string());
+            updates.push("completed_by = NULL".to_string());
+        }
+
+        if !updates.is_empty() {
+            let sql = format!("UPDATE todos SET {} WHERE id = ?1", updates.join(", "));
+            self.conn
+                .execute(&sql, params_from_iter(values.iter()))
+                .map_err(TodoError::from)?;
+        }
+
+        if let Some(category) = new_category {
+            self.add_category(&category)?;
+            let category_id = self.get_category_id(&category)?;
+            self.conn
+                .execute("DELETE FROM todo_categories WHERE todo_id = ?1", [&id])
+                .map_err(TodoError::from)?;
+            self.conn
+                .execute(
+                    "INSERT INTO todo_categories (todo_id, category_id) VALUES (?1, ?2)",
+                    [&id, &category_id],
+                )
+                .map_err(TodoError::from)?;
+        }
+
+        if !new_tags.is_empty() {
+            self.conn
+                .execute("DELETE FROM todo_tags WHERE todo_id = ?1", [&id])
+                .map_err(TodoError::from)?;
+
+            // Split tags by comma and trim them
+            let tags_list: Vec<&str> = new_tags
+                .iter()
+                .flat_map(|t| t.split(',').map(|s| s.trim()))
+                .collect();
+
+            for tag in tags_list {
+                self.add_tag(tag)?;
+                let tag_id = self.get_tag_id(tag)?;
+                self.conn
+                    .execute(
+                        "INSERT INTO todo_tags (todo_id, tag_id) VALUES (?1, ?2)",
+                        [&id, &tag_id],
+                    )
+                    .map_err(TodoError::from)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn add_goal(
+        &self,
+        kind: &str,
+        name: &str,
+        target: i64,
+        period: &str,
+    ) -> Result<i32, TodoError> {
+        let id: i32 = self
+            .conn
+            .query_row(
+                "INSERT INTO goals (kind, name, target, period) VALUES (?1, ?2, ?3, ?4) RETURNING id",
+                params![kind, name, target, period],
+                |row| row.get(0),
+            )
+            .map_err(TodoError::from)?;
+        Ok(id)
+    }
+
+    // Returns false when no goal has the given ID
+    pub fn delete_goal(&self, id: i32) -> Result<bool, TodoError> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM goals WHERE id = ?1", [id])
+            .map_err(TodoError::from)?;
+        Ok(deleted > 0)
+    }
+
+    pub fn list_goals(&self) -> Result<Vec<Goal>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, kind, name, target, period FROM goals ORDER BY id")
+            .map_err(TodoError::from)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Goal {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    name: row.get(2)?,
+                    target: row.get(3)?,
+                    period: row.get(4)?,
+                })
+            })
+            .map_err(TodoError::from)?;
+        let mut goals = Vec::new();
+        for row in rows {
+            goals.push(row.map_err(TodoError::from)?);
+        }
+        Ok(goals)
+    }
+
+    // Saves a template, replacing any template of the same name
+    pub fn save_template(&self, template: &TaskTemplate) -> Result<(), TodoError> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO templates (name, pattern, category, tags, priority, due_in) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    template.name,
+                    template.pattern,
+                    template.category,
+                    template.tags.join(","),
+                    template.priority,
+                    template.due_in
+                ],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    // Returns false when no template has the given name
+    pub fn delete_template(&self, name: &str) -> Result<bool, TodoError> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM templates WHERE name = ?1", [name])
+            .map_err(TodoError::from)?;
+        Ok(deleted > 0)
+    }
+
+    pub fn get_template(&self, name: &str) -> Result<Option<TaskTemplate>, TodoError> {
+        Ok(self
+            .list_templates()?
+            .into_iter()
+            .find(|template| template.name == name))
+    }
+
+    pub fn list_templates(&self) -> Result<Vec<TaskTemplate>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, pattern, category, tags, priority, due_in FROM templates ORDER BY name")
+            .map_err(TodoError::from)?;
+        let rows = stmt
+            .query_map([], |row| {
+                let tags: Option<String> = row.get(3)?;
+                Ok(TaskTemplate {
+                    name: row.get(0)?,
+                    pattern: row.get(1)?,
+                    category: row.get(2)?,
+                    tags: tags
+                        .unwrap_or_default()
+                        .split(',')
+                        .filter(|tag| !tag.is_empty())
+                        .map(String::from)
+                        .collect(),
+                    priority: row.get(4)?,
+                    due_in: row.get(5)?,
+                })
+            })
+            .map_err(TodoError::from)?;
+        let mut templates = Vec::new();
+        for row in rows {
+            templates.push(row.map_err(TodoError::from)?);
+        }
+        Ok(templates)
+    }
+
+    // Counts tasks counting towards a goal that were completed between two dates, inclusive
+    pub fn count_completed(
+        &self,
+        goal: &Goal,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<i64, TodoError> {
+        let sql = match goal.kind.as_str() {
+            "tag" => {
+                "SELECT COUNT(DISTINCT t.id) FROM todos t
+                 JOIN todo_tags tt ON t.id = tt.todo_id
+                 JOIN tags g ON g.id = tt.tag_id
+                 WHERE g.name = ?1 AND t.done = 1
+                 AND t.completion_date BETWEEN CAST(?2 AS DATE) AND CAST(?3 AS DATE)"
+            }
+            _ => {
+                "SELECT COUNT(DISTINCT t.id) FROM todos t
+                 JOIN todo_categories tc ON t.id = tc.todo_id
+                 JOIN categories c ON c.id = tc.category_id
+                 WHERE c.name = ?1 AND t.done = 1
+                 AND t.completion_date BETWEEN CAST(?2 AS DATE) AND CAST(?3 AS DATE)"
+            }
+        };
+        let count: i64 = self
+            .conn
+            .query_row(
+                sql,
+                params![
+                    goal.name,
+                    start.format("%Y-%m-%d").to_string(),
+                    end.format("%Y-%m-%d").to_string()
+                ],
+                |row| row.get(0),
+            )
+            .map_err(TodoError::from)?;
+        Ok(count)
+    }
+
+    // Small key/value store for state that outlives a single invocation
+    pub fn get_state(&self, key: &str) -> Result<Option<String>, TodoError> {
+        let value = self
+            .conn
+            .query_row("SELECT value FROM state WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(TodoError::from)?;
+        Ok(value)
+    }
+
+    pub fn set_state(&self, key: &str, value: Option<&str>) -> Result<(), TodoError> {
+        match value {
+            Some(value) => self
+                .conn
+                .execute(
+                    "INSERT OR REPLACE INTO state (key, value) VALUES (?1, ?2)",
+                    [key, value],
+                )
+                .map_err(TodoError::from)?,
+            None => self
+                .conn
+                .execute("DELETE FROM state WHERE key = ?1", [key])
+                .map_err(TodoError::from)?,
+        };
+        Ok(())
+    }
+
+    // IDs of the tasks a rule has already changed
+    pub fn rule_applied_to(&self, rule: &str) -> Result<Vec<i32>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT todo_id FROM rule_runs WHERE rule = ?1")
+            .map_err(TodoError::from)?;
+        let ids = stmt
+            .query_map([rule], |row| row.get(0))
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<i32>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(ids)
+    }
+
+    pub fn record_rule_run(&self, rule: &str, id: i32, date: NaiveDate) -> Result<(), TodoError> {
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO rule_runs (rule, todo_id, applied_on) VALUES (?1, ?2, CAST(?3 AS DATE))",
+                params![rule, id, date.format("%Y-%m-%d").to_string()],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    pub fn record_export_run(&self, preset: &str, ok: bool, detail: &str) -> Result<(), TodoError> {
+        let ran_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn
+            .execute(
+                "INSERT INTO export_runs (preset, ran_at, ok, detail) \
+                 VALUES (?1, CAST(?2 AS TIMESTAMP), ?3, ?4)",
+                params![preset, ran_at, ok, detail],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    // The most recent run of every preset that has been exported
+    pub fn last_export_runs(&self) -> Result<Vec<ExportRun>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT preset, strftime(ran_at, '%Y-%m-%d %H:%M'), ok, detail FROM export_runs \
+                 QUALIFY row_number() OVER (PARTITION BY preset ORDER BY ran_at DESC) = 1 \
+                 ORDER BY preset",
+            )
+            .map_err(TodoError::from)?;
+        let runs = stmt
+            .query_map([], |row| {
+                Ok(ExportRun {
+                    preset: row.get(0)?,
+                    ran_at: row.get(1)?,
+                    ok: row.get(2)?,
+                    detail: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(runs)
+    }
+
+    /// Records a command line (without the program name) before it runs,
+    /// returning its ID for `finish_operation`. Only the latest
+    /// `OPERATION_LIMIT` are kept.
+    pub fn begin_operation(&self, args: &[String]) -> Result<i64, TodoError> {
+        let args = serde_json::to_string(args)
+            .map_err(|e| TodoError::Custom(format!("Could not record the command: {}", e)))?;
+        let started_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let id: i64 = self
+            .conn
+            .query_row(
+                "INSERT INTO operations (args, started_at, status) \
+                 VALUES (?1, CAST(?2 AS TIMESTAMP), 'running') RETURNING id",
+                params![args, started_at],
+                |row| row.get(0),
+            )
+            .map_err(TodoError::from)?;
+        self.conn
+            .execute(
+                "DELETE FROM operations WHERE id <= ?1",
+                [id - OPERATION_LIMIT],
+            )
+            .map_err(TodoError::from)?;
+        Ok(id)
+    }
+
+    pub fn finish_operation(&self, id: i64, status: &str) -> Result<(), TodoError> {
+        self.conn
+            .execute(
+                "UPDATE operations SET status = ?1 WHERE id = ?2",
+                params![status, id],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    // The latest recorded command lines, newest first
+    pub fn recent_operations(&self, limit: usize) -> Result<Vec<Operation>, TodoError> {
+        self.query_operations("ORDER BY id DESC LIMIT ?1", [limit as i64])
+    }
+
+    pub fn get_operation(&self, id: i64) -> Result<Option<Operation>, TodoError> {
+        Ok(self.query_operations("WHERE id = ?1", [id])?.pop())
+    }
+
+    // The most recent command line that started but never finished
+    pub fn last_interrupted_operation(&self) -> Result<Option<Operation>, TodoError> {
+        Ok(self
+            .query_operations("WHERE status = 'running' ORDER BY id DESC LIMIT 1", [])?
+            .pop())
+    }
+
+    fn query_operations<P: duckdb::Params>(
+        &self,
+        clause: &str,
+        params: P,
+    ) -> Result<Vec<Operation>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT id, args, strftime(started_at, '%Y-%m-%d %H:%M:%S'), status \
+                 FROM operations {}",
+                clause
+            ))
+            .map_err(TodoError::from)?;
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+
+        rows.into_iter()
+            .map(|(id, args, started_at, status)| {
+                let args = serde_json::from_str(&args).map_err(|e| {
+                    TodoError::Custom(format!("Could not read operation {}: {}", id, e))
+                })?;
+                Ok(Operation {
+                    id,
+                    args,
+                    started_at,
+                    status,
+                })
+            })
+            .collect()
+    }
+
+    /// Runs `f` in one transaction, so the changes it makes are all kept when
+    /// it succeeds and all rolled back when it fails. `f` can't start a
+    /// transaction of its own, as deleting tasks does.
+    pub fn in_transaction<T>(
+        &self,
+        f: impl FnOnce() -> Result<T, TodoError>,
+    ) -> Result<T, TodoError> {
+        let tx = self.conn.unchecked_transaction().map_err(TodoError::from)?;
+        let value = f()?;
+        tx.commit().map_err(TodoError::from)?;
+        Ok(value)
+    }
+
+    /// Records one operation for `undo` and in the history. Call it after
+    /// `add` with the new IDs, and before `done`, `update` or `delete` so the
+    /// journal keeps the tasks as they were; IDs of tasks that don't exist
+    /// are skipped.
+    pub fn journal(&self, action: &str, ids: &[i32]) -> Result<(), TodoError> {
+        let op_id: i64 = self
+            .conn
+            .query_row(
+                "SELECT COALESCE(MAX(op_id), 0) + 1 FROM journal",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(TodoError::from)?;
+        let recorded_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        for &id in ids {
+            let before = if action == "add" {
+                None
+            } else {
+                let Some(task) = self.get_task(id)? else {
+                    continue;
+                };
+                self.record_history(action, id, Some(&task), &recorded_at)?;
+                let snapshot = self.snapshot(task)?;
+                Some(serde_json::to_string(&snapshot).map_err(|e| {
+                    TodoError::Custom(format!("Could not record task {}: {}", id, e))
+                })?)
+            };
+            if before.is_none() {
+                self.record_history(action, id, None, &recorded_at)?;
+            }
+            self.conn
+                .execute(
+                    "INSERT INTO journal (op_id, action, todo_id, before, recorded_at) \
+                     VALUES (?1, ?2, ?3, ?4, CAST(?5 AS TIMESTAMP))",
+                    params![op_id, action, id, before, recorded_at],
+                )
+                .map_err(TodoError::from)?;
+        }
+
+        self.conn
+            .execute(
+                "DELETE FROM journal WHERE op_id <= ?1",
+                [op_id - JOURNAL_LIMIT],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    // Adds a change to the task history, keeping the task as it was before it when there was one
+    fn record_history(
+        &self,
+        action: &str,
+        id: i32,
+        before: Option<&Task>,
+        recorded_at: &str,
+    ) -> Result<(), TodoError> {
+        let before = before
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| TodoError::Custom(format!("Could not record task {}: {}", id, e)))?;
+        self.conn
+            .execute(
+                "INSERT INTO history (todo_id, action, before, recorded_at) \
+                 VALUES (?1, ?2, ?3, CAST(?4 AS TIMESTAMP))",
+                params![id, action, before, recorded_at],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    /// The recorded changes to the task with ID `id`, or to every task, made
+    /// on or after `since`, oldest first. Each change is told from the task as
+    /// it was before it and as it was before the next change, or as it is now.
+    pub fn history(
+        &self,
+        id: Option<i32>,
+        since: Option<NaiveDate>,
+    ) -> Result<Vec<HistoryEntry>, TodoError> {
+        let since = since.map(|d| d.format("%Y-%m-%d").to_string());
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT todo_id, action, before, strftime(recorded_at, '%Y-%m-%d %H:%M') \
+                 FROM history WHERE (?1 IS NULL OR todo_id = ?1) \
+                 AND (?2 IS NULL OR recorded_at >= CAST(?2 AS DATE)) ORDER BY id DESC",
+            )
+            .map_err(TodoError::from)?;
+        let rows = stmt
+            .query_map(params![id, since], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+
+        // The state of each task after the change being looked at, walking back in time.
+        // Every later change is made on or after `since` too, so none are missing.
+        let mut after: HashMap<i32, Option<Task>> = HashMap::new();
+        let mut entries = Vec::new();
+        for (todo_id, action, before, recorded_at) in rows {
+            let before: Option<Task> = before.and_then(|json| serde_json::from_str(&json).ok());
+            let later = match after.remove(&todo_id) {
+                Some(later) => later,
+                None => self.get_task(todo_id)?,
+            };
+            let changes = match (&before, &later) {
+                (Some(before), Some(later)) => history::changes(before, later),
+                _ => Vec::new(),
+            };
+            entries.push(HistoryEntry {
+                recorded_at,
+                todo_id,
+                name: later
+                    .as_ref()
+                    .or(before.as_ref())
+                    .map(|t| t.name.clone())
+                    .unwrap_or_default(),
+                action,
+                changes,
+            });
+            after.insert(todo_id, before);
+        }
+        entries.reverse();
+        Ok(entries)
+    }
+
+    // Records tasks added as part of the latest journaled operation, so undoing it removes them
+    pub fn journal_added(&self, ids: &[i32]) -> Result<(), TodoError> {
+        let recorded_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        for id in ids {
+            self.record_history("add", *id, None, &recorded_at)?;
+            self.conn
+                .execute(
+                    "INSERT INTO journal (op_id, action, todo_id, before, recorded_at) \
+                     SELECT op_id, action, ?1, NULL, recorded_at FROM journal \
+                     WHERE op_id = (SELECT MAX(op_id) FROM journal) LIMIT 1",
+                    [id],
+                )
+                .map_err(TodoError::from)?;
+        }
+        Ok(())
+    }
+
+    /// Rolls back the most recent journaled operation: added tasks are
+    /// deleted again, and changed or deleted tasks get their recorded state
+    /// back. Returns the operation's action and task IDs, or None when the
+    /// journal is empty.
+    pub fn undo_last(&self) -> Result<Option<(String, Vec<i32>)>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT op_id, action, todo_id, before FROM journal \
+                 WHERE op_id = (SELECT MAX(op_id) FROM journal) ORDER BY todo_id",
+            )
+            .map_err(TodoError::from)?;
+        let entries = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i32>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        let Some((op_id, action, _, _)) = entries.first().cloned() else {
+            return Ok(None);
+        };
 
-        if let Some(task) = new_task {
-            updates.push(format!("task = '{}'", task));
+        let ids: Vec<i32> = entries.iter().map(|(_, _, id, _)| *id).collect();
+        let recorded_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        for &id in &ids {
+            let task = self.get_task(id)?;
+            self.record_history("undo", id, task.as_ref(), &recorded_at)?;
         }
-        if let Some(due_date) = new_due_date {
-            updates.push(format!("due_date = '{}'", due_date));
-        }
-        if let Some(priority) = new_priority {
-            updates.push(format!("priority = {}", priority));
-        }
-        if mark_undone {
-            updates.push("done = 0".to_string());
-            updates.push("completion_date = NULL".to_string());
+        if action == "add" {
+            self.delete_tasks(&ids)?;
+        } else {
+            if action == "done" {
+                self.ungroup_completed(&ids)?;
+            }
+            // Tasks the operation added along the way, like the next instance of a recurring task
+            let added: Vec<i32> = entries
+                .iter()
+                .filter(|(_, _, _, before)| before.is_none())
+                .map(|(_, _, id, _)| *id)
+                .collect();
+            self.delete_tasks(&added)?;
+            for (_, _, id, before) in entries.iter().filter(|(_, _, _, b)| b.is_some()) {
+                let snapshot: TaskSnapshot =
+                    serde_json::from_str(before.as_deref().unwrap_or_default()).map_err(|e| {
+                        TodoError::Custom(format!(
+                            "Could not read the saved state of task {}: {}",
+                            id, e
+                        ))
+                    })?;
+                self.restore_task(snapshot)?;
+            }
         }
 
-        if !updates.is_empty() {
-            let sql = format!("UPDATE todos SET {} WHERE id = ?1", updates.join(", "));
-            self.conn.execute(&sql, &[&id]).map_err(TodoError::from)?;
-        }
+        self.conn
+            .execute("DELETE FROM journal WHERE op_id = ?1", [op_id])
+            .map_err(TodoError::from)?;
+        Ok(Some((action, ids)))
+    }
 
-        if let Some(category) = new_category {
-            self.add_category(&category)?;
-            let category_id = self.get_category_id(&category)?;
+    // A task with everything kept alongside it, so `restore_task` can bring it all back
+    fn snapshot(&self, task: Task) -> Result<TaskSnapshot, TodoError> {
+        let id = task.id;
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT strftime(created_at, '%Y-%m-%d %H:%M:%S'), body FROM annotations \
+                 WHERE todo_id = ?1 ORDER BY created_at, rowid",
+            )
+            .map_err(TodoError::from)?;
+        let annotations = stmt
+            .query_map([id], |row| {
+                Ok(Annotation {
+                    created_at: row.get(0)?,
+                    body: row.get(1)?,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT strftime(started_at, '%Y-%m-%d %H:%M:%S'), \
+                 strftime(ended_at, '%Y-%m-%d %H:%M:%S'), COALESCE(pomodoro, false) \
+                 FROM time_entries WHERE todo_id = ?1 ORDER BY started_at",
+            )
+            .map_err(TodoError::from)?;
+        let time_entries = stmt
+            .query_map([id], |row| {
+                Ok(TimeEntry {
+                    started_at: row.get(0)?,
+                    ended_at: row.get(1)?,
+                    pomodoro: row.get(2)?,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(TaskSnapshot {
+            comments: self.get_comments(id)?,
+            notes: self.get_notes(id)?,
+            annotations,
+            time_entries,
+            reminders: self.get_reminders(id)?,
+            task,
+        })
+    }
+
+    // Puts a task back exactly as snapshotted, recreating it (and everything kept with it) if it was deleted or trashed
+    fn restore_task(&self, snapshot: TaskSnapshot) -> Result<(), TodoError> {
+        let task = &snapshot.task;
+        let date = |date: Option<NaiveDate>| date.map(|d| d.format("%Y-%m-%d").to_string());
+        let (due_date, completion_date, scheduled_date, created_at, follow_up, start_date) = (
+            date(task.due_date),
+            date(task.completion_date),
+            date(task.scheduled_date),
+            date(task.created_at),
+            date(task.follow_up),
+            date(task.start_date),
+        );
+        let values = params![
+            task.id,
+            task.name,
+            task.done,
+            due_date,
+            completion_date,
+            task.priority,
+            scheduled_date,
+            created_at,
+            task.pinned,
+            task.context,
+            task.waiting_for,
+            follow_up,
+            task.created_by,
+            task.completed_by,
+            task.parent_id,
+            task.repeat,
+            start_date,
+            task.icon,
+            task.estimate,
+            task.assignee
+        ];
+
+        if self.get_task(task.id)?.is_some() {
             self.conn
-                .execute("DELETE FROM todo_categories WHERE todo_id = ?1", &[&id])
+                .execute(
+                    "UPDATE todos SET task = ?2, done = ?3, due_date = ?4, completion_date = ?5, \
+                     priority = ?6, scheduled_date = ?7, created_at = ?8, pinned = ?9, context = ?10, \
+                     waiting_for = ?11, follow_up = ?12, created_by = ?13, completed_by = ?14, \
+                     parent_id = ?15, repeat = ?16, start_date = ?17, icon = ?18, estimate = ?19, assignee = ?20 WHERE id = ?1",
+                    values,
+                )
                 .map_err(TodoError::from)?;
+            for table in ["todo_categories", "todo_tags"] {
+                self.conn
+                    .execute(
+                        &format!("DELETE FROM {} WHERE todo_id = ?1", table),
+                        [task.id],
+                    )
+                    .map_err(TodoError::from)?;
+            }
+        } else {
             self.conn
                 .execute(
-                    "INSERT INTO todo_categories (todo_id, category_id) VALUES (?1, ?2)",
-                    &[&id, &category_id],
+                    &format!(
+                        "INSERT INTO todos ({}) VALUES \
+                         (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
+                        TASK_COLUMNS
+                    ),
+                    values,
                 )
                 .map_err(TodoError::from)?;
-        }
-
-        if !new_tags.is_empty() {
+            // A trashed task keeps its comments, notes and the rest, which the snapshot carries as well
             self.conn
-                .execute("DELETE FROM todo_tags WHERE todo_id = ?1", &[&id])
+                .execute("DELETE FROM trash WHERE id = ?1", [task.id])
                 .map_err(TodoError::from)?;
-
-            // Split tags by comma and trim them
-            let tags_list: Vec<&str> = new_tags
-                .iter()
-                .flat_map(|t| t.split(',').map(|s| s.trim()))
-                .collect();
-
-            for tag in tags_list {
-                self.add_tag(tag)?;
-                let tag_id = self.get_tag_id(tag)?;
+            for table in [
+                "comments",
+                "notes",
+                "annotations",
+                "time_entries",
+                "reminders",
+            ] {
                 self.conn
                     .execute(
-                        "INSERT INTO todo_tags (todo_id, tag_id) VALUES (?1, ?2)",
-                        &[&id, &tag_id],
+                        &format!("DELETE FROM {} WHERE todo_id = ?1", table),
+                        [task.id],
+                    )
+                    .map_err(TodoError::from)?;
+            }
+            for comment in &snapshot.comments {
+                self.conn
+                    .execute(
+                        "INSERT INTO comments (todo_id, author, created_at, body) \
+                         VALUES (?1, ?2, CAST(?3 AS TIMESTAMP), ?4)",
+                        params![task.id, comment.author, comment.created_at, comment.body],
+                    )
+                    .map_err(TodoError::from)?;
+            }
+            for note in &snapshot.notes {
+                self.conn
+                    .execute(
+                        "INSERT INTO notes (id, todo_id, created_at, body) \
+                         VALUES (?1, ?2, CAST(?3 AS TIMESTAMP), ?4)",
+                        params![note.id, task.id, note.created_at, note.body],
+                    )
+                    .map_err(TodoError::from)?;
+            }
+            for annotation in &snapshot.annotations {
+                self.conn
+                    .execute(
+                        "INSERT INTO annotations (todo_id, created_at, body) \
+                         VALUES (?1, CAST(?2 AS TIMESTAMP), ?3)",
+                        params![task.id, annotation.created_at, annotation.body],
+                    )
+                    .map_err(TodoError::from)?;
+            }
+            for entry in &snapshot.time_entries {
+                self.conn
+                    .execute(
+                        "INSERT INTO time_entries (todo_id, started_at, ended_at, pomodoro) \
+                         VALUES (?1, CAST(?2 AS TIMESTAMP), CAST(?3 AS TIMESTAMP), ?4)",
+                        params![task.id, entry.started_at, entry.ended_at, entry.pomodoro],
                     )
                     .map_err(TodoError::from)?;
             }
+            for days in &snapshot.reminders {
+                self.add_reminder(task.id, *days)?;
+            }
         }
 
-        Ok(())
+        self.conn
+            .execute("DELETE FROM task_attributes WHERE todo_id = ?1", [task.id])
+            .map_err(TodoError::from)?;
+        for (name, value) in &task.attributes {
+            self.set_attribute(task.id, name, Some(value))?;
+        }
+        self.link_category_and_tags(task.id, task.category.as_deref(), &task.tags)
+    }
+
+    // Returns false when no task has the given ID
+    pub fn add_annotation(&self, todo_id: i32, body: &str) -> Result<bool, TodoError> {
+        let created_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let added = self
+            .conn
+            .execute(
+                "INSERT INTO annotations (todo_id, created_at, body) \
+                 SELECT id, CAST(?2 AS TIMESTAMP), ?3 FROM todos WHERE id = ?1",
+                params![todo_id, created_at, body],
+            )
+            .map_err(TodoError::from)?;
+        Ok(added > 0)
+    }
+
+    pub fn get_annotations(&self, todo_id: i32) -> Result<Vec<Annotation>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT strftime(created_at, '%Y-%m-%d %H:%M'), body FROM annotations \
+                 WHERE todo_id = ?1 ORDER BY created_at, rowid",
+            )
+            .map_err(TodoError::from)?;
+        let annotations = stmt
+            .query_map([todo_id], |row| {
+                Ok(Annotation {
+                    created_at: row.get(0)?,
+                    body: row.get(1)?,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(annotations)
+    }
+
+    // Returns false when no task has the given ID
+    pub fn add_comment(
+        &self,
+        todo_id: i32,
+        author: Option<&str>,
+        body: &str,
+    ) -> Result<bool, TodoError> {
+        let created_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let added = self
+            .conn
+            .execute(
+                "INSERT INTO comments (todo_id, author, created_at, body) \
+                 SELECT id, ?2, CAST(?3 AS TIMESTAMP), ?4 FROM todos WHERE id = ?1",
+                params![todo_id, author, created_at, body],
+            )
+            .map_err(TodoError::from)?;
+        Ok(added > 0)
+    }
+
+    pub fn get_comments(&self, todo_id: i32) -> Result<Vec<Comment>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT author, strftime(created_at, '%Y-%m-%d %H:%M'), body FROM comments \
+                 WHERE todo_id = ?1 ORDER BY created_at, rowid",
+            )
+            .map_err(TodoError::from)?;
+        let comments = stmt
+            .query_map([todo_id], |row| {
+                Ok(Comment {
+                    author: row.get(0)?,
+                    created_at: row.get(1)?,
+                    body: row.get(2)?,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(comments)
+    }
+
+    // Returns the new note's ID, or None when no task has the given ID
+    pub fn add_note(&self, todo_id: i32, body: &str) -> Result<Option<i32>, TodoError> {
+        let created_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn
+            .query_row(
+                "INSERT INTO notes (todo_id, created_at, body) \
+                 SELECT id, CAST(?2 AS TIMESTAMP), ?3 FROM todos WHERE id = ?1 RETURNING id",
+                params![todo_id, created_at, body],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(TodoError::from)
+    }
+
+    pub fn get_notes(&self, todo_id: i32) -> Result<Vec<Note>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT id, strftime(created_at, '%Y-%m-%d %H:%M'), body FROM notes \
+                 WHERE todo_id = ?1 ORDER BY created_at, id",
+            )
+            .map_err(TodoError::from)?;
+        let notes = stmt
+            .query_map([todo_id], |row| {
+                Ok(Note {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    body: row.get(2)?,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(notes)
+    }
+
+    // Returns false when no note has the given ID
+    pub fn delete_note(&self, id: i32) -> Result<bool, TodoError> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM notes WHERE id = ?1", [id])
+            .map_err(TodoError::from)?;
+        Ok(deleted > 0)
     }
 
     fn get_category_id(&self, name: &str) -> Result<i32, TodoError> {
@@ -403,7 +3344,7 @@ impl Database {
 
     pub fn add_category(&self, name: &str) -> Result<(), TodoError> {
         let sql = "INSERT OR IGNORE INTO categories (name) VALUES (?1)";
-        self.conn.execute(sql, &[name]).map_err(TodoError::from)?;
+        self.conn.execute(sql, [name]).map_err(TodoError::from)?;
 
         // Check if the category was actually added
         let mut stmt = self
@@ -423,7 +3364,7 @@ impl Database {
 
     pub fn delete_category(&self, name: &str) -> Result<(), TodoError> {
         let sql = "DELETE FROM categories WHERE name = ?1";
-        self.conn.execute(sql, &[name]).map_err(TodoError::from)?;
+        self.conn.execute(sql, [name]).map_err(TodoError::from)?;
         Ok(())
     }
 
@@ -442,9 +3383,271 @@ impl Database {
         Ok(categories)
     }
 
+    // A limit of None removes the category's WIP limit
+    pub fn set_category_wip_limit(&self, name: &str, limit: Option<i64>) -> Result<(), TodoError> {
+        let sql = "UPDATE categories SET wip_limit = ?1 WHERE name = ?2";
+        self.conn
+            .execute(sql, params![limit, name])
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    // An icon of None removes the category's icon
+    pub fn set_category_icon(&self, name: &str, icon: Option<&str>) -> Result<(), TodoError> {
+        let sql = "UPDATE categories SET icon = ?1 WHERE name = ?2";
+        self.conn
+            .execute(sql, params![icon, name])
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    fn get_category_icon(&self, name: &str) -> Result<Option<String>, TodoError> {
+        let icon: Option<Option<String>> = self
+            .conn
+            .query_row(
+                "SELECT icon FROM categories WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(TodoError::from)?;
+        Ok(icon.flatten())
+    }
+
+    // Saves the steps added as subtasks to each new task in the category, one per line; no steps removes the checklist
+    pub fn set_category_checklist(&self, name: &str, steps: &[String]) -> Result<(), TodoError> {
+        let checklist = (!steps.is_empty()).then(|| steps.join("\n"));
+        self.conn
+            .execute(
+                "UPDATE categories SET checklist = ?1 WHERE name = ?2",
+                params![checklist, name],
+            )
+            .map_err(TodoError::from)?;
+        Ok(())
+    }
+
+    pub fn get_category_checklist(&self, name: &str) -> Result<Vec<String>, TodoError> {
+        let checklist: Option<Option<String>> = self
+            .conn
+            .query_row(
+                "SELECT checklist FROM categories WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(TodoError::from)?;
+        Ok(checklist
+            .flatten()
+            .map(|steps| steps.lines().map(String::from).collect())
+            .unwrap_or_default())
+    }
+
+    /// Adds the checklist of the task's category as subtasks of it, in the
+    /// same category and by the same person, and returns their IDs.
+    /// Subtasks get no checklist of their own, so a checklist is only added
+    /// to top-level tasks.
+    pub fn add_checklist(&self, id: i32) -> Result<Vec<i32>, TodoError> {
+        let Some(task) = self.get_task(id)? else {
+            return Ok(Vec::new());
+        };
+        let Some(category) = task.category.filter(|_| task.parent_id.is_none()) else {
+            return Ok(Vec::new());
+        };
+        let mut ids = Vec::new();
+        for step in self.get_category_checklist(&category)? {
+            let mut subtask = Task::new(&step, category.clone(), None, Vec::new(), 0);
+            subtask.parent_id = Some(id);
+            subtask.created_by = task.created_by.clone();
+            ids.push(self.add_task(subtask)?);
+        }
+        Ok(ids)
+    }
+
+    pub fn category_loads(&self) -> Result<Vec<CategoryLoad>, TodoError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT c.name, COUNT(t.id) FILTER (WHERE t.done = 0), c.wip_limit, c.icon
+                 FROM categories c
+                 LEFT JOIN todo_categories tc ON c.id = tc.category_id
+                 LEFT JOIN todos t ON t.id = tc.todo_id
+                 GROUP BY c.name, c.wip_limit, c.icon
+                 ORDER BY c.name",
+            )
+            .map_err(TodoError::from)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(CategoryLoad {
+                    name: row.get(0)?,
+                    open_tasks: row.get(1)?,
+                    wip_limit: row.get(2)?,
+                    icon: row.get(3)?,
+                })
+            })
+            .map_err(TodoError::from)?;
+        let mut loads = Vec::new();
+        for row in rows {
+            loads.push(row.map_err(TodoError::from)?);
+        }
+        Ok(loads)
+    }
+
+    // None when there is no such category
+    pub fn project_counts(
+        &self,
+        category: &str,
+        today: NaiveDate,
+    ) -> Result<Option<ProjectCounts>, TodoError> {
+        let counts = self
+            .conn
+            .query_row(
+                "SELECT COUNT(t.id) FILTER (WHERE t.done = 0),
+                        COUNT(t.id) FILTER (WHERE t.done = 1),
+                        COUNT(t.id) FILTER (WHERE t.done = 0 AND t.due_date < CAST(?2 AS DATE)),
+                        COUNT(t.id) FILTER (WHERE t.done = 0 AND t.waiting_for IS NOT NULL),
+                        COUNT(t.id) FILTER (WHERE t.completion_date > CAST(?2 AS DATE) - 7),
+                        c.wip_limit
+                 FROM categories c
+                 LEFT JOIN todo_categories tc ON c.id = tc.category_id
+                 LEFT JOIN todos t ON t.id = tc.todo_id
+                 WHERE c.name = ?1
+                 GROUP BY c.wip_limit",
+                params![category, today.format("%Y-%m-%d").to_string()],
+                |row| {
+                    Ok(ProjectCounts {
+                        open: row.get(0)?,
+                        done: row.get(1)?,
+                        overdue: row.get(2)?,
+                        waiting: row.get(3)?,
+                        done_last_week: row.get(4)?,
+                        wip_limit: row.get(5)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(TodoError::from)?;
+        Ok(counts)
+    }
+
+    /// Counts tasks in a cross-tab of two dimensions (see PIVOT_DIMENSIONS)
+    /// with DuckDB's PIVOT. The first column holds the `rows` values; every
+    /// other column is one value of `cols`.
+    pub fn pivot_report(
+        &self,
+        rows: &str,
+        cols: &str,
+        today: NaiveDate,
+        week_start: Weekday,
+    ) -> Result<PivotTable, TodoError> {
+        let dimension = |name: &str| {
+            pivot_dimension(name, today, week_start).ok_or_else(|| {
+                TodoError::Custom(format!(
+                    "Unknown dimension '{}'. Please use one of: {}.",
+                    name,
+                    PIVOT_DIMENSIONS.join(", ")
+                ))
+            })
+        };
+        let (row_sql, col_sql) = (dimension(rows)?, dimension(cols)?);
+        // Tasks with several tags would otherwise be counted once per tag
+        let tag_join = if rows == "tag" || cols == "tag" {
+            "LEFT JOIN todo_tags tt ON tt.todo_id = t.id LEFT JOIN tags tg ON tg.id = tt.tag_id"
+        } else {
+            ""
+        };
+        let query = format!(
+            "PIVOT (
+                SELECT {} AS row_key, {} AS col_key FROM todos t
+                LEFT JOIN todo_categories tc ON tc.todo_id = t.id
+                LEFT JOIN categories c ON c.id = tc.category_id
+                {}
+             ) ON col_key USING count(*) GROUP BY row_key ORDER BY row_key",
+            row_sql, col_sql, tag_join
+        );
+
+        let mut stmt = self.conn.prepare(&query).map_err(TodoError::from)?;
+        let mut table = PivotTable {
+            headers: Vec::new(),
+            rows: Vec::new(),
+        };
+        let mut result = stmt.query([]).map_err(TodoError::from)?;
+        while let Some(row) = result.next().map_err(TodoError::from)? {
+            let mut values = Vec::new();
+            for i in 0..row.as_ref().column_count() {
+                let value = match row.get_ref(i).map_err(TodoError::from)? {
+                    ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned(),
+                    ValueRef::Null => String::new(),
+                    _ => row.get::<_, i64>(i).map_err(TodoError::from)?.to_string(),
+                };
+                values.push(value);
+            }
+            table.rows.push(values);
+        }
+        drop(result);
+        table.headers = stmt.column_names();
+        table.headers[0] = rows.to_string();
+        Ok(table)
+    }
+
+    /// Days from creation to completion of finished tasks, archived ones
+    /// included, grouped by "category" or "priority", followed by a row for
+    /// all of them.
+    pub fn completion_times(&self, by: &str) -> Result<Vec<CompletionTimes>, TodoError> {
+        if by != "category" && by != "priority" {
+            return Err(TodoError::Custom(format!(
+                "Unknown grouping '{}'. Please use category or priority.",
+                by
+            )));
+        }
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "WITH finished AS (
+                    SELECT COALESCE(c.name, 'none') AS category, t.priority,
+                           t.completion_date - t.created_at AS days, t.completion_date > t.due_date AS late
+                    FROM todos t
+                    LEFT JOIN todo_categories tc ON tc.todo_id = t.id
+                    LEFT JOIN categories c ON c.id = tc.category_id
+                    WHERE t.done = 1 AND t.completion_date IS NOT NULL AND t.created_at IS NOT NULL
+                    UNION ALL
+                    SELECT COALESCE(category, 'none'), priority,
+                           completion_date - created_at, completion_date > due_date
+                    FROM archive
+                    WHERE completion_date IS NOT NULL AND created_at IS NOT NULL
+                 )
+                 SELECT CAST({0} AS TEXT), COUNT(*), median(days), quantile_cont(days, 0.9),
+                        avg(CAST(late AS INTEGER))
+                 FROM finished
+                 GROUP BY GROUPING SETS (({0}), ())
+                 ORDER BY {0} NULLS LAST",
+                by
+            ))
+            .map_err(TodoError::from)?;
+        // With no finished tasks the grand total row is still there, with no median
+        let times = stmt
+            .query_map([], |row| {
+                let median: Option<f64> = row.get(2)?;
+                let p90: Option<f64> = row.get(3)?;
+                Ok(match (median, p90) {
+                    (Some(median), Some(p90)) => Some(CompletionTimes {
+                        group: row.get(0)?,
+                        tasks: row.get(1)?,
+                        median,
+                        p90,
+                        late: row.get(4)?,
+                    }),
+                    _ => None,
+                })
+            })
+            .map_err(TodoError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TodoError::from)?;
+        Ok(times.into_iter().flatten().collect())
+    }
+
     pub fn add_tag(&self, name: &str) -> Result<(), TodoError> {
         let sql = "INSERT OR IGNORE INTO tags (name) VALUES (?1)";
-        self.conn.execute(sql, &[name]).map_err(TodoError::from)?;
+        self.conn.execute(sql, [name]).map_err(TodoError::from)?;
 
         // Check if the tag was actually added
         let mut stmt = self
@@ -464,7 +3667,7 @@ impl Database {
 
     pub fn delete_tag(&self, name: &str) -> Result<(), TodoError> {
         let sql = "DELETE FROM tags WHERE name = ?1";
-        self.conn.execute(sql, &[name]).map_err(TodoError::from)?;
+        self.conn.execute(sql, [name]).map_err(TodoError::from)?;
         Ok(())
     }
 
@@ -483,3 +3686,213 @@ impl Database {
         Ok(tags)
     }
 }
+
+// Dimensions `report pivot` can use for its rows and columns
+pub const PIVOT_DIMENSIONS: &[&str] = &[
+    "category",
+    "status",
+    "due-week",
+    "due-month",
+    "done-week",
+    "priority",
+    "context",
+    "tag",
+    "created-by",
+];
+
+// The SQL expression grouping tasks by a pivot dimension
+fn pivot_dimension(name: &str, today: NaiveDate, week_start: Weekday) -> Option<String> {
+    let expression = match name {
+        "category" => "COALESCE(c.name, 'none')".to_string(),
+        "status" => format!(
+            "CASE WHEN t.done THEN 'done' \
+                  WHEN t.waiting_for IS NOT NULL THEN 'waiting' \
+                  WHEN t.due_date < DATE '{}' THEN 'overdue' \
+                  ELSE 'pending' END",
+            today.format("%Y-%m-%d")
+        ),
+        "due-week" => format!("COALESCE({}, 'none')", week_sql("t.due_date", week_start)),
+        "due-month" => "COALESCE(strftime(t.due_date, '%Y-%m'), 'none')".to_string(),
+        "done-week" => format!(
+            "COALESCE({}, 'none')",
+            week_sql("t.completion_date", week_start)
+        ),
+        "priority" => "CAST(t.priority AS TEXT)".to_string(),
+        "context" => "COALESCE(t.context, 'none')".to_string(),
+        "tag" => "COALESCE(tg.name, 'none')".to_string(),
+        "created-by" => "COALESCE(t.created_by, 'unknown')".to_string(),
+        _ => return None,
+    };
+    Some(expression)
+}
+
+// The week a date column falls in, numbered the way `dates::week_label` does
+fn week_sql(column: &str, week_start: Weekday) -> String {
+    format!(
+        "strftime(CAST({0} - CAST((isodow({0}) + 6 - {1}) % 7 AS INTEGER) + 3 AS DATE), '%G-W%V')",
+        column,
+        week_start.num_days_from_monday()
+    )
+}
+
+// A SQL expression picking a placeholder task name like "Review quarterly
+// report" from the words above, the same one for the same integer `key`
+fn placeholder_sql(key: &str) -> String {
+    let list = |words: &[&str]| {
+        format!(
+            "[{}]",
+            words
+                .iter()
+                .map(|w| format!("'{}'", w))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    format!(
+        "({0}[CAST({3} % {4} AS BIGINT) + 1] || ' ' || \
+          {1}[CAST({3} // {4} % {5} AS BIGINT) + 1] || ' ' || \
+          {2}[CAST({3} * 7 % {6} AS BIGINT) + 1])",
+        list(PLACEHOLDER_VERBS),
+        list(PLACEHOLDER_ADJECTIVES),
+        list(PLACEHOLDER_NOUNS),
+        // Kept small so multiplying a hash key by 7 can't overflow
+        format!("(CAST({} AS UBIGINT) % 1000003)", key),
+        PLACEHOLDER_VERBS.len(),
+        PLACEHOLDER_ADJECTIVES.len(),
+        PLACEHOLDER_NOUNS.len()
+    )
+}
+
+// Completed tasks, finished before `before` if given
+fn archivable_condition(before: Option<NaiveDate>) -> String {
+    match before {
+        Some(date) => format!(
+            "done = 1 AND completion_date < DATE '{}'",
+            date.format("%Y-%m-%d")
+        ),
+        None => "done = 1".to_string(),
+    }
+}
+
+// The tasks an export covers, as a subquery with the columns of `todos` and
+// each task's category and comma-separated tags: the pending ("pending") or
+// done ("done") tasks of the list, the archived ("archived") or trashed
+// ("trashed") ones, or all of them ("all")
+fn scope_source(scope: &str) -> Result<String, TodoError> {
+    let listed = |condition: &str| {
+        format!(
+            "SELECT {}, \
+                 (SELECT c.name FROM todo_categories tc JOIN categories c ON c.id = tc.category_id \
+                  WHERE tc.todo_id = todos.id LIMIT 1) AS category, \
+                 (SELECT string_agg(g.name, ',' ORDER BY g.name) FROM todo_tags tt \
+                  JOIN tags g ON g.id = tt.tag_id WHERE tt.todo_id = todos.id) AS tags \
+             FROM todos WHERE {}",
+            TASK_COLUMNS, condition
+        )
+    };
+    let shelved = |table: &str| format!("SELECT {}, category, tags FROM {}", TASK_COLUMNS, table);
+    let source = match scope {
+        "all" => format!(
+            "{} UNION ALL {} UNION ALL {}",
+            listed("true"),
+            shelved("archive"),
+            shelved("trash")
+        ),
+        "pending" => listed("NOT done"),
+        "done" => listed("done"),
+        "archived" => shelved("archive"),
+        "trashed" => shelved("trash"),
+        _ => {
+            return Err(TodoError::Custom(format!(
+                "Unknown scope '{}'. Please use all, pending, done, archived, or trashed.",
+                scope
+            )))
+        }
+    };
+    Ok(format!("({})", source))
+}
+
+// Refuses a redaction that names neither a column in `known` nor one of the
+// words `redact_select` understands
+fn check_redact(redact: &[String], known: &[(String, String)]) -> Result<(), TodoError> {
+    match redact
+        .iter()
+        .find(|r| !REDACT_WORDS.contains(&r.as_str()) && !known.iter().any(|(name, _)| name == *r))
+    {
+        Some(unknown) => Err(TodoError::Custom(format!("Unknown column '{}'.", unknown))),
+        None => Ok(()),
+    }
+}
+
+// The one redaction step every export goes through: each of `columns`, a name
+// and type, is selected as it is, written empty when `redact` names it, or with
+// its web links removed from text when `redact` has "urls". "name" stands for
+// the task name, and "notes" for the text of comments and annotations.
+fn redact_select(columns: &[(String, String)], redact: &[String]) -> Vec<String> {
+    let redacted = |column: &str| {
+        redact.iter().any(|r| {
+            r == column || (r == "name" && column == "task") || (r == "notes" && column == "body")
+        })
+    };
+    let strip_urls = redact.iter().any(|r| r == "urls");
+    columns
+        .iter()
+        .map(|(name, column_type)| {
+            if redacted(name) {
+                format!("CAST(NULL AS {}) AS {}", column_type, name)
+            } else if strip_urls && column_type == "VARCHAR" {
+                format!(
+                    "regexp_replace({0}, 'https?://[^[:space:]]+', '[link removed]', 'g') AS {0}",
+                    name
+                )
+            } else {
+                name.clone()
+            }
+        })
+        .collect()
+}
+
+// The current local time, written the way TIMESTAMP columns are cast from
+fn now_timestamp() -> String {
+    Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Reads the TASK_COLUMNS of a row; the category and tags are left for the caller
+fn task_from_row(row: &Row) -> duckdb::Result<Task> {
+    let pinned: Option<bool> = row.get(8)?;
+    Ok(Task {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        category: None,
+        tags: Vec::new(),
+        done: row.get(2)?,
+        due_date: date_from_ref(row.get_ref(3)?),
+        completion_date: date_from_ref(row.get_ref(4)?),
+        priority: row.get(5)?,
+        scheduled_date: date_from_ref(row.get_ref(6)?),
+        created_at: date_from_ref(row.get_ref(7)?),
+        pinned: pinned.unwrap_or(false),
+        context: row.get(9)?,
+        waiting_for: row.get(10)?,
+        follow_up: date_from_ref(row.get_ref(11)?),
+        created_by: row.get(12)?,
+        completed_by: row.get(13)?,
+        parent_id: row.get(14)?,
+        repeat: row.get(15)?,
+        start_date: date_from_ref(row.get_ref(16)?),
+        icon: row.get(17)?,
+        estimate: row.get(18)?,
+        assignee: row.get(19)?,
+        category_icon: None,
+        attributes: BTreeMap::new(),
+    })
+}
+
+fn date_from_ref(value: ValueRef) -> Option<NaiveDate> {
+    match value {
+        ValueRef::Date32(date32) => {
+            Some(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() + Duration::days(date32 as i64))
+        }
+        _ => None,
+    }
+}
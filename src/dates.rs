@@ -0,0 +1,243 @@
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+/// Reads a date written as YYYY-MM-DD or as a phrase relative to `today`:
+/// "today", "tomorrow", a weekday like "friday" (the next one after today),
+/// "next week" (its Monday), "next month" (its first day), "next friday",
+/// "end of week", "end of month", "in 3 days" (weeks, months), or an offset
+/// like "+3d", "+2w", "+1m" or "+1y" counted from `base`, usually today.
+pub fn parse(text: &str, today: NaiveDate, base: NaiveDate) -> Option<NaiveDate> {
+    if let Some(date) = offset(text.trim(), base) {
+        return Some(date);
+    }
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    match phrase_at(&words, today) {
+        Some((date, len)) if len == words.len() => Some(date),
+        _ => None,
+    }
+}
+
+/// The date a phrase starting at the first word refers to, with how many words it uses.
+/// Words are expected in lowercase.
+pub fn phrase_at(words: &[String], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let word = |i: usize| words.get(i).map(String::as_str).unwrap_or_default();
+
+    if let Ok(date) = NaiveDate::parse_from_str(word(0), "%Y-%m-%d") {
+        return Some((date, 1));
+    }
+    if let Some(date) = offset(word(0), today) {
+        return Some((date, 1));
+    }
+    match (word(0), word(1), word(2)) {
+        ("today" | "tonight", _, _) => Some((today, 1)),
+        ("tomorrow", _, _) => Some((today + Duration::days(1), 1)),
+        ("next", "week", _) => Some((next_weekday(today, Weekday::Mon), 2)),
+        ("next", "month", _) => Some((today.with_day(1).unwrap() + Months::new(1), 2)),
+        ("next", day, _) => weekday(day).map(|d| (next_weekday(today, d), 2)),
+        ("end", "of", "month") => Some((
+            today.with_day(1).unwrap() + Months::new(1) - Duration::days(1),
+            3,
+        )),
+        ("end", "of", "week") => Some((next_weekday(today - Duration::days(1), Weekday::Sun), 3)),
+        ("in", n, unit) => {
+            let n: u32 = n.parse().ok()?;
+            let date = match unit.trim_end_matches('s') {
+                "day" => today.checked_add_signed(Duration::try_days(n as i64)?),
+                "week" => today.checked_add_signed(Duration::try_weeks(n as i64)?),
+                "month" => today.checked_add_months(Months::new(n)),
+                _ => return None,
+            };
+            Some((date?, 3))
+        }
+        (day, _, _) => weekday(day).map(|d| (next_weekday(today, d), 1)),
+    }
+}
+
+// "+3d", "+2w", "+1m" or "+1y" after `base`
+fn offset(word: &str, base: NaiveDate) -> Option<NaiveDate> {
+    let count = word.strip_prefix('+')?;
+    let unit = count.chars().last()?;
+    let n: u32 = count[..count.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'd' => base.checked_add_signed(Duration::try_days(n as i64)?),
+        'w' => base.checked_add_signed(Duration::try_weeks(n as i64)?),
+        'm' => base.checked_add_months(Months::new(n)),
+        'y' => base.checked_add_months(Months::new(n.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// A lead time like "2d" or "1w" in days, for reminders before a due date.
+pub fn lead_days(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let unit = text.chars().last()?;
+    let n: i64 = text[..text.len() - unit.len_utf8()]
+        .parse()
+        .ok()
+        .filter(|n| *n >= 0)?;
+    let days = match unit {
+        'd' => Some(n),
+        'w' => n.checked_mul(7),
+        _ => None,
+    }?;
+    // Kept within what a Duration holds, so callers can count back from today
+    Duration::try_days(days).map(|_| days)
+}
+
+// `lead_days` as clap's `value_parser`, for ages like 90d or 12w
+pub fn parse_days(text: &str) -> Result<i64, String> {
+    lead_days(text).ok_or_else(|| "use a number of days or weeks like 90d or 12w".to_string())
+}
+
+/// A length of time like "45m", "2h" or "1h30m", in minutes.
+pub fn minutes(text: &str) -> Option<i64> {
+    let text: String = text.split_whitespace().collect();
+    let (hours, rest) = match text.split_once('h') {
+        Some((hours, rest)) => (hours.parse::<i64>().ok()?, rest),
+        None => (0, text.as_str()),
+    };
+    let minutes = match rest {
+        "" => 0,
+        rest => rest.strip_suffix('m')?.parse::<i64>().ok()?,
+    };
+    let total = hours.checked_mul(60)?.checked_add(minutes)?;
+    Duration::try_minutes(total)?;
+    (hours >= 0 && minutes >= 0 && total > 0).then_some(total)
+}
+
+// `minutes` as clap's `value_parser`
+pub fn parse_minutes(text: &str) -> Result<i64, String> {
+    minutes(text).ok_or_else(|| "use a length of time like 45m, 2h or 1h30m".to_string())
+}
+
+// Minutes written the way `minutes` reads them, like "1h 30m"
+pub fn describe_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// The week `date` falls in, like "2025-W14". Weeks start on `week_start` and
+/// take the ISO 8601 number of the week holding their fourth day, so with
+/// Monday weeks this is exactly the ISO week.
+pub fn week_label(date: NaiveDate, week_start: Weekday) -> String {
+    let middle = first_day_of_week(date, week_start) + Duration::days(3);
+    let week = middle.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+// The first day of the week `date` falls in, with weeks starting on `week_start`
+pub fn first_day_of_week(date: NaiveDate, week_start: Weekday) -> NaiveDate {
+    let into_week =
+        (date.weekday().num_days_from_monday() + 7 - week_start.num_days_from_monday()) % 7;
+    date - Duration::days(into_week as i64)
+}
+
+/// Reads a week like "2025-W14" (or "2025w14"), written back the way
+/// `week_label` writes it. Used as clap's `value_parser`.
+pub fn parse_week(text: &str) -> Result<String, String> {
+    let upper = text.trim().to_uppercase();
+    let week = upper.split_once('W').and_then(|(year, week)| {
+        let year: i32 = year.trim_end_matches('-').parse().ok()?;
+        let week: u32 = week.parse().ok()?;
+        NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).map(|_| (year, week))
+    });
+    match week {
+        Some((year, week)) => Ok(format!("{}-W{:02}", year, week)),
+        None => Err("use an ISO week like 2025-W14".to_string()),
+    }
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" => Some(Weekday::Mon),
+        "tuesday" => Some(Weekday::Tue),
+        "wednesday" => Some(Weekday::Wed),
+        "thursday" => Some(Weekday::Thu),
+        "friday" => Some(Weekday::Fri),
+        "saturday" => Some(Weekday::Sat),
+        "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+// The first `weekday` strictly after `date`
+fn next_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() as i64
+        - date.weekday().num_days_from_monday() as i64)
+        .rem_euclid(7);
+    date + Duration::days(if ahead == 0 { 7 } else { ahead })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_phrases() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d);
+        // A Wednesday
+        let today = date(3, 5).unwrap();
+
+        assert_eq!(parse("2025-04-01", today, today), date(4, 1));
+        assert_eq!(parse("tomorrow", today, today), date(3, 6));
+        assert_eq!(parse("Friday", today, today), date(3, 7));
+        assert_eq!(parse("wednesday", today, today), date(3, 12));
+        assert_eq!(parse("next week", today, today), date(3, 10));
+        assert_eq!(parse("end of month", today, today), date(3, 31));
+        assert_eq!(parse("in 2 weeks", today, today), date(3, 19));
+        assert_eq!(parse("+3d", today, today), date(3, 8));
+        assert_eq!(parse("+1m", today, today), date(4, 5));
+        assert_eq!(parse("+10d", today, date(4, 1).unwrap()), date(4, 11));
+        assert_eq!(parse("+d", today, today), None);
+        assert_eq!(parse("+1é", today, today), None);
+        assert_eq!(parse("+99999999d", today, today), None);
+        assert_eq!(parse("in 99999999 months", today, today), None);
+        assert_eq!(parse("friday afternoon", today, today), None);
+        assert_eq!(parse("2025-13-01", today, today), None);
+    }
+
+    #[test]
+    fn test_week_label() {
+        // A Sunday, the last day of ISO week 14
+        let sunday = NaiveDate::from_ymd_opt(2025, 4, 6).unwrap();
+        assert_eq!(week_label(sunday, Weekday::Mon), "2025-W14");
+        assert_eq!(week_label(sunday, Weekday::Sun), "2025-W15");
+        assert_eq!(first_day_of_week(sunday, Weekday::Sun), sunday);
+        assert_eq!(
+            first_day_of_week(sunday, Weekday::Mon),
+            NaiveDate::from_ymd_opt(2025, 3, 31).unwrap()
+        );
+        // ISO weeks can belong to the year before or after
+        let new_year = NaiveDate::from_ymd_opt(2024, 12, 30).unwrap();
+        assert_eq!(week_label(new_year, Weekday::Mon), "2025-W01");
+
+        assert_eq!(parse_week("2025w4"), Ok("2025-W04".to_string()));
+        assert!(parse_week("2025-W54").is_err());
+    }
+
+    #[test]
+    fn test_lead_days() {
+        assert_eq!(lead_days("2d"), Some(2));
+        assert_eq!(lead_days("1w"), Some(7));
+        assert_eq!(lead_days("0d"), Some(0));
+        assert_eq!(lead_days("-1d"), None);
+        assert_eq!(lead_days("3"), None);
+        assert_eq!(lead_days("2é"), None);
+        assert_eq!(lead_days("999999999999999d"), None);
+    }
+
+    #[test]
+    fn test_minutes() {
+        assert_eq!(minutes("45m"), Some(45));
+        assert_eq!(minutes("2h"), Some(120));
+        assert_eq!(minutes("1h30m"), Some(90));
+        assert_eq!(minutes("1h 30m"), Some(90));
+        assert_eq!(minutes("0m"), None);
+        assert_eq!(minutes("45"), None);
+        assert_eq!(minutes("999999999999999999m"), None);
+        assert_eq!(describe_minutes(90), "1h 30m");
+        assert_eq!(describe_minutes(45), "45m");
+    }
+}
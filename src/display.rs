@@ -1,21 +1,83 @@
-use crate::task::Task;
-use prettytable::{Cell, Row, Table};
+use crate::audit::DateIssue;
+use crate::database::{
+    Annotation, CategoryLoad, Comment, CompletionTimes, EstimateAccuracy, ExportRun, HistoryEntry,
+    ImportOutcome, ImportReport, Note, Operation, PivotTable, PomodoroCount, ProjectCounts,
+};
+use crate::dates;
+use crate::goal::Goal;
+use crate::priority;
+use crate::task::{urls, Task};
+use crate::task_template::TaskTemplate;
+use crate::theme::{self, Role};
+use crate::urgency::Urgency;
+use chrono::{Local, NaiveDate, Weekday};
+use prettytable::{Attr, Cell, Row, Table};
+use std::collections::BTreeMap;
 
 pub struct Display;
 
+// Optional columns shown by `show_tasks`
+#[derive(Default)]
+pub struct TaskColumns {
+    pub completion_date: bool,
+    pub context: bool,
+    pub start_date: bool,
+    pub assignee: bool,
+    // Custom fields shown as columns, in this order
+    pub attributes: Vec<String>,
+    pub created_by: bool,
+    pub completed_by: bool,
+    // Shows the Age column; tasks older than this many days are flagged as stale
+    pub age: Option<i64>,
+    // Categories over their WIP limit, highlighted in the Category column
+    pub over_limit_categories: Vec<String>,
+    // Shows the Week column, numbering weeks that start on this day
+    pub week: Option<Weekday>,
+    // Shows the Urgency column, scored with these weights
+    pub urgency: Option<Urgency>,
+}
+
 impl Display {
-    pub fn show_tasks(tasks: Vec<Task>, show_completion_date: bool) {
+    pub fn show_tasks(tasks: Vec<Task>, columns: &TaskColumns) {
         let mut table = Table::new();
+        let today = Local::now().date_naive();
 
         // Add table headers
         let mut headers = vec![
             "ID", "Name", "Category", "Tags", "Due Date", "Done", "Priority",
         ];
 
-        // Add "Completion Date" header only if show_completion_date is true
-        if show_completion_date {
+        // Add "Completion Date" header only if requested
+        if columns.completion_date {
             headers.push("Completion Date");
         }
+        if columns.context {
+            headers.push("Context");
+        }
+        if columns.start_date {
+            headers.push("Start Date");
+        }
+        if columns.week.is_some() {
+            headers.push("Week");
+        }
+        if columns.assignee {
+            headers.push("Assignee");
+        }
+        for name in &columns.attributes {
+            headers.push(name);
+        }
+        if columns.created_by {
+            headers.push("Created By");
+        }
+        if columns.completed_by {
+            headers.push("Completed By");
+        }
+        if columns.age.is_some() {
+            headers.push("Age");
+        }
+        if columns.urgency.is_some() {
+            headers.push("Urgency");
+        }
 
         table.add_row(Row::new(
             headers
@@ -26,10 +88,15 @@ impl Display {
 
         // Add task rows
         for task in tasks {
+            // Names with web links in them are underlined, as `open` can open them
+            let mut name = Cell::new(&task.display_name());
+            if !urls(&task.name).is_empty() {
+                name.style(Attr::Underline(true));
+            }
             let mut row = vec![
                 Cell::new(&task.id.to_string()),
-                Cell::new(&task.name),
-                Cell::new(&task.category.clone().unwrap_or_default()),
+                name,
+                category_cell(&task, columns),
                 Cell::new(&task.tags.join(", ")),
                 Cell::new(
                     &task
@@ -38,11 +105,12 @@ impl Display {
                         .unwrap_or_default(),
                 ),
                 Cell::new(&task.done.to_string()),
-                Cell::new(&task.priority.to_string()),
+                Cell::new(&priority::label(task.priority))
+                    .style_spec(priority::style(task.priority)),
             ];
 
-            // Add "Completion Date" cell only if show_completion_date is true
-            if show_completion_date {
+            // Add "Completion Date" cell only if requested
+            if columns.completion_date {
                 row.push(Cell::new(
                     &task
                         .completion_date
@@ -50,6 +118,70 @@ impl Display {
                         .unwrap_or_default(),
                 ));
             }
+            if columns.context {
+                row.push(Cell::new(&task.context.clone().unwrap_or_default()));
+            }
+            if columns.start_date {
+                row.push(Cell::new(
+                    &task
+                        .start_date
+                        .map(|d| d.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default(),
+                ));
+            }
+            if let Some(week_start) = columns.week {
+                row.push(Cell::new(
+                    &task
+                        .due_date
+                        .map(|d| dates::week_label(d, week_start))
+                        .unwrap_or_default(),
+                ));
+            }
+            if columns.assignee {
+                row.push(Cell::new(&task.assignee.clone().unwrap_or_default()));
+            }
+            for name in &columns.attributes {
+                row.push(Cell::new(
+                    task.attributes
+                        .get(name)
+                        .map(String::as_str)
+                        .unwrap_or_default(),
+                ));
+            }
+            if columns.created_by {
+                row.push(Cell::new(&task.created_by.clone().unwrap_or_default()));
+            }
+            if columns.completed_by {
+                row.push(Cell::new(&task.completed_by.clone().unwrap_or_default()));
+            }
+
+            let mut stale = false;
+            if let Some(stale_after) = columns.age {
+                let age = task.age_in_days(today);
+                stale = !task.done && age.is_some_and(|days| days >= stale_after);
+                row.push(Cell::new(
+                    &age.map(|days| {
+                        let marker = age_marker(days, stale_after);
+                        if stale {
+                            format!("{}d {} stale", days, marker)
+                        } else {
+                            format!("{}d {}", days, marker)
+                        }
+                    })
+                    .unwrap_or_default(),
+                ));
+            }
+            if let Some(urgency) = &columns.urgency {
+                row.push(Cell::new(&format!("{:.1}", urgency.score(&task, today))));
+            }
+
+            // Dim stale tasks so old cruft stands out from current work
+            if stale {
+                row = row
+                    .into_iter()
+                    .map(|cell| cell.with_style(Attr::Dim))
+                    .collect();
+            }
 
             table.add_row(Row::new(row));
         }
@@ -57,15 +189,838 @@ impl Display {
         table.printstd();
     }
 
-    pub fn show_categories(categories: Vec<String>) {
+    // Screen-reader friendly output: one labeled sentence per task, no box drawing
+    pub fn show_tasks_accessible(tasks: Vec<Task>, columns: &TaskColumns) {
+        if tasks.is_empty() {
+            println!("No tasks found.");
+            return;
+        }
+
+        let today = Local::now().date_naive();
+        for task in tasks {
+            let mut parts = vec![format!(
+                "Task {}: {}",
+                task.id,
+                task.name.trim_end_matches('.')
+            )];
+
+            if let Some(category) = &task.category {
+                parts.push(format!("Category {}", category));
+            }
+            if !task.tags.is_empty() {
+                parts.push(format!("Tags {}", task.tags.join(", ")));
+            }
+            if let Some(context) = &task.context {
+                parts.push(format!("Context {}", context));
+            }
+            if let Some(start_date) = task.start_date {
+                parts.push(format!("Starts {}", describe_date(start_date, today)));
+            }
+            if let Some(due_date) = task.due_date {
+                parts.push(format!("Due {}", describe_date(due_date, today)));
+                if let Some(week_start) = columns.week {
+                    parts.push(format!("Week {}", dates::week_label(due_date, week_start)));
+                }
+            }
+            parts.push(format!("Priority {}", priority::label(task.priority)));
+            parts.push(if task.done { "Done" } else { "Not done" }.to_string());
+            if let Some(assignee) = &task.assignee {
+                parts.push(format!("Assigned to {}", assignee));
+            }
+            for (name, value) in &task.attributes {
+                parts.push(format!("{} {}", name, value));
+            }
+            if let Some(created_by) = &task.created_by {
+                parts.push(format!("Added by {}", created_by));
+            }
+            if let Some(completed_by) = &task.completed_by {
+                parts.push(format!("Completed by {}", completed_by));
+            }
+
+            if columns.completion_date {
+                if let Some(completion_date) = task.completion_date {
+                    parts.push(format!(
+                        "Completed {}",
+                        describe_date(completion_date, today)
+                    ));
+                }
+            }
+
+            if let (Some(stale_after), Some(created_at)) = (columns.age, task.created_at) {
+                parts.push(format!("Added {}", describe_date(created_at, today)));
+                if !task.done && (today - created_at).num_days() >= stale_after {
+                    parts.push("Stale".to_string());
+                }
+            }
+            if let Some(urgency) = &columns.urgency {
+                parts.push(format!("Urgency {:.1}", urgency.score(&task, today)));
+            }
+
+            println!("{}.", parts.join(". "));
+        }
+    }
+
+    pub fn show_reminders(due_soon: &[&Task], newly_overdue: &[&Task]) {
+        if due_soon.is_empty() && newly_overdue.is_empty() {
+            return;
+        }
+
+        let today = Local::now().date_naive();
+        println!("Reminders:");
+        for task in newly_overdue {
+            if let Some(due_date) = task.due_date {
+                println!(
+                    "  ! Task {} ({}) is now overdue; it was due {}.",
+                    task.id,
+                    task.name,
+                    describe_date(due_date, today)
+                );
+            }
+        }
+        for task in due_soon {
+            if let Some(due_date) = task.due_date {
+                println!(
+                    "  - Task {} ({}) is due {}.",
+                    task.id,
+                    task.name,
+                    describe_date(due_date, today)
+                );
+            }
+        }
+        println!();
+    }
+
+    // One-screen overview of a category; `trends` holds daily counts over the
+    // last 30 days, and `recent` holds (date, what happened, task)
+    pub fn show_project(
+        name: &str,
+        counts: &ProjectCounts,
+        trends: &[(&str, Vec<i64>)],
+        next_due: &[&Task],
+        blocked: &[&Task],
+        recent: &[(NaiveDate, &str, &Task)],
+    ) {
+        let today = Local::now().date_naive();
+        println!("Project: {}", name);
+        println!(
+            "  {} open, {} done ({} in the last 7 days), {} overdue, {} waiting{}",
+            counts.open,
+            counts.done,
+            counts.done_last_week,
+            counts.overdue,
+            counts.waiting,
+            counts
+                .wip_limit
+                .map(|limit| format!(", WIP limit {}", limit))
+                .unwrap_or_default()
+        );
+        for (label, daily) in trends {
+            println!(
+                "  {:<6} {}  {} in the last 30 days",
+                label,
+                sparkline(daily),
+                daily.iter().sum::<i64>()
+            );
+        }
+
+        println!("\nNext due:");
+        if next_due.is_empty() {
+            println!("  Nothing with a due date.");
+        }
+        for task in next_due {
+            if let Some(due_date) = task.due_date {
+                println!(
+                    "  {:>4}  {} ({})",
+                    task.id,
+                    task.name,
+                    describe_date(due_date, today)
+                );
+            }
+        }
+
+        println!("\nBlocked:");
+        if blocked.is_empty() {
+            println!("  Nothing is waiting on someone else.");
+        }
+        for task in blocked {
+            println!(
+                "  {:>4}  {} (waiting for {})",
+                task.id,
+                task.name,
+                task.waiting_for.as_deref().unwrap_or_default()
+            );
+        }
+
+        println!("\nRecent activity:");
+        if recent.is_empty() {
+            println!("  Nothing in the last two weeks.");
+        }
+        for (date, what, task) in recent {
+            println!("  {}  {:<5}  {:>4}  {}", date, what, task.id, task.name);
+        }
+    }
+
+    /// Prints a status update on a category for people outside the team:
+    /// what was finished between `from` and `to`, what is still open and
+    /// what is due soon, in plain sentences without IDs or priorities.
+    /// csv and json give one row per task instead.
+    pub fn show_stakeholder_report(
+        category: &str,
+        (from, to): (NaiveDate, NaiveDate),
+        completed: &[&Task],
+        in_progress: &[&Task],
+        upcoming: &[&Task],
+        format: &str,
+    ) {
+        let day = |date: NaiveDate| date.format("%B %-d").to_string();
+        let line = |task: &Task| {
+            let mut text = task.name.clone();
+            if let Some(who) = &task.waiting_for {
+                text.push_str(&format!(" (waiting on {})", who));
+            }
+            text
+        };
+        let sections = [
+            (
+                "Completed",
+                completed
+                    .iter()
+                    .map(|t| (line(t), t.completion_date))
+                    .collect::<Vec<_>>(),
+                "Nothing was finished in this period.",
+            ),
+            (
+                "In progress",
+                in_progress.iter().map(|t| (line(t), t.due_date)).collect(),
+                "Nothing else is under way.",
+            ),
+            (
+                "Coming up",
+                upcoming.iter().map(|t| (line(t), t.due_date)).collect(),
+                "Nothing is due in the coming days.",
+            ),
+        ];
+
+        if format == "csv" || format == "json" {
+            let headers = ["Section", "Task", "Date"].map(String::from);
+            let rows: Vec<Vec<String>> = sections
+                .iter()
+                .flat_map(|(title, items, _)| {
+                    items.iter().map(|(text, date)| {
+                        vec![
+                            title.to_string(),
+                            text.clone(),
+                            date.map(|d| d.to_string()).unwrap_or_default(),
+                        ]
+                    })
+                })
+                .collect();
+            Self::show_report(&headers, &rows, format, false);
+            return;
+        }
+
+        let (heading, subheading) = if format == "markdown" {
+            ("## ", "### ")
+        } else {
+            ("", "")
+        };
+        println!(
+            "{}{}: status update for {} to {}",
+            heading,
+            category,
+            day(from),
+            day(to)
+        );
+        for (i, (title, items, nothing)) in sections.iter().enumerate() {
+            println!("\n{}{}", subheading, title);
+            if items.is_empty() {
+                println!("- {}", nothing);
+            }
+            for (text, date) in items {
+                match (i, date) {
+                    (_, None) => println!("- {}", text),
+                    (0, Some(date)) => println!("- {} (finished {})", text, day(*date)),
+                    (_, Some(date)) => println!("- {} (due {})", text, day(*date)),
+                }
+            }
+        }
+    }
+
+    pub fn show_templates(templates: &[TaskTemplate]) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Name"),
+            Cell::new("Task"),
+            Cell::new("Category"),
+            Cell::new("Tags"),
+            Cell::new("Priority"),
+            Cell::new("Due"),
+        ]));
+        for template in templates {
+            table.add_row(Row::new(vec![
+                Cell::new(&template.name),
+                Cell::new(&template.pattern),
+                Cell::new(template.category.as_deref().unwrap_or("General")),
+                Cell::new(&template.tags.join(", ")),
+                Cell::new(&priority::label(template.priority)),
+                Cell::new(
+                    &template
+                        .due_in
+                        .map(|days| format!("in {} days", days))
+                        .unwrap_or_default(),
+                ),
+            ]));
+        }
+        table.printstd();
+    }
+
+    pub fn show_goals(progress: &[(Goal, i64)], accessible: bool) {
+        if progress.is_empty() {
+            println!("No goals yet. Add one with `yawmak goals add`.");
+            return;
+        }
+
+        if accessible {
+            for (goal, done) in progress {
+                println!(
+                    "Goal {}: {}. {} of {} done this {}.",
+                    goal.id,
+                    goal.describe(),
+                    done,
+                    goal.target,
+                    goal.period
+                );
+            }
+            return;
+        }
+
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("ID"),
+            Cell::new("Goal"),
+            Cell::new("Progress"),
+        ]));
+        for (goal, done) in progress {
+            let mut bar = Cell::new(&format!(
+                "{} {}/{}",
+                progress_bar(*done, goal.target, 20),
+                done,
+                goal.target
+            ));
+            if *done >= goal.target {
+                bar = bar.style_spec(theme::style(Role::Good));
+            }
+            table.add_row(Row::new(vec![
+                Cell::new(&goal.id.to_string()),
+                Cell::new(&goal.describe()),
+                bar,
+            ]));
+        }
+        table.printstd();
+    }
+
+    // One column per period, most recent first; met targets are marked with a check
+    pub fn show_goal_history(history: &[(Goal, Vec<i64>)]) {
+        let periods = history.first().map_or(0, |(_, counts)| counts.len());
+        let mut table = Table::new();
+
+        let mut headers = vec![Cell::new("Goal")];
+        for index in 0..periods {
+            headers.push(Cell::new(&match index {
+                0 => "Current".to_string(),
+                n => format!("-{}", n),
+            }));
+        }
+        headers.push(Cell::new("Met"));
+        table.add_row(Row::new(headers));
+
+        for (goal, counts) in history {
+            let mut row = vec![Cell::new(&goal.describe())];
+            for count in counts {
+                let mark = if *count >= goal.target { " ✓" } else { "" };
+                row.push(Cell::new(&format!("{}{}", count, mark)));
+            }
+            // The current period is still in progress, so it doesn't count as missed
+            let finished = &counts[1.min(counts.len())..];
+            let met = finished.iter().filter(|c| **c >= goal.target).count();
+            row.push(Cell::new(&format!("{} of {}", met, finished.len())));
+            table.add_row(Row::new(row));
+        }
+        table.printstd();
+    }
+
+    pub fn show_agenda(days: &BTreeMap<NaiveDate, Vec<Task>>) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Day"),
+            Cell::new("ID"),
+            Cell::new("Name"),
+            Cell::new("Due Date"),
+            Cell::new("Priority"),
+        ]));
+
+        for (day, tasks) in days {
+            let label = day.format("%a %Y-%m-%d").to_string();
+            if tasks.is_empty() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&label),
+                    Cell::new(""),
+                    Cell::new("(free)"),
+                    Cell::new(""),
+                    Cell::new(""),
+                ]));
+            }
+            for (index, task) in tasks.iter().enumerate() {
+                table.add_row(Row::new(vec![
+                    Cell::new(if index == 0 { &label } else { "" }),
+                    Cell::new(&task.id.to_string()),
+                    Cell::new(&task.display_name()),
+                    Cell::new(
+                        &task
+                            .due_date
+                            .map(|d| d.format("%Y-%m-%d").to_string())
+                            .unwrap_or_default(),
+                    ),
+                    Cell::new(&priority::label(task.priority))
+                        .style_spec(priority::style(task.priority)),
+                ]));
+            }
+        }
+
+        table.printstd();
+    }
+
+    pub fn show_categories(categories: Vec<CategoryLoad>) {
         let mut table = Table::new();
-        table.add_row(Row::new(vec![Cell::new("Category")]));
+        table.add_row(Row::new(vec![
+            Cell::new("Category"),
+            Cell::new("Open"),
+            Cell::new("WIP Limit"),
+        ]));
         for category in categories {
-            table.add_row(Row::new(vec![Cell::new(&category)]));
+            let mut open = Cell::new(&category.open_tasks.to_string());
+            if category.is_over_limit() {
+                open = open.style_spec(theme::style(Role::Bad));
+            }
+            let name = match &category.icon {
+                Some(icon) => format!("{} {}", icon, category.name),
+                None => category.name.clone(),
+            };
+            table.add_row(Row::new(vec![
+                Cell::new(&name),
+                open,
+                Cell::new(
+                    &category
+                        .wip_limit
+                        .map(|limit| limit.to_string())
+                        .unwrap_or_default(),
+                ),
+            ]));
         }
         table.printstd();
     }
 
+    pub fn show_import_report(report: &ImportReport, detailed: bool) {
+        println!(
+            "{} inserted, {} updated, {} skipped, {} failed.",
+            report.count(ImportOutcome::Inserted),
+            report.count(ImportOutcome::Updated),
+            report.count(ImportOutcome::Skipped),
+            report.count(ImportOutcome::Failed),
+        );
+        if let Some(scope) = &report.scope {
+            println!("The file holds the '{}' scope of an export.", scope);
+        }
+        if !report.ignored_columns.is_empty() {
+            println!("Ignored columns: {}.", report.ignored_columns.join(", "));
+        }
+
+        let rows: Vec<_> = report
+            .rows
+            .iter()
+            .filter(|r| detailed || r.outcome == ImportOutcome::Failed)
+            .collect();
+        if rows.is_empty() {
+            return;
+        }
+
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Row"),
+            Cell::new("ID"),
+            Cell::new("Outcome"),
+            Cell::new("Reason"),
+        ]));
+        for row in rows {
+            let mut outcome = Cell::new(&format!("{:?}", row.outcome));
+            if row.outcome == ImportOutcome::Failed {
+                outcome = outcome.style_spec(theme::style(Role::Bad));
+            }
+            table.add_row(Row::new(vec![
+                Cell::new(&row.row.to_string()),
+                Cell::new(&row.id.map(|id| id.to_string()).unwrap_or_default()),
+                outcome,
+                Cell::new(row.reason.as_deref().unwrap_or_default()),
+            ]));
+        }
+        table.printstd();
+    }
+
+    pub fn show_operations(operations: &[Operation]) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("#"),
+            Cell::new("Started"),
+            Cell::new("Result"),
+            Cell::new("Command"),
+        ]));
+        for operation in operations {
+            // A command still marked running was cut short, since `last` itself isn't recorded
+            let result = match operation.status.as_str() {
+                "done" => Cell::new("done").style_spec(theme::style(Role::Good)),
+                "failed" => Cell::new("failed").style_spec(theme::style(Role::Bad)),
+                "running" => Cell::new("interrupted").style_spec(theme::style(Role::Warning)),
+                other => Cell::new(other),
+            };
+            table.add_row(Row::new(vec![
+                Cell::new(&operation.id.to_string()),
+                Cell::new(&operation.started_at),
+                result,
+                Cell::new(&operation.command_line()),
+            ]));
+        }
+        table.printstd();
+    }
+
+    // One row per change, with each changed field on a line of its own
+    pub fn show_history(entries: &[HistoryEntry]) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("When"),
+            Cell::new("ID"),
+            Cell::new("Task"),
+            Cell::new("Action"),
+            Cell::new("Changes"),
+        ]));
+        for entry in entries {
+            table.add_row(Row::new(vec![
+                Cell::new(&entry.recorded_at),
+                Cell::new(&entry.todo_id.to_string()),
+                Cell::new(&entry.name),
+                Cell::new(action_label(&entry.action)),
+                Cell::new(&entry.changes.join("\n")),
+            ]));
+        }
+        table.printstd();
+    }
+
+    // One task's history for `show`: "2024-09-01 18:30  updated" with the changes below
+    pub fn show_task_history(entries: &[HistoryEntry]) {
+        for entry in entries {
+            println!("{}  {}", entry.recorded_at, action_label(&entry.action));
+            for change in &entry.changes {
+                println!("    {}", change);
+            }
+        }
+    }
+
+    // One row per suspicious date found by `audit dates`
+    pub fn show_date_issues(issues: &[(&Task, DateIssue)]) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("ID"),
+            Cell::new("Task"),
+            Cell::new("Problem"),
+        ]));
+        for (task, issue) in issues {
+            table.add_row(Row::new(vec![
+                Cell::new(&task.id.to_string()),
+                Cell::new(&task.name),
+                Cell::new(&issue.problem).style_spec(theme::style(Role::Warning)),
+            ]));
+        }
+        table.printstd();
+    }
+
+    pub fn show_export_runs(runs: &[ExportRun]) {
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![
+            Cell::new("Preset"),
+            Cell::new("Last Run"),
+            Cell::new("Result"),
+            Cell::new("Detail"),
+        ]));
+        for run in runs {
+            let result = if run.ok {
+                Cell::new("ok").style_spec(theme::style(Role::Good))
+            } else {
+                Cell::new("failed").style_spec(theme::style(Role::Bad))
+            };
+            table.add_row(Row::new(vec![
+                Cell::new(&run.preset),
+                Cell::new(&run.ran_at),
+                result,
+                Cell::new(&run.detail),
+            ]));
+        }
+        table.printstd();
+    }
+
+    // One block per comment, oldest first: "2024-09-01 18:30  Alice" then the text
+    pub fn show_comments(comments: &[Comment]) {
+        for comment in comments {
+            println!(
+                "{}  {}",
+                comment.created_at,
+                comment.author.as_deref().unwrap_or("Unknown")
+            );
+            for line in comment.body.lines() {
+                println!("    {}", line);
+            }
+            println!();
+        }
+    }
+
+    pub fn show_notes(notes: &[Note]) {
+        for note in notes {
+            println!("Note {}  {}", note.id, note.created_at);
+            for line in note.body.lines() {
+                println!("    {}", line);
+            }
+            println!();
+        }
+    }
+
+    /// Prints every field of a task that has a value, one "Label: value"
+    /// line each under a "Task 3: name" heading, followed by `extra` lines
+    /// like reminders and tracked time.
+    pub fn show_task_detail(task: &Task, extra: &[(String, String)]) {
+        let icon = task
+            .icon
+            .as_ref()
+            .or(task.category_icon.as_ref())
+            .map(|icon| format!("{} ", icon))
+            .unwrap_or_default();
+        println!("Task {}: {}{}", task.id, icon, task.name);
+
+        let mut rows = detail_rows(task, Local::now().date_naive());
+        rows.extend(extra.iter().cloned());
+        let width = rows
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        for (label, value) in rows {
+            println!(
+                "  {:<width$}  {}",
+                format!("{}:", label),
+                value,
+                width = width
+            );
+        }
+    }
+
+    pub fn show_annotations(annotations: &[Annotation]) {
+        for annotation in annotations {
+            println!("{}  {}", annotation.created_at, annotation.body);
+        }
+    }
+
+    // One "[x] id  name" line per task, indented by how deeply it is nested
+    pub fn show_checklist(tasks: &[(usize, Task)]) {
+        for (depth, task) in tasks {
+            println!(
+                "{}[{}] {}  {}",
+                "    ".repeat(*depth),
+                if task.done { "x" } else { " " },
+                task.id,
+                task.name
+            );
+        }
+    }
+
+    pub fn show_completion_times(by: &str, times: &[CompletionTimes], format: &str) {
+        let headers = [
+            if by == "priority" {
+                "Priority"
+            } else {
+                "Category"
+            },
+            "Tasks",
+            "Median Days",
+            "90th Percentile",
+            "Finished Late",
+        ];
+        let rows: Vec<Vec<String>> = times
+            .iter()
+            .map(|times| {
+                vec![
+                    times.group.clone().unwrap_or_else(|| "All".to_string()),
+                    times.tasks.to_string(),
+                    format!("{:.1}", times.median),
+                    format!("{:.1}", times.p90),
+                    times
+                        .late
+                        .map(|late| format!("{:.0}%", late * 100.0))
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+        Self::show_report(&headers.map(String::from), &rows, format, true);
+    }
+
+    // Each task's estimate and tracked time, with how far off the estimate was
+    pub fn show_estimate_accuracy(tasks: &[EstimateAccuracy], format: &str) {
+        let headers = ["ID", "Task", "Done", "Estimate", "Tracked", "Off By"];
+        let off_by = |estimate: i64, tracked: i64| {
+            format!(
+                "{:+.0}%",
+                (tracked - estimate) as f64 * 100.0 / estimate.max(1) as f64
+            )
+        };
+        let mut rows: Vec<Vec<String>> = tasks
+            .iter()
+            .map(|task| {
+                vec![
+                    task.id.to_string(),
+                    task.name.clone(),
+                    task.done.to_string(),
+                    dates::describe_minutes(task.estimate),
+                    dates::describe_minutes(task.tracked),
+                    off_by(task.estimate, task.tracked),
+                ]
+            })
+            .collect();
+        let (estimate, tracked) = (
+            tasks.iter().map(|t| t.estimate).sum(),
+            tasks.iter().map(|t| t.tracked).sum(),
+        );
+        rows.push(vec![
+            "Total".to_string(),
+            String::new(),
+            String::new(),
+            dates::describe_minutes(estimate),
+            dates::describe_minutes(tracked),
+            off_by(estimate, tracked),
+        ]);
+        Self::show_report(&headers.map(String::from), &rows, format, true);
+    }
+
+    pub fn show_pomodoros(counts: &[PomodoroCount], format: &str) {
+        let headers = ["ID", "Task", "Pomodoros", "Time"];
+        let mut rows: Vec<Vec<String>> = counts
+            .iter()
+            .map(|count| {
+                vec![
+                    count.id.to_string(),
+                    count.name.clone(),
+                    count.pomodoros.to_string(),
+                    dates::describe_minutes(count.minutes),
+                ]
+            })
+            .collect();
+        rows.push(vec![
+            "Total".to_string(),
+            String::new(),
+            counts.iter().map(|c| c.pomodoros).sum::<i64>().to_string(),
+            dates::describe_minutes(counts.iter().map(|c| c.minutes).sum()),
+        ]);
+        Self::show_report(&headers.map(String::from), &rows, format, true);
+    }
+
+    // The pivot counts with a Total column and a Total row added
+    pub fn show_pivot(pivot: &PivotTable, format: &str) {
+        let mut headers = pivot.headers.clone();
+        headers.push("Total".to_string());
+
+        let mut rows = Vec::new();
+        let mut column_totals = vec![0; pivot.headers.len().saturating_sub(1)];
+        for values in &pivot.rows {
+            let counts: Vec<i64> = values[1..].iter().map(|v| v.parse().unwrap_or(0)).collect();
+            for (total, count) in column_totals.iter_mut().zip(&counts) {
+                *total += count;
+            }
+            let mut row = vec![values[0].clone()];
+            row.extend(counts.iter().map(|c| c.to_string()));
+            row.push(counts.iter().sum::<i64>().to_string());
+            rows.push(row);
+        }
+
+        let mut totals = vec!["Total".to_string()];
+        totals.extend(column_totals.iter().map(|t| t.to_string()));
+        totals.push(column_totals.iter().sum::<i64>().to_string());
+        rows.push(totals);
+        Self::show_report(&headers, &rows, format, true);
+    }
+
+    /// Prints report rows as a table, or as csv, json (an array of objects
+    /// keyed by header) or markdown for use elsewhere. With `totals` the
+    /// last row is set in bold in the table.
+    pub fn show_report(headers: &[String], rows: &[Vec<String>], format: &str, totals: bool) {
+        match format {
+            "csv" => {
+                println!(
+                    "{}",
+                    headers
+                        .iter()
+                        .map(|h| csv_field(h))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                );
+                for row in rows {
+                    println!(
+                        "{}",
+                        row.iter()
+                            .map(|v| csv_field(v))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    );
+                }
+            }
+            "json" => {
+                let objects: Vec<serde_json::Value> = rows
+                    .iter()
+                    .map(|row| {
+                        headers
+                            .iter()
+                            .zip(row)
+                            .map(|(header, value)| (header.clone(), json_value(value)))
+                            .collect::<serde_json::Map<_, _>>()
+                            .into()
+                    })
+                    .collect();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&objects).unwrap_or_default()
+                );
+            }
+            "markdown" => {
+                println!("| {} |", headers.join(" | "));
+                println!("|{}", "---|".repeat(headers.len()));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|v| v.replace('|', "\\|")).collect();
+                    println!("| {} |", cells.join(" | "));
+                }
+            }
+            _ => {
+                let mut table = Table::new();
+                table.add_row(Row::new(headers.iter().map(|h| Cell::new(h)).collect()));
+                for (i, row) in rows.iter().enumerate() {
+                    let style = if totals && i + 1 == rows.len() {
+                        "b"
+                    } else {
+                        ""
+                    };
+                    table.add_row(Row::new(
+                        row.iter().map(|v| Cell::new(v).style_spec(style)).collect(),
+                    ));
+                }
+                table.printstd();
+            }
+        }
+    }
+
     pub fn show_tags(tags: Vec<String>) {
         let mut table = Table::new();
         table.add_row(Row::new(vec![Cell::new("Tag")]));
@@ -74,4 +1029,233 @@ impl Display {
         }
         table.printstd();
     }
+
+    pub fn show_names_accessible(label: &str, names: Vec<String>) {
+        if names.is_empty() {
+            println!("No {}s found.", label.to_lowercase());
+            return;
+        }
+        for name in names {
+            println!("{}: {}.", label, name);
+        }
+    }
+}
+
+fn category_cell(task: &Task, columns: &TaskColumns) -> Cell {
+    let category = task.category.clone().unwrap_or_default();
+    let cell = Cell::new(&category);
+    if columns.over_limit_categories.contains(&category) {
+        cell.style_spec(theme::style(Role::Bad))
+    } else {
+        cell
+    }
+}
+
+// One bar per value, from ▁ for none up to █ for the largest; any value
+// above zero gets at least ▂
+fn sparkline(values: &[i64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|&v| BARS[((v.max(0) * 7 + max - 1) / max).min(7) as usize])
+        .collect()
+}
+
+fn progress_bar(done: i64, target: i64, width: i64) -> String {
+    let filled = if target > 0 {
+        (done * width / target).min(width)
+    } else {
+        width
+    };
+    format!(
+        "{}{}",
+        "█".repeat(filled as usize),
+        "░".repeat((width - filled) as usize)
+    )
+}
+
+// One dot for fresh tasks, two past a third of the stale threshold, three once stale
+fn age_marker(age_days: i64, stale_after: i64) -> &'static str {
+    if age_days >= stale_after {
+        "···"
+    } else if age_days * 3 >= stale_after {
+        "··"
+    } else {
+        "·"
+    }
+}
+
+// Describes a date relative to today, falling back to a spelled-out date
+fn describe_date(date: NaiveDate, today: NaiveDate) -> String {
+    match (date - today).num_days() {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        days @ 2..=6 => format!("in {} days", days),
+        days @ -6..=-2 => format!("{} days ago", -days),
+        _ => date.format("%A, %B %-d, %Y").to_string(),
+    }
+}
+
+// The labelled fields of `show_task_detail`, leaving out those without a value
+fn detail_rows(task: &Task, today: NaiveDate) -> Vec<(String, String)> {
+    let date = |date: NaiveDate| match (date - today).num_days() {
+        -6..=6 => format!("{} ({})", date, describe_date(date, today)),
+        _ => date.format("%Y-%m-%d (%A)").to_string(),
+    };
+    let by = |who: &Option<String>| {
+        who.as_ref()
+            .map(|who| format!(" by {}", who))
+            .unwrap_or_default()
+    };
+
+    let status = match task.completion_date {
+        _ if !task.done => match task.due_date {
+            Some(due) if due < today => "open, overdue".to_string(),
+            _ => "open".to_string(),
+        },
+        Some(completed) => format!("done on {}{}", completed, by(&task.completed_by)),
+        None => format!("done{}", by(&task.completed_by)),
+    };
+    let mut rows = vec![("Status".to_string(), status)];
+    let mut add = |label: &str, value: Option<String>| {
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            rows.push((label.to_string(), value));
+        }
+    };
+    add("Category", task.category.clone());
+    add("Tags", Some(task.tags.join(", ")));
+    add("Priority", Some(priority::label(task.priority)));
+    add("Due", task.due_date.map(date));
+    add("Starts", task.start_date.map(date));
+    add("Scheduled", task.scheduled_date.map(date));
+    add("Follow up", task.follow_up.map(date));
+    add("Context", task.context.clone());
+    add("Assignee", task.assignee.clone());
+    add("Waiting for", task.waiting_for.clone());
+    add("Repeats", task.repeat.clone());
+    add("Estimate", task.estimate.map(dates::describe_minutes));
+    add(
+        "Subtask of",
+        task.parent_id.map(|id| format!("task {}", id)),
+    );
+    add("Pinned", task.pinned.then(|| "yes".to_string()));
+    add(
+        "Added",
+        task.created_at
+            .map(|created| format!("{}{}", created, by(&task.created_by))),
+    );
+    for (name, value) in &task.attributes {
+        add(name, Some(value.clone()));
+    }
+    rows
+}
+
+// How `history` and `show` name a journaled action
+fn action_label(action: &str) -> &str {
+    match action {
+        "add" => "added",
+        "update" => "updated",
+        "delete" => "deleted",
+        "undo" => "undone",
+        other => other,
+    }
+}
+
+// Quotes a CSV field when it holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Numbers stay numbers in JSON output; empty cells become null
+fn json_value(value: &str) -> serde_json::Value {
+    if value.is_empty() {
+        serde_json::Value::Null
+    } else if let Ok(number) = value.parse::<i64>() {
+        number.into()
+    } else if let Ok(number) = value.parse::<f64>() {
+        number.into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_date() {
+        let today = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+
+        assert_eq!(describe_date(today, today), "today");
+        assert_eq!(
+            describe_date(NaiveDate::from_ymd_opt(2024, 9, 3).unwrap(), today),
+            "tomorrow"
+        );
+        assert_eq!(
+            describe_date(NaiveDate::from_ymd_opt(2024, 8, 30).unwrap(), today),
+            "3 days ago"
+        );
+        assert_eq!(
+            describe_date(NaiveDate::from_ymd_opt(2024, 12, 25).unwrap(), today),
+            "Wednesday, December 25, 2024"
+        );
+    }
+
+    #[test]
+    fn test_detail_rows_leave_out_empty_fields() {
+        let today = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let mut task = Task::new(
+            "Send invoice",
+            "Work".to_string(),
+            Some("2024-09-01".to_string()),
+            vec!["billing".to_string(), "q3".to_string()],
+            3,
+        );
+        task.estimate = Some(90);
+        task.attributes
+            .insert("client".to_string(), "ACME".to_string());
+
+        let rows = detail_rows(&task, today);
+        let labels: Vec<&str> = rows.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(
+            labels,
+            ["Status", "Category", "Tags", "Priority", "Due", "Estimate", "client"]
+        );
+        assert_eq!(rows[0].1, "open, overdue");
+        assert_eq!(rows[2].1, "billing, q3");
+        assert_eq!(rows[4].1, "2024-09-01 (yesterday)");
+
+        task.done = true;
+        task.completion_date = Some(today);
+        task.completed_by = Some("Sam".to_string());
+        assert_eq!(detail_rows(&task, today)[0].1, "done on 2024-09-02 by Sam");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0, 1, 4, 7, 0]), "▁▂▅█▁");
+        assert_eq!(sparkline(&[0, 0, 0]), "▁▁▁");
+        assert_eq!(sparkline(&[3, 3]), "██");
+    }
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(0, 4, 4), "░░░░");
+        assert_eq!(progress_bar(2, 4, 4), "██░░");
+        assert_eq!(progress_bar(9, 4, 4), "████");
+    }
+
+    #[test]
+    fn test_age_marker() {
+        assert_eq!(age_marker(0, 30), "·");
+        assert_eq!(age_marker(10, 30), "··");
+        assert_eq!(age_marker(45, 30), "···");
+    }
 }
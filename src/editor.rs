@@ -0,0 +1,139 @@
+use crate::error::TodoError;
+use crate::task::Task;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, fs, process};
+
+const HEADER: &str = "# Edit the task, then save and close the editor to apply the changes.\n\
+                      # Remove the due_date or context line to clear it.\n\n";
+
+// The fields of a task that `edit` lets you change
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct TaskFields {
+    pub task: String,
+    pub due_date: Option<NaiveDate>,
+    pub category: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub priority: i32,
+    pub context: Option<String>,
+}
+
+impl TaskFields {
+    pub fn of(task: &Task) -> Self {
+        TaskFields {
+            task: task.name.clone(),
+            due_date: task.due_date,
+            category: task
+                .category
+                .clone()
+                .unwrap_or_else(|| "General".to_string()),
+            tags: task.tags.clone(),
+            priority: task.priority,
+            context: task.context.clone(),
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        format!("{}{}", HEADER, toml::to_string(self).unwrap_or_default())
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, TodoError> {
+        toml::from_str(text)
+            .map_err(|e| TodoError::Custom(format!("Could not read the task: {}", e)))
+    }
+}
+
+/// Writes `text` to a new temporary file named after `prefix` and
+/// `extension`, opens it in `$VISUAL` or `$EDITOR` (falling back to vi) and
+/// returns what was saved along with the file's path. The caller removes the
+/// file, so edits that can't be applied aren't lost.
+pub fn edit_text(
+    prefix: &str,
+    extension: &str,
+    text: &str,
+) -> Result<(String, PathBuf), TodoError> {
+    let (path, mut file) = create_temp_file(prefix, extension)?;
+    file.write_all(text.as_bytes())?;
+    drop(file);
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // The editor may come with arguments, like "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .map_err(|e| {
+            TodoError::Custom(format!("Could not start the editor '{}': {}", editor, e))
+        })?;
+    if !status.success() {
+        return Err(TodoError::Custom(format!(
+            "The editor '{}' exited without saving.",
+            editor
+        )));
+    }
+    Ok((fs::read_to_string(&path)?, path))
+}
+
+// Creates a file in the temporary directory under a name no other file has,
+// readable only by the current user, so nobody else can read or swap it
+fn create_temp_file(prefix: &str, extension: &str) -> Result<(PathBuf, fs::File), TodoError> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    for attempt in 0..100u32 {
+        let path = env::temp_dir().join(format!(
+            "{}-{}-{:08x}.{}",
+            prefix,
+            process::id(),
+            nanos.wrapping_add(attempt.wrapping_mul(0x9e37_79b9)),
+            extension
+        ));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Err(TodoError::Custom(
+        "Could not create a temporary file for the editor.".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_survive_a_round_trip() {
+        let mut task = Task::new(
+            "Write the \"annual\" report",
+            "Work".to_string(),
+            Some("2025-03-05".to_string()),
+            vec!["writing".to_string()],
+            2,
+        );
+        task.context = Some("office".to_string());
+        let fields = TaskFields::of(&task);
+
+        assert_eq!(TaskFields::from_toml(&fields.to_toml()).unwrap(), fields);
+
+        let cleared = TaskFields::from_toml("task = \"Call Ann\"\ncategory = \"Home\"\n").unwrap();
+        assert_eq!(cleared.due_date, None);
+        assert!(cleared.tags.is_empty());
+    }
+}
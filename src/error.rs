@@ -1,4 +1,3 @@
-use duckdb;
 use std::fmt;
 use std::io;
 
@@ -7,6 +6,8 @@ pub enum TodoError {
     DuckDB(duckdb::Error),
     Io(io::Error),
     Custom(String),
+    // A failure already explained to the user, which only has to end the command as failed
+    Reported(String),
 }
 
 impl fmt::Display for TodoError {
@@ -22,6 +23,7 @@ impl fmt::Display for TodoError {
             },
             TodoError::Io(err) => write!(f, "There was an input/output error: {}. Please check your file paths and permissions.", err),
             TodoError::Custom(msg) => write!(f, "{}", msg),
+            TodoError::Reported(msg) => write!(f, "{}", msg),
         }
     }
 }
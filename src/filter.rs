@@ -0,0 +1,149 @@
+use crate::dates;
+use crate::priority;
+use crate::task::Task;
+use chrono::NaiveDate;
+
+// The keys a filter term can use, besides the custom fields from the config
+const KEYS: &[&str] = &[
+    "category",
+    "tag",
+    "context",
+    "assignee",
+    "priority",
+    "done",
+    "name",
+    "due",
+    "due.before",
+    "due.after",
+];
+
+// One `key:value` condition of a filter
+#[derive(Debug, PartialEq)]
+enum Term {
+    Category(String),
+    Tag(String),
+    Context(String),
+    Assignee(String),
+    Priority(i32),
+    Done(bool),
+    // The name contains this text
+    Name(String),
+    Due(NaiveDate),
+    DueBefore(NaiveDate),
+    DueAfter(NaiveDate),
+    Attribute(String, String),
+}
+
+/// Picks tasks with terms like `category:Work due.before:2025-01-01`, all
+/// of which have to hold. Text is matched ignoring case, and dates can be
+/// written any way a due date can, as long as they are one word.
+#[derive(Debug)]
+pub struct Filter {
+    terms: Vec<Term>,
+}
+
+impl Filter {
+    /// Reads the terms of `text`. `attributes` are the custom fields that can
+    /// be used as keys, like `client:ACME`.
+    pub fn parse(text: &str, today: NaiveDate, attributes: &[String]) -> Result<Filter, String> {
+        let mut terms = Vec::new();
+        for word in text.split_whitespace() {
+            let Some((key, value)) = word.split_once(':').filter(|(_, v)| !v.is_empty()) else {
+                return Err(format!(
+                    "'{}' isn't a filter term; use one like category:Work.",
+                    word
+                ));
+            };
+            let date = || {
+                dates::parse(value, today, today).ok_or_else(|| {
+                    format!("'{}' in '{}' isn't a date yawmak understands.", value, word)
+                })
+            };
+            terms.push(match key {
+                "category" => Term::Category(value.to_string()),
+                "tag" => Term::Tag(value.to_string()),
+                "context" => Term::Context(value.to_string()),
+                "assignee" => Term::Assignee(value.to_string()),
+                "priority" => Term::Priority(priority::parse(value)?),
+                "done" => Term::Done(match value {
+                    "true" | "yes" => true,
+                    "false" | "no" => false,
+                    _ => return Err(format!("'{}' needs true or false.", word)),
+                }),
+                "name" => Term::Name(value.to_lowercase()),
+                "due" => Term::Due(date()?),
+                "due.before" => Term::DueBefore(date()?),
+                "due.after" => Term::DueAfter(date()?),
+                _ if attributes.iter().any(|a| a == key) => {
+                    Term::Attribute(key.to_string(), value.to_string())
+                }
+                _ => {
+                    return Err(format!(
+                        "Unknown filter key '{}'. Use {} or a custom field.",
+                        key,
+                        KEYS.join(", ")
+                    ))
+                }
+            });
+        }
+        if terms.is_empty() {
+            return Err("The filter is empty; give at least one term like category:Work.".into());
+        }
+        Ok(Filter { terms })
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        let same = |text: &Option<String>, value: &str| {
+            text.as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(value))
+        };
+        self.terms.iter().all(|term| match term {
+            Term::Category(category) => same(&task.category, category),
+            Term::Tag(tag) => task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            Term::Context(context) => same(&task.context, context),
+            Term::Assignee(who) => same(&task.assignee, who),
+            Term::Priority(level) => task.priority == *level,
+            Term::Done(done) => task.done == *done,
+            Term::Name(text) => task.name.to_lowercase().contains(text),
+            Term::Due(date) => task.due_date == Some(*date),
+            Term::DueBefore(date) => task.due_date.is_some_and(|d| d < *date),
+            Term::DueAfter(date) => task.due_date.is_some_and(|d| d > *date),
+            Term::Attribute(name, value) => task
+                .attributes
+                .get(name)
+                .is_some_and(|v| v.eq_ignore_ascii_case(value)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_matches_every_term() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let attributes = vec!["client".to_string()];
+        let mut task = Task::new(
+            "Send invoice",
+            "Work".to_string(),
+            Some("2024-12-20".to_string()),
+            vec!["billing".to_string()],
+            2,
+        );
+        task.attributes
+            .insert("client".to_string(), "ACME".to_string());
+
+        let filter = |text: &str| Filter::parse(text, today, &attributes).unwrap();
+        assert!(filter("category:work due.before:2025-01-01").matches(&task));
+        assert!(filter("tag:Billing priority:medium client:acme").matches(&task));
+        assert!(filter("name:invoice done:false").matches(&task));
+        assert!(!filter("category:Work due.after:2025-01-01").matches(&task));
+        assert!(!filter("category:Home").matches(&task));
+
+        assert!(Filter::parse("", today, &attributes).is_err());
+        assert!(Filter::parse("category", today, &attributes).is_err());
+        assert!(Filter::parse("owner:me", today, &attributes).is_err());
+        assert!(Filter::parse("due.before:someday", today, &attributes).is_err());
+    }
+}
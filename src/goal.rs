@@ -0,0 +1,75 @@
+use chrono::{Datelike, Duration, Months, NaiveDate};
+
+// A target number of completed tasks in a category or with a tag per period
+pub struct Goal {
+    pub id: i32,
+    // Either "category" or "tag"
+    pub kind: String,
+    pub name: String,
+    pub target: i64,
+    // Either "week" or "month"
+    pub period: String,
+}
+
+impl Goal {
+    pub fn period_bounds(&self, date: NaiveDate, periods_ago: u32) -> (NaiveDate, NaiveDate) {
+        period_bounds(&self.period, date, periods_ago)
+    }
+
+    pub fn describe(&self) -> String {
+        match self.kind.as_str() {
+            "tag" => format!("{} +{} per {}", self.target, self.name, self.period),
+            _ => format!("{} {} per {}", self.target, self.name, self.period),
+        }
+    }
+}
+
+/// Returns the first and last day of the week or month containing `date`,
+/// moved back by `periods_ago` periods. Weeks start on Monday.
+pub fn period_bounds(period: &str, date: NaiveDate, periods_ago: u32) -> (NaiveDate, NaiveDate) {
+    match period {
+        "month" => {
+            let start = date.with_day(1).unwrap() - Months::new(periods_ago);
+            (start, start + Months::new(1) - Duration::days(1))
+        }
+        _ => {
+            let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+            let start = monday - Duration::weeks(periods_ago as i64);
+            (start, start + Duration::days(6))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn goal(period: &str) -> Goal {
+        Goal {
+            id: 1,
+            kind: "tag".to_string(),
+            name: "exercise".to_string(),
+            target: 5,
+            period: period.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_week_bounds_start_on_monday() {
+        let wednesday = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let (start, end) = goal("week").period_bounds(wednesday, 0);
+        assert_eq!(start, NaiveDate::from_ymd_opt(2025, 3, 3).unwrap());
+        assert_eq!(end, NaiveDate::from_ymd_opt(2025, 3, 9).unwrap());
+
+        let (start, _) = goal("week").period_bounds(wednesday, 2);
+        assert_eq!(start, NaiveDate::from_ymd_opt(2025, 2, 17).unwrap());
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        let date = NaiveDate::from_ymd_opt(2025, 3, 31).unwrap();
+        let (start, end) = goal("month").period_bounds(date, 1);
+        assert_eq!(start, NaiveDate::from_ymd_opt(2025, 2, 1).unwrap());
+        assert_eq!(end, NaiveDate::from_ymd_opt(2025, 2, 28).unwrap());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::task::{references, Task};
+use crate::theme;
+use std::collections::HashSet;
+
+pub struct Graph;
+
+impl Graph {
+    /// Draws the tasks as a Mermaid flowchart. Subtasks hang off their parent
+    /// with a solid arrow, a task naming another as `#<id>` points to it with
+    /// a dotted arrow, and related tasks are joined by a plain line. Links to
+    /// tasks that aren't drawn are left out, and done tasks are greyed.
+    pub fn mermaid(tasks: &[Task], relations: &[(i32, i32)]) -> String {
+        let ids: HashSet<i32> = tasks.iter().map(|t| t.id).collect();
+        let mut lines = vec!["flowchart TD".to_string()];
+
+        for task in tasks {
+            lines.push(format!(
+                "    t{}[\"{} {}\"]",
+                task.id,
+                task.id,
+                label(&task.name)
+            ));
+        }
+        for task in tasks {
+            if let Some(parent_id) = task.parent_id.filter(|p| ids.contains(p)) {
+                lines.push(format!("    t{} --> t{}", parent_id, task.id));
+            }
+        }
+        for task in tasks {
+            for id in references(&task.name) {
+                if id != task.id && ids.contains(&id) {
+                    lines.push(format!("    t{} -.-> t{}", task.id, id));
+                }
+            }
+        }
+        for (a, b) in relations {
+            if ids.contains(a) && ids.contains(b) {
+                lines.push(format!("    t{} --- t{}", a, b));
+            }
+        }
+
+        let done: Vec<String> = tasks
+            .iter()
+            .filter(|t| t.done)
+            .map(|t| format!("t{}", t.id))
+            .collect();
+        if !done.is_empty() {
+            lines.push(format!("    classDef done {}", theme::chart_done_style()));
+            lines.push(format!("    class {} done", done.join(",")));
+        }
+        lines.join("\n")
+    }
+}
+
+// Mermaid reads quotes and brackets in a label as syntax, so they are written as entities
+fn label(name: &str) -> String {
+    name.replace('"', "#quot;")
+        .replace('[', "#91;")
+        .replace(']', "#93;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: i32, name: &str, parent_id: Option<i32>, done: bool) -> Task {
+        let mut task = Task::new(name, "General".to_string(), None, vec![], 0);
+        task.id = id;
+        task.parent_id = parent_id;
+        task.done = done;
+        task
+    }
+
+    #[test]
+    fn test_mermaid_draws_subtasks_references_and_relations() {
+        let tasks = vec![
+            task(1, "Plan \"the\" trip", None, false),
+            task(2, "Pack", Some(1), true),
+            task(3, "Ask about #1 and #9", None, false),
+        ];
+
+        let chart = Graph::mermaid(&tasks, &[(2, 3), (3, 8)]);
+
+        assert_eq!(
+            chart,
+            "flowchart TD\n\
+             \x20   t1[\"1 Plan #quot;the#quot; trip\"]\n\
+             \x20   t2[\"2 Pack\"]\n\
+             \x20   t3[\"3 Ask about #1 and #9\"]\n\
+             \x20   t1 --> t2\n\
+             \x20   t3 -.-> t1\n\
+             \x20   t2 --- t3\n\
+             \x20   classDef done fill:#eee,color:#888\n\
+             \x20   class t2 done"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use crate::priority;
+use crate::task::Task;
+use chrono::NaiveDate;
+use std::collections::BTreeSet;
+
+/// What changed between two states of a task, one "field: old → new" line
+/// per changed field. Fields without a value show as "-".
+pub fn changes(before: &Task, after: &Task) -> Vec<String> {
+    let date = |date: Option<NaiveDate>| {
+        date.map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let text = |text: &Option<String>| text.clone().unwrap_or_default();
+    let fields = [
+        ("name", before.name.clone(), after.name.clone()),
+        ("category", text(&before.category), text(&after.category)),
+        ("tags", before.tags.join(", "), after.tags.join(", ")),
+        ("due", date(before.due_date), date(after.due_date)),
+        (
+            "priority",
+            priority::label(before.priority),
+            priority::label(after.priority),
+        ),
+        ("context", text(&before.context), text(&after.context)),
+        ("start", date(before.start_date), date(after.start_date)),
+        ("repeat", text(&before.repeat), text(&after.repeat)),
+        ("assignee", text(&before.assignee), text(&after.assignee)),
+        (
+            "waiting for",
+            text(&before.waiting_for),
+            text(&after.waiting_for),
+        ),
+        ("icon", text(&before.icon), text(&after.icon)),
+        (
+            "estimate",
+            before.estimate.map(|m| m.to_string()).unwrap_or_default(),
+            after.estimate.map(|m| m.to_string()).unwrap_or_default(),
+        ),
+        (
+            "parent",
+            before
+                .parent_id
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+            after.parent_id.map(|id| id.to_string()).unwrap_or_default(),
+        ),
+    ];
+
+    let shown = |value: &str| {
+        if value.is_empty() {
+            "-".to_string()
+        } else {
+            value.to_string()
+        }
+    };
+    let mut lines: Vec<String> = fields
+        .iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| format!("{}: {} → {}", field, shown(old), shown(new)))
+        .collect();
+
+    let names: BTreeSet<&String> = before
+        .attributes
+        .keys()
+        .chain(after.attributes.keys())
+        .collect();
+    for name in names {
+        let (old, new) = (before.attributes.get(name), after.attributes.get(name));
+        if old != new {
+            lines.push(format!(
+                "{}: {} → {}",
+                name,
+                shown(old.map(String::as_str).unwrap_or_default()),
+                shown(new.map(String::as_str).unwrap_or_default())
+            ));
+        }
+    }
+
+    if before.done != after.done {
+        lines.push(
+            if after.done {
+                "marked done"
+            } else {
+                "marked not done"
+            }
+            .to_string(),
+        );
+    }
+    if before.pinned != after.pinned {
+        lines.push(if after.pinned { "pinned" } else { "unpinned" }.to_string());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_lists_each_changed_field() {
+        let before = Task::new(
+            "Pay rent",
+            "Home".to_string(),
+            None,
+            vec!["bills".to_string()],
+            1,
+        );
+        let mut after = before.clone();
+        assert!(changes(&before, &after).is_empty());
+
+        after.priority = 3;
+        after.due_date = NaiveDate::from_ymd_opt(2025, 3, 1);
+        after
+            .attributes
+            .insert("client".to_string(), "ACME".to_string());
+        after.done = true;
+
+        assert_eq!(
+            changes(&before, &after),
+            vec![
+                "due: - → 2025-03-01",
+                "priority: low → high",
+                "client: - → ACME",
+                "marked done",
+            ]
+        );
+    }
+}
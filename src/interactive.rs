@@ -0,0 +1,270 @@
+use crate::database::Database;
+use crate::dates;
+use crate::error::TodoError;
+use crate::priority;
+use crate::prompt;
+use crate::task::{references, Task};
+use crate::theme;
+use chrono::Local;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{execute, queue};
+use std::io::{self, Stdout, Write};
+
+const HELP: &str = "up/down move  space done/undone  +/- priority  e edit  q quit";
+
+pub struct Interactive;
+
+impl Interactive {
+    /// Shows `tasks` with a cursor that moves with the arrow keys (or j/k).
+    /// Space toggles the selected task between done and pending, + and -
+    /// change its priority and e edits its name, due date and priority.
+    /// Tasks marked done are recorded as completed by `done_by`, and the
+    /// tasks finished during one session are related to each other.
+    pub fn run(
+        conn: &Database,
+        mut tasks: Vec<Task>,
+        done_by: Option<&str>,
+    ) -> Result<(), TodoError> {
+        if tasks.is_empty() {
+            println!("No tasks to show.");
+            return Ok(());
+        }
+
+        let pending: Vec<i32> = tasks.iter().filter(|t| !t.done).map(|t| t.id).collect();
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, Hide)?;
+        let result = Self::event_loop(conn, &mut tasks, done_by, &mut stdout);
+        // Always hand the terminal back, even when an update failed
+        execute!(stdout, Show, MoveTo(0, 0), Clear(ClearType::All))?;
+        terminal::disable_raw_mode()?;
+        result?;
+
+        // Tasks finished in one session are related, to help find the context later
+        let finished: Vec<i32> = tasks
+            .iter()
+            .filter(|t| t.done && pending.contains(&t.id))
+            .map(|t| t.id)
+            .collect();
+        conn.group_completed(&finished)
+    }
+
+    fn event_loop(
+        conn: &Database,
+        tasks: &mut [Task],
+        done_by: Option<&str>,
+        stdout: &mut Stdout,
+    ) -> Result<(), TodoError> {
+        let mut selected = 0;
+        let mut message = String::new();
+        loop {
+            Self::draw(stdout, tasks, selected, &message)?;
+            message.clear();
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            let task = &mut tasks[selected];
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    selected = (selected + 1).min(tasks.len() - 1)
+                }
+                KeyCode::Char(' ') => {
+                    if task.done {
+                        conn.reopen_task(task.id)?;
+                        task.done = false;
+                        task.completion_date = None;
+                        task.completed_by = None;
+                        message = format!("Task {} is pending again.", task.id);
+                    } else {
+                        let completion = conn.complete_tasks(&[task.id], done_by)?;
+                        task.done = true;
+                        task.completed_by = done_by.map(String::from);
+                        task.completion_date = Some(Local::now().date_naive());
+                        message = match completion.next.first() {
+                            Some(next) => format!(
+                                "Task {} marked as done; it comes back as task {}.",
+                                task.id, next.id
+                            ),
+                            None => format!("Task {} marked as done.", task.id),
+                        };
+                    }
+                }
+                KeyCode::Char('+') | KeyCode::Char('-') => {
+                    let priority = if key.code == KeyCode::Char('+') {
+                        task.priority.max((task.priority + 1).min(priority::MAX))
+                    } else {
+                        (task.priority - 1).max(0)
+                    };
+                    conn.journal("update", &[task.id])?;
+                    conn.update_task(
+                        task.id,
+                        None,
+                        None,
+                        None,
+                        vec![],
+                        Some(priority),
+                        None,
+                        false,
+                    )?;
+                    task.priority = priority;
+                }
+                KeyCode::Char('e') => {
+                    message = Self::edit(conn, task, stdout)?;
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(())
+                }
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(
+        stdout: &mut Stdout,
+        tasks: &[Task],
+        selected: usize,
+        message: &str,
+    ) -> Result<(), TodoError> {
+        // Keep the selected row on screen, leaving room for the header and footer
+        let visible = match terminal::size()? {
+            (_, 0) => tasks.len(),
+            (_, rows) => (rows as usize).saturating_sub(4).max(1),
+        };
+        let first = (selected + 1).saturating_sub(visible);
+
+        queue!(
+            stdout,
+            MoveTo(0, 0),
+            Clear(ClearType::All),
+            Print(format!(
+                "{:>4}  {:<4}  {:>8}  {:<10}  {}\r\n",
+                "ID", "Done", "Priority", "Due Date", "Name"
+            ))
+        )?;
+        for (i, task) in tasks.iter().enumerate().skip(first).take(visible) {
+            if i == selected {
+                queue!(stdout, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(
+                stdout,
+                Print(format!(
+                    "{:>4}  {:<4}  ",
+                    task.id,
+                    if task.done { "[x]" } else { "[ ]" },
+                ))
+            )?;
+            let color = priority::role(task.priority).and_then(theme::terminal_color);
+            if let Some(color) = color {
+                queue!(stdout, SetForegroundColor(color))?;
+            }
+            queue!(
+                stdout,
+                Print(format!("{:>8}", priority::label(task.priority))),
+                ResetColor,
+                Print(format!(
+                    "  {:<10}  ",
+                    task.due_date
+                        .map(|d| d.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default(),
+                )),
+            )?;
+            Self::draw_name(stdout, &task.display_name())?;
+            queue!(stdout, SetAttribute(Attribute::Reset), Print("\r\n"))?;
+        }
+        queue!(stdout, Print(format!("\r\n{}\r\n{}", HELP, message)))?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    // Underlines the #<id> references to other tasks in a task name
+    fn draw_name(stdout: &mut Stdout, name: &str) -> Result<(), TodoError> {
+        for word in name.split_inclusive(' ') {
+            let trimmed = word.trim_end_matches([' ', ',', '.', ';', ':', ')']);
+            if trimmed.starts_with('#') && !references(trimmed).is_empty() {
+                queue!(
+                    stdout,
+                    SetAttribute(Attribute::Underlined),
+                    Print(trimmed),
+                    SetAttribute(Attribute::NoUnderline),
+                    Print(&word[trimmed.len()..])
+                )?;
+            } else {
+                queue!(stdout, Print(word))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Leaves raw mode to ask for new values line by line; empty answers keep the old ones
+    fn edit(conn: &Database, task: &mut Task, stdout: &mut Stdout) -> Result<String, TodoError> {
+        execute!(stdout, Show, MoveTo(0, 0), Clear(ClearType::All))?;
+        terminal::disable_raw_mode()?;
+        println!("Editing task {}. Press Enter to keep a value.", task.id);
+
+        let name = prompt::ask("Task", &task.name)?;
+        let due_date = prompt::ask(
+            "Due date",
+            &task
+                .due_date
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+        )?;
+        let priority = prompt::ask("Priority", &priority::label(task.priority))?;
+
+        terminal::enable_raw_mode()?;
+        execute!(stdout, Hide)?;
+
+        let today = Local::now().date_naive();
+        let due_date = match due_date {
+            Some(text) => match dates::parse(&text, today, today) {
+                Some(date) => Some(date),
+                None => {
+                    return Ok(format!(
+                        "'{}' isn't a date like 2024-09-01, \"friday\" or \"+3d\".",
+                        text
+                    ))
+                }
+            },
+            None => None,
+        };
+        let priority = match priority {
+            Some(text) => match priority::parse(&text) {
+                Ok(priority) => Some(priority),
+                Err(e) => return Ok(format!("'{}' isn't a priority: {}.", text, e)),
+            },
+            None => None,
+        };
+        if name.is_none() && due_date.is_none() && priority.is_none() {
+            return Ok(format!("Task {} is unchanged.", task.id));
+        }
+
+        conn.journal("update", &[task.id])?;
+        conn.update_task(
+            task.id,
+            name.clone(),
+            due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            None,
+            vec![],
+            priority,
+            None,
+            false,
+        )?;
+        if let Some(name) = name {
+            task.name = name;
+        }
+        if due_date.is_some() {
+            task.due_date = due_date;
+        }
+        if let Some(priority) = priority {
+            task.priority = priority;
+        }
+        Ok(format!("Task {} updated.", task.id))
+    }
+}
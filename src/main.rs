@@ -1,29 +1,78 @@
+mod audit;
+mod autobackup;
+mod capture;
+mod chart;
+mod collation;
 mod config;
 mod database;
+mod dates;
 mod display;
+mod editor;
 mod error;
+mod filter;
+mod goal;
+mod graph;
+mod history;
+mod interactive;
+mod organize;
+mod planner;
+mod pomodoro;
+mod priority;
+mod prompt;
+mod recurrence;
+mod rules;
 mod search;
+mod site;
 mod task;
+mod task_template;
+mod template;
+mod theme;
+mod urgency;
+mod workflow;
 
-use crate::config::Config;
-use crate::database::Database;
-use crate::display::Display;
+use crate::autobackup::AutoBackup;
+use crate::config::{Config, Identity, Settings};
+use crate::database::{CategoryLoad, Database, PIVOT_DIMENSIONS};
+use crate::display::{Display, TaskColumns};
+use crate::editor::TaskFields;
 use crate::error::TodoError;
+use crate::filter::Filter;
+use crate::graph::Graph;
+use crate::interactive::Interactive;
+use crate::planner::Planner;
+use crate::pomodoro::Pomodoro;
+use crate::recurrence::Recurrence;
 use crate::search::Search;
-use crate::task::Task;
-use chrono::NaiveDate;
-use clap::{Arg, Command};
+use crate::site::Site;
+use crate::task::{tree_order, Task};
+use crate::task_template::TaskTemplate;
+use chrono::{Duration, Local, Months, NaiveDate, Weekday};
+use clap::builder::PossibleValuesParser;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgGroup, Command};
 use clap_complete::{
     generate,
     shells::{Bash, Fish, PowerShell, Zsh},
 };
+use std::collections::{BTreeSet, HashSet};
 use std::fs;
-use std::io;
+use std::io::{self, Write};
 use std::process;
 
+// State key holding the context `list` filters by when none is given
+const ACTIVE_CONTEXT_KEY: &str = "active_context";
+// State key holding the date `list` last ran, used to spot newly overdue tasks
+const LAST_SEEN_KEY: &str = "last_seen";
+// State key holding the start of the week automatic rollover last ran for
+const LAST_ROLLOVER_KEY: &str = "last_rollover";
+// Tag prefix counting how many times a task has been rolled over
+const ROLLOVER_TAG_PREFIX: &str = "rollover-";
+
 fn main() {
     if let Err(e) = run() {
-        eprintln!("Oops! Something went wrong: {}", e);
+        if !matches!(e, TodoError::Reported(_)) {
+            eprintln!("Oops! Something went wrong: {}", e);
+        }
         process::exit(1);
     }
 }
@@ -42,49 +91,263 @@ fn run() -> Result<(), TodoError> {
 
     let mut cmd = build_cli();
     let matches = cmd.clone().get_matches();
+    theme::set(config.get_settings().theme);
+
+    // Undoing first thing in a new week undoes the user's last change, not the rollover
+    if config.get_settings().auto_rollover && matches.subcommand_name() != Some("undo") {
+        auto_rollover(&conn, config.get_settings().week_start)?;
+    }
+
+    // A pomodoro lets go of the database while it counts down, so other
+    // commands can use it in the meantime
+    if let Some(("pomodoro", sub_m)) = matches.subcommand() {
+        return handle_pomodoro(conn, db_path.to_str().unwrap(), sub_m);
+    }
+
+    let operation = if changes_data(&matches, config.get_settings()) {
+        let args: Vec<String> = std::env::args_os()
+            .skip(1)
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        Some(conn.begin_operation(&args)?)
+    } else {
+        None
+    };
 
+    let result = dispatch(&conn, &config, &mut cmd, &matches);
+    if let Some(id) = operation {
+        conn.finish_operation(id, if result.is_ok() { "done" } else { "failed" })?;
+    }
+    result
+}
+
+// Whether a command can change the database, so it is recorded for `last` and `replay`.
+// Listings nested under a command, like `note list`, and `rules test` are read-only too,
+// while `list` counts when it runs the rules first or opens the interactive list.
+fn changes_data(matches: &clap::ArgMatches, settings: &Settings) -> bool {
+    const READ_ONLY: &[&str] = &[
+        "completion",
+        "search",
+        "list-categories",
+        "list-tags",
+        "export",
+        "anonymize",
+        "doctor",
+        "show",
+        "open",
+        "backlinks",
+        "reminders",
+        "graph",
+        "report",
+        "focus",
+        "project",
+        "pomodoro",
+        "last",
+        "history",
+        "replay",
+    ];
+    match matches.subcommand() {
+        Some(("list", sub_m)) => settings.run_rules_before_list || sub_m.get_flag("interactive"),
+        Some((name, _)) if READ_ONLY.contains(&name) => false,
+        Some((_, sub_m)) => !matches!(
+            sub_m.subcommand_name(),
+            Some("list" | "show" | "status" | "test")
+        ),
+        None => false,
+    }
+}
+
+fn dispatch(
+    conn: &Database,
+    config: &Config,
+    cmd: &mut Command,
+    matches: &clap::ArgMatches,
+) -> Result<(), TodoError> {
     match matches.subcommand() {
         Some(("completion", sub_m)) => {
-            handle_completion(&mut cmd, sub_m);
+            handle_completion(cmd, sub_m);
         }
         Some(("add", sub_m)) => {
-            handle_add(&conn, sub_m);
+            handle_add(conn, sub_m, config.get_settings())?;
+        }
+        Some(("template", sub_m)) => {
+            handle_template(conn, sub_m, config.get_settings())?;
         }
         Some(("list", sub_m)) => {
-            handle_list(&conn, sub_m)?;
+            handle_list(conn, sub_m, config.get_settings())?;
         }
         Some(("done", sub_m)) => {
-            handle_done(&conn, sub_m);
+            handle_done(conn, sub_m, config.get_settings())?;
         }
         Some(("update", sub_m)) => {
-            handle_update(&conn, sub_m);
+            handle_update(conn, sub_m, config.get_settings())?;
+        }
+        Some(("pin", sub_m)) => {
+            handle_pin(conn, sub_m, config.get_settings())?;
+        }
+        Some(("unpin", sub_m)) => {
+            handle_unpin(conn, sub_m)?;
+        }
+        Some(("waiting", sub_m)) => {
+            handle_waiting(conn, sub_m)?;
+        }
+        Some(("context", sub_m)) => {
+            handle_context(conn, sub_m)?;
+        }
+        Some(("focus", sub_m)) => {
+            handle_focus(conn, sub_m)?;
         }
         Some(("search", sub_m)) => {
-            handle_search(&conn, sub_m);
+            handle_search(conn, sub_m);
         }
         Some(("add-category", sub_m)) => {
-            handle_add_category(&conn, sub_m);
+            handle_add_category(conn, sub_m)?;
         }
         Some(("delete-category", sub_m)) => {
-            handle_delete_category(&conn, sub_m);
+            handle_delete_category(conn, sub_m)?;
         }
-        Some(("list-categories", _)) => {
-            handle_list_categories(&conn)?;
+        Some(("list-categories", sub_m)) => {
+            handle_list_categories(conn, sub_m, config.get_settings())?;
         }
         Some(("add-tag", sub_m)) => {
-            handle_add_tag(&conn, sub_m);
+            handle_add_tag(conn, sub_m)?;
         }
         Some(("delete-tag", sub_m)) => {
-            handle_delete_tag(&conn, sub_m);
+            handle_delete_tag(conn, sub_m)?;
         }
-        Some(("list-tags", _)) => {
-            handle_list_tags(&conn)?;
+        Some(("list-tags", sub_m)) => {
+            handle_list_tags(conn, sub_m, config.get_settings())?;
         }
         Some(("import", sub_m)) => {
-            handle_import(&conn, sub_m)?;
+            handle_import(conn, sub_m, config.get_settings())?;
         }
         Some(("export", sub_m)) => {
-            handle_export(&conn, sub_m)?;
+            handle_export(conn, sub_m, config.get_settings())?;
+        }
+        Some(("delete", sub_m)) => {
+            handle_delete(conn, sub_m)?;
+        }
+        Some(("trash", sub_m)) => {
+            handle_trash(conn, sub_m)?;
+        }
+        Some(("archive", sub_m)) => {
+            handle_archive(conn, sub_m)?;
+        }
+        Some(("anonymize", sub_m)) => {
+            let path = sub_m.get_one::<String>("to").unwrap();
+            conn.anonymize_to(path)?;
+            println!("Wrote an anonymized copy of the database to {}.", path);
+        }
+        Some(("maintain", sub_m)) => {
+            handle_maintain(conn, sub_m, config.get_settings())?;
+        }
+        Some(("last", sub_m)) => {
+            handle_last(conn, sub_m)?;
+        }
+        Some(("audit", sub_m)) => {
+            if let Some(("dates", dates_m)) = sub_m.subcommand() {
+                handle_audit_dates(conn, dates_m)?;
+            }
+        }
+        Some(("doctor", _)) => {
+            handle_doctor(conn)?;
+        }
+        Some(("purge", sub_m)) => {
+            handle_purge(conn, sub_m)?;
+        }
+        Some(("history", sub_m)) => {
+            handle_history(conn, sub_m)?;
+        }
+        Some(("replay", sub_m)) => {
+            handle_replay(conn, config, sub_m)?;
+        }
+        Some(("undo", _)) => {
+            handle_undo(conn)?;
+        }
+        Some((action @ ("append" | "prepend"), sub_m)) => {
+            handle_extend(conn, sub_m, action == "prepend")?;
+        }
+        Some(("edit", sub_m)) => {
+            handle_edit(conn, sub_m)?;
+        }
+        Some(("show", sub_m)) => {
+            handle_show(conn, sub_m)?;
+        }
+        Some(("open", sub_m)) => {
+            handle_open(conn, sub_m)?;
+        }
+        Some(("relate", sub_m)) => {
+            handle_relate(conn, sub_m)?;
+        }
+        Some(("remind", sub_m)) => {
+            handle_remind(conn, sub_m)?;
+        }
+        Some(("start", sub_m)) => {
+            handle_start(conn, sub_m)?;
+        }
+        Some(("stop", _)) => match conn.stop_timer()? {
+            Some((id, minutes)) => println!(
+                "Stopped the timer on task {} after {}.",
+                id,
+                dates::describe_minutes(minutes)
+            ),
+            None => println!("No timer is running."),
+        },
+        Some(("log", sub_m)) => {
+            handle_log(conn, sub_m)?;
+        }
+        Some(("reminders", _)) => {
+            let today = Local::now().date_naive();
+            let tasks = conn.reminders_on(today)?;
+            Display::show_reminders(&tasks.iter().collect::<Vec<_>>(), &[]);
+        }
+        Some(("backlinks", sub_m)) => {
+            handle_backlinks(conn, sub_m)?;
+        }
+        Some(("graph", sub_m)) => {
+            handle_graph(conn, sub_m)?;
+        }
+        Some(("clone", sub_m)) => {
+            handle_clone(conn, sub_m, config.get_settings())?;
+        }
+        Some(("note", sub_m)) => {
+            handle_note(conn, sub_m)?;
+        }
+        Some(("comment", sub_m)) => {
+            handle_comment(conn, sub_m, config.get_settings())?;
+        }
+        Some(("annotate", sub_m)) => {
+            handle_annotate(conn, sub_m)?;
+        }
+        Some(("capture", sub_m)) => {
+            handle_capture(conn, sub_m, config.get_settings())?;
+        }
+        Some(("organize", sub_m)) => {
+            handle_organize(conn, sub_m, config.get_settings())?;
+        }
+        Some(("rules", sub_m)) => {
+            handle_rules(conn, sub_m, config.get_settings())?;
+        }
+        Some(("report", sub_m)) => {
+            handle_report(conn, sub_m, config.get_settings())?;
+        }
+        Some(("project", sub_m)) => {
+            handle_project(conn, sub_m)?;
+        }
+        Some(("workflow", sub_m)) => {
+            handle_workflow(conn, sub_m, config.get_settings())?;
+        }
+        Some(("autobackup", sub_m)) => {
+            handle_autobackup(conn, sub_m, config.get_settings())?;
+        }
+        Some(("goals", sub_m)) => {
+            handle_goals(conn, sub_m)?;
+        }
+        Some(("rollover", sub_m)) => {
+            handle_rollover(conn, sub_m, config.get_settings())?;
+        }
+        Some(("plan", sub_m)) => {
+            handle_plan(conn, sub_m, config.get_settings())?;
         }
         _ => {
             println!("Invalid command. Use --help for available commands.");
@@ -102,6 +365,13 @@ fn build_cli() -> Command {
         .version("1.0")
         .author("Hassan El-Masri <hassan@unixtime.com>")
         .about("Manages your todos")
+        .arg(
+            Arg::new("accessible")
+                .long("accessible")
+                .help("Prints labeled lines instead of tables, for use with screen readers.")
+                .global(true)
+                .action(clap::ArgAction::SetTrue),
+        )
         .subcommand(
             Command::new("add")
                 .about(
@@ -110,12 +380,12 @@ fn build_cli() -> Command {
                 .arg(
                     Arg::new("TASK")
                         .help("The task description.")
-                        .required(true)
+                        .required_unless_present_any(["from-template", "interactive", "stdin", "file"])
                         .index(1),
                 )
                 .arg(
                     Arg::new("DUE_DATE")
-                        .help("The due date for the task in YYYY-MM-DD format.")
+                        .help("The due date for the task, as YYYY-MM-DD, a phrase like \"friday\" or \"end of month\", or an offset like +3d.")
                         .required(false)
                         .index(2),
                 )
@@ -137,10 +407,161 @@ fn build_cli() -> Command {
                 .arg(
                     Arg::new("priority")
                         .long("priority")
-                        .help("Priority of the task.")
+                        .help("Priority of the task: low, medium, high or critical, or 0-4.")
                         .value_name("PRIORITY")
+                        .value_parser(priority::parse)
                         .required(false)
                         .default_value("0"),
+                )
+                .arg(
+                    Arg::new("context")
+                        .long("context")
+                        .help("Where the task can be done, e.g. office or errands.")
+                        .value_name("CONTEXT")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("from-template")
+                        .long("from-template")
+                        .help("Starts from a saved template; other options override its values.")
+                        .value_name("NAME"),
+                )
+                .arg(
+                    Arg::new("interactive")
+                        .long("interactive")
+                        .short('i')
+                        .help("Asks for each detail of the task in turn.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("stdin")
+                        .long("stdin")
+                        .help("Adds one task per line read from standard input, read like `capture` does.")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["TASK", "DUE_DATE", "interactive", "from-template", "file"]),
+                )
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .help("Adds one task per line of this file, read like `capture` does.")
+                        .value_name("PATH")
+                        .conflicts_with_all(["TASK", "DUE_DATE", "interactive", "from-template"]),
+                )
+                .arg(
+                    Arg::new("parent")
+                        .long("parent")
+                        .help("Adds the task as a subtask of the task with this ID.")
+                        .value_name("ID")
+                        .value_parser(clap::value_parser!(i32)),
+                )
+                .arg(
+                    Arg::new("start")
+                        .long("start")
+                        .help("The day work on the task can start, as YYYY-MM-DD or a phrase like \"next monday\".")
+                        .value_name("DATE"),
+                )
+                .arg(
+                    Arg::new("repeat")
+                        .long("repeat")
+                        .help("Brings the task back once done: daily, weekly, monthly, yearly, or e.g. \"every 2 weeks\".")
+                        .value_name("RULE"),
+                )
+                .arg(
+                    Arg::new("icon")
+                        .long("icon")
+                        .help("An emoji or symbol shown before the task's name, in place of its category's icon.")
+                        .value_name("ICON"),
+                )
+                .arg(
+                    Arg::new("estimate")
+                        .long("estimate")
+                        .help("How long you expect the task to take, like 45m, 2h or 1h30m.")
+                        .value_name("DURATION")
+                        .value_parser(dates::parse_minutes),
+                )
+                .arg(
+                    Arg::new("assignee")
+                        .long("assignee")
+                        .help("The person the task is for.")
+                        .value_name("WHO"),
+                )
+                .arg(
+                    Arg::new("set")
+                        .long("set")
+                        .help("Sets a custom field named under `attributes` in the config, like client=ACME. Repeat for more fields.")
+                        .value_name("FIELD=VALUE")
+                        .value_parser(task::parse_attribute)
+                        .action(clap::ArgAction::Append),
+                ),
+        )
+        .subcommand(
+            Command::new("template")
+                .about("Saves task definitions as named templates and adds tasks from them.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("save")
+                        .about("Saves a template, replacing any template with the same name.")
+                        .arg(
+                            Arg::new("NAME")
+                                .help("The name of the template.")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("TASK")
+                                .help("The task description; {{date}} is replaced by the day the task is added.")
+                                .required(true)
+                                .index(2),
+                        )
+                        .arg(
+                            Arg::new("category")
+                                .long("category")
+                                .help("The category of the task.")
+                                .value_name("CATEGORY"),
+                        )
+                        .arg(
+                            Arg::new("tags")
+                                .long("tags")
+                                .help("Tags associated with the task.")
+                                .value_name("TAGS")
+                                .num_args(1..),
+                        )
+                        .arg(
+                            Arg::new("priority")
+                                .long("priority")
+                                .help("Priority of the task: low, medium, high or critical, or 0-4.")
+                                .value_name("PRIORITY")
+                                .value_parser(priority::parse)
+                                .default_value("0"),
+                        )
+                        .arg(
+                            Arg::new("due-in")
+                                .long("due-in")
+                                .help("Makes the task due this many days after it is added.")
+                                .value_name("DAYS")
+                                .value_parser(clap::value_parser!(i64).range(0..)),
+                        ),
+                )
+                .subcommand(Command::new("list").about("Lists the saved templates."))
+                .subcommand(
+                    Command::new("apply")
+                        .about("Adds a task from a template.")
+                        .arg(
+                            Arg::new("NAME")
+                                .help("The name of the template.")
+                                .required(true)
+                                .index(1),
+                        ),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Deletes a template.")
+                        .arg(
+                            Arg::new("NAME")
+                                .help("The name of the template.")
+                                .required(true)
+                                .index(1),
+                        ),
                 ),
         )
         .subcommand(
@@ -151,16 +572,134 @@ fn build_cli() -> Command {
                         .long("done-only")
                         .help("Lists only completed tasks.")
                         .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("archived")
+                        .long("archived")
+                        .help("Lists the tasks moved to the archive.")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["done-only", "interactive", "waiting"]),
+                )
+                .arg(
+                    Arg::new("age")
+                        .long("age")
+                        .help("Shows how long ago each task was added and flags stale ones.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("urgency")
+                        .long("urgency")
+                        .help("Shows each task's urgency score, from its priority, due date, age and tags.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("interactive")
+                        .long("interactive")
+                        .short('i')
+                        .help("Moves through the tasks with the arrow keys to mark, reprioritize or edit them.")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("accessible"),
+                )
+                .arg(
+                    Arg::new("context")
+                        .long("context")
+                        .help("Lists only tasks in this context instead of the active one.")
+                        .value_name("CONTEXT")
+                        .conflicts_with("all-contexts"),
+                )
+                .arg(
+                    Arg::new("all-contexts")
+                        .long("all-contexts")
+                        .help("Ignores the active context and lists tasks from every context.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("waiting")
+                        .long("waiting")
+                        .help("Lists only tasks that are waiting on someone else.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("template")
+                        .long("template")
+                        .help("Prints one line per task from a template like '{{id}} {{name}} ({{due}})'.")
+                        .value_name("TEMPLATE")
+                        .conflicts_with_all(["interactive", "accessible", "age"]),
+                )
+                .arg(
+                    Arg::new("created-by")
+                        .long("created-by")
+                        .help("Lists only tasks added by this person (name or email).")
+                        .value_name("WHO"),
+                )
+                .arg(
+                    Arg::new("completed-by")
+                        .long("completed-by")
+                        .help("Lists only tasks completed by this person (name or email).")
+                        .value_name("WHO"),
+                )
+                .arg(
+                    Arg::new("assignee")
+                        .long("assignee")
+                        .help("Lists only tasks assigned to this person (name or email).")
+                        .value_name("WHO"),
+                )
+                .arg(
+                    Arg::new("attr")
+                        .long("attr")
+                        .help("Lists only tasks with this custom field value, like client=ACME; client= lists tasks with no client. Repeat to combine.")
+                        .value_name("FIELD=VALUE")
+                        .value_parser(task::parse_attribute)
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("startable")
+                        .long("startable")
+                        .help("Hides tasks whose start date is still in the future.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("due-week")
+                        .long("due-week")
+                        .help("Lists only tasks due in this week, like 2025-W14.")
+                        .value_name("WEEK")
+                        .value_parser(dates::parse_week),
+                )
+                .arg(
+                    Arg::new("week")
+                        .long("week")
+                        .help("Shows the week each task is due in.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("sort")
+                        .long("sort")
+                        .help("Orders the list by name, category, due date, priority or urgency instead of ID. Names follow the `collation` setting.")
+                        .value_parser(PossibleValuesParser::new(["id", "name", "category", "due", "priority", "urgency"]))
+                        .default_value("id"),
+                )
+                .arg(
+                    Arg::new("tree")
+                        .long("tree")
+                        .help("Lists subtasks indented under their parent task.")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["interactive", "template"]),
                 ),
         )
         .subcommand(
             Command::new("done")
-                .about("Marks a todo task as done.")
+                .about("Marks one or more todo tasks as done.")
                 .arg(
-                    Arg::new("ID")
-                        .help("The ID of the todo task.")
+                    Arg::new("IDS")
+                        .help("The IDs of the todo tasks, or ranges like 10-15.")
                         .required(true)
-                        .index(1),
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("with-subtasks")
+                        .long("with-subtasks")
+                        .help("Also marks the tasks' open subtasks as done.")
+                        .action(clap::ArgAction::SetTrue),
                 ),
         )
         .subcommand(
@@ -169,9 +708,24 @@ fn build_cli() -> Command {
                 .arg(
                     Arg::new("ID")
                         .help("The ID of the todo task to update.")
-                        .required(true)
+                        .required_unless_present("where")
                         .index(1),
                 )
+                .arg(
+                    Arg::new("where")
+                        .long("where")
+                        .help("Updates every task matching a filter like \"category:Work due.before:2025-01-01\" instead of one task, after showing them. Keys: category, tag, context, assignee, priority, done, name, due, due.before, due.after and custom fields.")
+                        .value_name("FILTER")
+                        .conflicts_with_all(["ID", "TASK"]),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .help("Updates the tasks matching --where without asking first.")
+                        .action(clap::ArgAction::SetTrue)
+                        .requires("where"),
+                )
                 .arg(
                     Arg::new("TASK")
                         .long("task")
@@ -182,7 +736,7 @@ fn build_cli() -> Command {
                 .arg(
                     Arg::new("DUE_DATE")
                         .long("due-date")
-                        .help("The new due date for the task in YYYY-MM-DD format.")
+                        .help("The new due date for the task, as YYYY-MM-DD, a phrase like \"tomorrow\" or \"next week\", or an offset like +10d from the current due date.")
                         .value_name("DUE_DATE")
                         .required(false),
                 )
@@ -204,8 +758,16 @@ fn build_cli() -> Command {
                 .arg(
                     Arg::new("priority")
                         .long("priority")
-                        .help("The new priority of the task.")
+                        .help("The new priority of the task: low, medium, high or critical, or 0-4.")
                         .value_name("PRIORITY")
+                        .value_parser(priority::parse)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("context")
+                        .long("context")
+                        .help("The new context of the task.")
+                        .value_name("CONTEXT")
                         .required(false),
                 )
                 .arg(
@@ -213,343 +775,3810 @@ fn build_cli() -> Command {
                         .long("undone")
                         .help("Marks the task as not done.")
                         .action(clap::ArgAction::SetTrue),
-                ),
-        )
-        .subcommand(
-            Command::new("search")
-                .about("Searches tasks by name, due date, category, or tags.")
-                .arg(Arg::new("QUERY").help("The search query.").required(true)),
-        )
-        .subcommand(
-            Command::new("add-category")
-                .about("Adds a new category.")
+                )
                 .arg(
-                    Arg::new("CATEGORY_NAME")
-                        .help("The name of the category.")
-                        .required(true),
+                    Arg::new("start")
+                        .long("start")
+                        .help("The day work on the task can start, as YYYY-MM-DD or a phrase like \"next monday\".")
+                        .value_name("DATE")
+                        .conflicts_with("no-start"),
+                )
+                .arg(
+                    Arg::new("no-start")
+                        .long("no-start")
+                        .help("Clears the task's start date.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("repeat")
+                        .long("repeat")
+                        .help("Makes the task recur: daily, weekly, monthly, yearly, or e.g. \"every 2 weeks\".")
+                        .value_name("RULE")
+                        .conflicts_with("no-repeat"),
+                )
+                .arg(
+                    Arg::new("no-repeat")
+                        .long("no-repeat")
+                        .help("Stops the task from recurring.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("icon")
+                        .long("icon")
+                        .help("An emoji or symbol shown before the task's name, in place of its category's icon.")
+                        .value_name("ICON")
+                        .conflicts_with("no-icon"),
+                )
+                .arg(
+                    Arg::new("no-icon")
+                        .long("no-icon")
+                        .help("Removes the task's own icon, so its category's shows again.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("estimate")
+                        .long("estimate")
+                        .help("How long you expect the task to take, like 45m, 2h or 1h30m.")
+                        .value_name("DURATION")
+                        .value_parser(dates::parse_minutes)
+                        .conflicts_with("no-estimate"),
+                )
+                .arg(
+                    Arg::new("no-estimate")
+                        .long("no-estimate")
+                        .help("Clears the task's estimate.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("assignee")
+                        .long("assignee")
+                        .help("The person the task is for.")
+                        .value_name("WHO")
+                        .conflicts_with("unassign"),
+                )
+                .arg(
+                    Arg::new("unassign")
+                        .long("unassign")
+                        .help("Removes the task's assignee.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("set")
+                        .long("set")
+                        .help("Sets a custom field, like client=ACME; an empty value like client= clears it. Repeat for more fields.")
+                        .value_name("FIELD=VALUE")
+                        .value_parser(task::parse_attribute)
+                        .action(clap::ArgAction::Append),
                 ),
         )
         .subcommand(
-            Command::new("delete-category")
-                .about("Deletes a category.")
+            Command::new("delete")
+                .about("Moves tasks to the trash, or deletes them for good with --permanent.")
                 .arg(
-                    Arg::new("CATEGORY_NAME")
-                        .help("The name of the category to delete.")
-                        .required(true),
+                    Arg::new("IDS")
+                        .help("The IDs of the todo tasks to delete, or ranges like 10-15.")
+                        .required(true)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("permanent")
+                        .long("permanent")
+                        .help("Skips the trash and deletes the tasks with their category, tags and comments.")
+                        .action(clap::ArgAction::SetTrue),
                 ),
         )
-        .subcommand(Command::new("list-categories").about("Lists all categories."))
         .subcommand(
-            Command::new("add-tag").about("Adds a new tag.").arg(
-                Arg::new("TAG_NAME")
-                    .help("The name of the tag.")
-                    .required(true),
-            ),
+            Command::new("trash")
+                .about("Shows, restores or purges deleted tasks.")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("Lists the tasks in the trash."))
+                .subcommand(
+                    Command::new("restore")
+                        .about("Moves tasks from the trash back into the list.")
+                        .arg(
+                            Arg::new("IDS")
+                                .help("The IDs of the trashed tasks, or ranges like 10-15.")
+                                .required(true)
+                                .num_args(1..),
+                        ),
+                )
+                .subcommand(
+                    Command::new("empty")
+                        .about("Permanently deletes everything in the trash.")
+                        .arg(
+                            Arg::new("yes")
+                                .long("yes")
+                                .help("Empties the trash without asking for confirmation.")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                ),
         )
         .subcommand(
-            Command::new("delete-tag").about("Deletes a tag.").arg(
-                Arg::new("TAG_NAME")
-                    .help("The name of the tag to delete.")
-                    .required(true),
-            ),
+            Command::new("archive")
+                .about("Moves completed tasks out of the main list into the archive.")
+                .arg(
+                    Arg::new("before")
+                        .long("before")
+                        .help("Archives only tasks completed before this date (YYYY-MM-DD).")
+                        .value_name("DATE"),
+                ),
         )
-        .subcommand(Command::new("list-tags").about("Lists all tags."))
         .subcommand(
-            Command::new("completion")
-                .about("Generate shell completion scripts for your shell")
+            Command::new("maintain")
+                .about("Archives and purges old tasks by the retention rules in the config file.")
                 .arg(
-                    Arg::new("shell")
-                        .help("The shell to generate the completion script for")
-                        .required(true)
-                        .value_parser(["bash", "zsh", "fish", "powershell"]),
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Shows what each rule would do without changing anything.")
+                        .action(clap::ArgAction::SetTrue),
                 ),
         )
         .subcommand(
-            Command::new("import")
-                .about("Import data into the todo list from a file")
+            Command::new("purge")
+                .about("Permanently deletes, or archives, completed tasks older than a retention window.")
                 .arg(
-                    Arg::new("format")
-                        .help("The format of the file (json, parquet, xlsx, csv)")
+                    Arg::new("done")
+                        .long("done")
+                        .help("Purges completed tasks, the only ones purge removes.")
+                        .action(clap::ArgAction::SetTrue)
                         .required(true),
                 )
                 .arg(
-                    Arg::new("file")
-                        .help("The file path to import from")
+                    Arg::new("older-than")
+                        .long("older-than")
+                        .help("Purges only tasks completed longer ago than this, like 90d or 12w.")
+                        .value_name("AGE")
+                        .value_parser(dates::parse_days)
                         .required(true),
                 )
                 .arg(
-                    Arg::new("strategy")
-                        .help("The import strategy (skip, remove, upsert)")
-                        .required(true),
+                    Arg::new("archive")
+                        .long("archive")
+                        .help("Moves the tasks to the archive instead of deleting them.")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Lists the tasks that would be removed without changing anything.")
+                        .action(clap::ArgAction::SetTrue),
                 ),
         )
         .subcommand(
-            Command::new("export")
-                .about("Export data from the todo list to a file")
+            Command::new("audit")
+                .about("Looks for data that is likely wrong, like dates left by a bad import.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("dates")
+                        .about("Lists tasks due long before they were added, completed before they were added, or dated decades ahead.")
+                        .arg(
+                            Arg::new("fix")
+                                .long("fix")
+                                .help("Walks through the dates one at a time, asking for a new date for each.")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Checks the todo list for problems and says how to fix them."),
+        )
+        .subcommand(
+            Command::new("anonymize")
+                .about("Writes a copy of the database with task names, notes and people replaced, for bug reports.")
                 .arg(
-                    Arg::new("format")
-                        .help("The format of the file (json, parquet, xlsx, csv)")
+                    Arg::new("to")
+                        .long("to")
+                        .help("The new database file to write.")
+                        .value_name("PATH")
                         .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("undo").about(
+                "Rolls back the last add, done, update or delete. Run it again to go further back.",
+            ),
+        )
+        .subcommand(
+            Command::new("last")
+                .about("Shows the latest commands that changed your todos, and whether each one finished.")
+                .arg(
+                    Arg::new("count")
+                        .short('n')
+                        .long("count")
+                        .help("How many commands to show (default 10)")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Shows what was added, changed, done, deleted or undone, and when.")
+                .arg(
+                    Arg::new("ID")
+                        .help("Shows the whole history of this task instead of recent changes.")
+                        .value_parser(clap::value_parser!(i32))
+                        .index(1),
                 )
                 .arg(
-                    Arg::new("file")
-                        .help("The file path to export to")
-                        .required(true),
+                    Arg::new("days")
+                        .long("days")
+                        .help("How many days back to show changes to any task")
+                        .value_parser(clap::value_parser!(i64).range(1..))
+                        .default_value("7")
+                        .conflicts_with("ID"),
                 ),
         )
-}
+        .subcommand(
+            Command::new("replay")
+                .about("Runs a recorded command again, by default the latest one that was cut short.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The number `last` shows for the command")
+                        .value_parser(clap::value_parser!(i64)),
+                ),
+        )
+        .subcommand(
+            Command::new("append")
+                .about("Adds text to the end of a task's description.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("TEXT")
+                        .help("The text to add.")
+                        .required(true)
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            Command::new("prepend")
+                .about("Adds text to the start of a task's description.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("TEXT")
+                        .help("The text to add.")
+                        .required(true)
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            Command::new("edit")
+                .about("Opens a task in $EDITOR and applies the changes when you save.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task to edit.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new("show")
+                .about("Shows every detail of a task, with its notes, comments, history and related tasks.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task to show.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new("open")
+                .about("Opens the web links in a task's name and notes in the browser.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("print")
+                        .long("print")
+                        .help("Prints the links, one per line, instead of opening them.")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("relate")
+                .about("Relates two tasks, so each is listed with the other in `show`.")
+                .arg(
+                    Arg::new("IDS")
+                        .help("The IDs of the two todo tasks.")
+                        .required(true)
+                        .num_args(2)
+                        .value_parser(clap::value_parser!(i32)),
+                ),
+        )
+        .subcommand(
+            Command::new("remind")
+                .about("Sets when `reminders` brings a task up, as lead times before its due date.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("before")
+                        .long("before")
+                        .help("How long before the due date, like 2d or 1w (0d for the day itself). Can be repeated.")
+                        .action(clap::ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("clear")
+                        .long("clear")
+                        .help("Removes the task's reminders.")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("before"),
+                ),
+        )
+        .subcommand(
+            Command::new("start")
+                .about("Starts timing work on a task, stopping the timer on any other task.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new("stop").about("Stops the running timer and records the time on its task."),
+        )
+        .subcommand(
+            Command::new("log")
+                .about("Records time already spent on a task, ending now.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("DURATION")
+                        .help("How long you worked on it, like 45m, 2h or 1h30m.")
+                        .required(true)
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            Command::new("pomodoro")
+                .about("Counts down a work session on a task, records it, then counts down a break.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("work")
+                        .long("work")
+                        .help("Minutes of work.")
+                        .value_name("MINUTES")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("25"),
+                )
+                .arg(
+                    Arg::new("break")
+                        .long("break")
+                        .help("Minutes of break afterwards (0 skips it).")
+                        .value_name("MINUTES")
+                        .value_parser(clap::value_parser!(u64))
+                        .default_value("5"),
+                ),
+        )
+        .subcommand(
+            Command::new("reminders")
+                .about("Prints the reminders falling today, for running from cron. Prints nothing when there are none."),
+        )
+        .subcommand(
+            Command::new("backlinks")
+                .about("Lists the tasks whose names refer to a task as #<ID>.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the referenced todo task.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new("graph")
+                .about("Draws the tasks with their subtasks, references and relations as a chart.")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("The chart syntax to print.")
+                        .value_parser(["mermaid"])
+                        .default_value("mermaid"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Includes done tasks as well as pending ones.")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("clone")
+                .about("Adds a copy of a task with the same name, category, tags and priority.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task to copy.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("due")
+                        .long("due")
+                        .help("Gives the copy this due date, as YYYY-MM-DD or a phrase like \"next friday\", instead of the original's.")
+                        .value_name("DATE"),
+                ),
+        )
+        .subcommand(
+            Command::new("note")
+                .about("Keeps multi-line notes on a task.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("add")
+                        .about("Adds a note to a task.")
+                        .arg(
+                            Arg::new("ID")
+                                .help("The ID of the todo task.")
+                                .required(true)
+                                .index(1),
+                        )
+                        .arg(
+                            Arg::new("TEXT")
+                                .help("The note; when left out, it is read from standard input.")
+                                .index(2),
+                        ),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("Shows a task's notes, oldest first.")
+                        .arg(
+                            Arg::new("ID")
+                                .help("The ID of the todo task.")
+                                .required(true)
+                                .index(1),
+                        ),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Deletes a note.")
+                        .arg(
+                            Arg::new("ID")
+                                .help("The ID of the note, as shown by `note list`.")
+                                .required(true)
+                                .index(1),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("comment")
+                .about("Adds a comment to a task, or shows its comments when no text is given.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task to comment on.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("TEXT")
+                        .help("The comment, signed with your configured identity.")
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            Command::new("annotate")
+                .about("Appends a timestamped annotation to a task, such as what happened on it.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task to annotate.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("TEXT")
+                        .help("The annotation; it can't be edited once added.")
+                        .required(true)
+                        .index(2),
+                ),
+        )
+        .subcommand(
+            Command::new("pin")
+                .about("Pins a task to the focus list.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task to pin.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new("unpin")
+                .about("Removes a task from the focus list.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task to unpin.")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            Command::new("waiting")
+                .about("Marks a task as waiting on someone, hiding it until its follow-up date.")
+                .arg(
+                    Arg::new("ID")
+                        .help("The ID of the todo task.")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("for")
+                        .long("for")
+                        .help("What or whom the task is waiting for.")
+                        .value_name("WHAT")
+                        .required_unless_present("clear"),
+                )
+                .arg(
+                    Arg::new("follow-up")
+                        .long("follow-up")
+                        .help("The date to follow up, as YYYY-MM-DD or a phrase like \"next week\".")
+                        .value_name("DATE"),
+                )
+                .arg(
+                    Arg::new("clear")
+                        .long("clear")
+                        .help("Stops waiting and shows the task in the list again.")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["for", "follow-up"]),
+                ),
+        )
+        .subcommand(
+            Command::new("context")
+                .about("Shows or sets the active context used to filter the task list.")
+                .arg(
+                    Arg::new("CONTEXT")
+                        .help("The context to switch to.")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("clear")
+                        .long("clear")
+                        .help("Clears the active context.")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("CONTEXT"),
+                ),
+        )
+        .subcommand(Command::new("focus").about("Lists only the pinned tasks that are not done."))
+        .subcommand(
+            Command::new("search")
+                .about("Searches tasks by name, due date, category, or tags.")
+                .arg(Arg::new("QUERY").help("The search query.").required(true)),
+        )
+        .subcommand(
+            Command::new("add-category")
+                .about("Adds a new category.")
+                .arg(
+                    Arg::new("CATEGORY_NAME")
+                        .help("The name of the category.")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("wip")
+                        .long("wip")
+                        .help("The most open tasks the category should hold (0 removes the limit).")
+                        .value_name("LIMIT")
+                        .value_parser(clap::value_parser!(i64).range(0..)),
+                )
+                .arg(
+                    Arg::new("icon")
+                        .long("icon")
+                        .help("An emoji or symbol shown before the names of the category's tasks (\"\" removes it).")
+                        .value_name("ICON"),
+                )
+                .arg(
+                    Arg::new("checklist")
+                        .long("checklist")
+                        .help("Steps added as subtasks to every new task in the category, like build tag changelog announce (\"\" removes them).")
+                        .value_name("STEPS")
+                        .num_args(1..),
+                ),
+        )
+        .subcommand(
+            Command::new("delete-category")
+                .about("Deletes a category.")
+                .arg(
+                    Arg::new("CATEGORY_NAME")
+                        .help("The name of the category to delete.")
+                        .required(true),
+                ),
+        )
+        .subcommand(Command::new("list-categories").about("Lists all categories."))
+        .subcommand(
+            Command::new("add-tag").about("Adds a new tag.").arg(
+                Arg::new("TAG_NAME")
+                    .help("The name of the tag.")
+                    .required(true),
+            ),
+        )
+        .subcommand(
+            Command::new("delete-tag").about("Deletes a tag.").arg(
+                Arg::new("TAG_NAME")
+                    .help("The name of the tag to delete.")
+                    .required(true),
+            ),
+        )
+        .subcommand(Command::new("list-tags").about("Lists all tags."))
+        .subcommand(
+            Command::new("completion")
+                .about("Generate shell completion scripts for your shell")
+                .arg(
+                    Arg::new("shell")
+                        .help("The shell to generate the completion script for")
+                        .required(true)
+                        .value_parser(["bash", "zsh", "fish", "powershell"]),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Import data into the todo list from a file")
+                .arg(
+                    Arg::new("format")
+                        .help("The format of the file (json, parquet, xlsx, csv)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("The file path to import from")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("strategy")
+                        .help("The import strategy (skip, remove, upsert)")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .help("Lists every row of the file with what happened to it")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("strict")
+                        .long("strict")
+                        .help("Checks the file's columns and types first and imports nothing if any row is invalid")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("goals")
+                .about("Shows progress towards completion goals, or manages them.")
+                .arg(
+                    Arg::new("history")
+                        .long("history")
+                        .help("Shows how many tasks counted towards each goal in past periods.")
+                        .value_name("PERIODS")
+                        .value_parser(clap::value_parser!(u32).range(1..)),
+                )
+                .subcommand(
+                    Command::new("add")
+                        .about("Adds a goal to complete a number of tasks per week or month.")
+                        .arg(
+                            Arg::new("category")
+                                .long("category")
+                                .help("Counts completed tasks in this category.")
+                                .value_name("CATEGORY"),
+                        )
+                        .arg(
+                            Arg::new("tag")
+                                .long("tag")
+                                .help("Counts completed tasks with this tag.")
+                                .value_name("TAG"),
+                        )
+                        .arg(
+                            Arg::new("count")
+                                .long("count")
+                                .help("How many tasks to complete per period.")
+                                .value_name("COUNT")
+                                .required(true)
+                                .value_parser(clap::value_parser!(i64).range(1..)),
+                        )
+                        .arg(
+                            Arg::new("per")
+                                .long("per")
+                                .help("The period the goal resets after.")
+                                .value_parser(["week", "month"])
+                                .default_value("week"),
+                        )
+                        .group(
+                            ArgGroup::new("target")
+                                .args(["category", "tag"])
+                                .required(true),
+                        ),
+                )
+                .subcommand(
+                    Command::new("delete").about("Deletes a goal.").arg(
+                        Arg::new("ID")
+                            .help("The ID of the goal to delete.")
+                            .required(true)
+                            .index(1),
+                    ),
+                ),
+        )
+        .subcommand(
+            Command::new("rollover")
+                .about("Moves unfinished tasks scheduled in earlier weeks or months to today.")
+                .arg(
+                    Arg::new("period")
+                        .long("period")
+                        .help("Rolls over tasks scheduled before the current week or month.")
+                        .value_parser(["week", "month"])
+                        .default_value("week"),
+                ),
+        )
+        .subcommand(
+            Command::new("plan")
+                .about("Spreads unscheduled tasks due soon across the coming week or month.")
+                .arg(
+                    Arg::new("PERIOD")
+                        .help("The period to plan.")
+                        .required(true)
+                        .value_parser(["week", "month"]),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .help("Applies the plan without asking for confirmation.")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Export data from the todo list to a file")
+                .arg(
+                    Arg::new("format")
+                        .help("The format of the file (json, parquet, xlsx, csv), or site for an HTML dashboard")
+                        .required_unless_present("preset"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .help("The file path to export to, or the directory for a site")
+                        .required_unless_present("preset"),
+                )
+                .arg(
+                    Arg::new("preset")
+                        .long("preset")
+                        .help("Runs an export preset from the config file")
+                        .conflicts_with_all(["format", "file"]),
+                )
+                .arg(
+                    Arg::new("scope")
+                        .long("scope")
+                        .help("Which tasks to export")
+                        .value_parser(["pending", "done", "archived", "trashed", "all"])
+                        .default_value("all")
+                        .conflicts_with("preset"),
+                )
+                .arg(
+                    Arg::new("comments")
+                        .long("comments")
+                        .help("Exports the comments on the tasks instead of the tasks themselves")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with("preset"),
+                )
+                .arg(
+                    Arg::new("annotations")
+                        .long("annotations")
+                        .help("Exports the annotations on the tasks instead of the tasks themselves")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["preset", "comments"]),
+                )
+                .arg(
+                    Arg::new("fields")
+                        .long("fields")
+                        .help("Exports only these columns, e.g. id,name,due_date")
+                        .value_name("FIELDS")
+                        .value_delimiter(',')
+                        .conflicts_with_all(["preset", "comments", "annotations"]),
+                )
+                .arg(
+                    Arg::new("redact")
+                        .long("redact")
+                        .help("Writes these columns empty; \"notes\" empties comments and annotations, and \"urls\" removes web links from the text")
+                        .value_name("FIELDS")
+                        .value_delimiter(',')
+                        .conflicts_with("preset"),
+                ),
+        )
+        .subcommand(
+            Command::new("capture")
+                .about("Adds a task described in plain words, after showing how it was understood")
+                .arg(
+                    Arg::new("text")
+                        .help("e.g. \"renew passport next month, high priority, personal\"")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .help("Adds the task without asking for confirmation")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("organize")
+                .about("Walks through a category's tasks in batches, moving them to the categories the config file suggests")
+                .arg(
+                    Arg::new("category")
+                        .long("category")
+                        .help("The category to sort out, like an inbox")
+                        .default_value("General"),
+                )
+                .arg(
+                    Arg::new("batch")
+                        .long("batch")
+                        .help("How many suggestions to confirm at a time")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("rules")
+                .about("Applies the rules from the config file to pending tasks")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("run")
+                        .about("Applies every rule to the tasks it has not changed yet")
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Shows what would change without saving it")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(Command::new("list").about("Lists the configured rules"))
+                .subcommand(
+                    Command::new("test")
+                        .about("Shows which auto-tag rules would tag a task with this name")
+                        .arg(
+                            Arg::new("text")
+                                .help("A sample task name, like \"Call the bank\"")
+                                .required(true),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("report")
+                .about("Summarizes tasks in reports.")
+                .subcommand_required(true)
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("How to print the report")
+                        .value_parser(["table", "csv", "json", "markdown"])
+                        .default_value("table")
+                        .global(true),
+                )
+                .arg(
+                    Arg::new("svg")
+                        .long("svg")
+                        .help("Draws the report as a bar chart in this SVG file instead of printing it")
+                        .value_name("FILE")
+                        .global(true),
+                )
+                .subcommand(
+                    Command::new("pivot")
+                        .about("Counts tasks in a table of one dimension against another.")
+                        .arg(
+                            Arg::new("rows")
+                                .long("rows")
+                                .help("What the table's rows group tasks by.")
+                                .value_parser(PossibleValuesParser::new(PIVOT_DIMENSIONS))
+                                .default_value("category"),
+                        )
+                        .arg(
+                            Arg::new("cols")
+                                .long("cols")
+                                .help("What the table's columns group tasks by.")
+                                .value_parser(PossibleValuesParser::new(PIVOT_DIMENSIONS))
+                                .default_value("status"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("completion")
+                        .about("Shows how many days finished tasks took from creation to completion")
+                        .arg(
+                            Arg::new("by")
+                                .long("by")
+                                .help("How to group the tasks")
+                                .value_parser(["category", "priority"])
+                                .default_value("category"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("pomodoro")
+                        .about("Counts the finished pomodoros and their time on each task"),
+                )
+                .subcommand(
+                    Command::new("accuracy")
+                        .about("Compares the estimates of tasks with the time tracked on them"),
+                )
+                .subcommand(
+                    Command::new("stakeholder")
+                        .about("Writes a plain-language status update on a category, ready to send")
+                        .arg(
+                            Arg::new("category")
+                                .long("category")
+                                .help("The category to report on")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("days")
+                                .long("days")
+                                .help("How many days back to report finished tasks, and ahead for upcoming ones")
+                                .value_parser(clap::value_parser!(i64).range(1..))
+                                .default_value("7"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("project")
+                .about("Shows a one-screen summary of a category")
+                .arg(
+                    Arg::new("category")
+                        .help("The category to summarize")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            Command::new("workflow")
+                .about("Creates the tasks of a multi-step workflow from the config file.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("start")
+                        .about("Adds one task per step of the workflow.")
+                        .arg(
+                            Arg::new("workflow")
+                                .help("The workflow's name in the config file.")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("title")
+                                .help("What the workflow is for, added to every step's name.")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("category")
+                                .long("category")
+                                .help("The category of the new tasks.")
+                                .default_value("General"),
+                        )
+                        .arg(
+                            Arg::new("start")
+                                .long("start")
+                                .help("The date step offsets count from, as YYYY-MM-DD or a phrase like \"next monday\"; today by default."),
+                        ),
+                )
+                .subcommand(Command::new("list").about("Lists the workflows and their steps.")),
+        )
+        .subcommand(
+            Command::new("autobackup")
+                .about("Schedules an export preset to run automatically.")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("install")
+                        .about("Installs a systemd timer or cron entry running the preset.")
+                        .arg(
+                            Arg::new("preset")
+                                .help("The export preset to run.")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("every")
+                                .long("every")
+                                .help("How often to run it.")
+                                .value_parser(["hourly", "daily", "weekly"])
+                                .default_value("daily"),
+                        )
+                        .arg(
+                            Arg::new("cron")
+                                .long("cron")
+                                .help("Uses the crontab even when systemd is available.")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("status")
+                        .about("Shows how the last run of each export preset went."),
+                ),
+        )
+}
+
+fn handle_completion(cmd: &mut Command, sub_m: &clap::ArgMatches) {
+    let shell = sub_m.get_one::<String>("shell").unwrap();
+    match shell.as_str() {
+        "bash" => generate(Bash, cmd, "yawmak", &mut io::stdout()),
+        "zsh" => generate(Zsh, cmd, "yawmak", &mut io::stdout()),
+        "fish" => generate(Fish, cmd, "yawmak", &mut io::stdout()),
+        "powershell" => generate(PowerShell, cmd, "yawmak", &mut io::stdout()),
+        _ => println!("Unsupported shell"),
+    }
+}
+
+fn handle_add(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    if sub_m.get_flag("stdin") || sub_m.contains_id("file") {
+        return handle_bulk_add(conn, sub_m, settings).map_err(handle_db_error);
+    }
+
+    let mut task = match sub_m.get_one::<String>("from-template") {
+        Some(name) => match conn.get_template(name) {
+            Ok(Some(template)) => template.to_task(Local::now().date_naive()),
+            Ok(None) => {
+                println!("No template named '{}'.", name);
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(handle_db_error(e));
+            }
+        },
+        None => Task::new("", "General".to_string(), None, vec![], 0),
+    };
+
+    if let Some(task_description) = sub_m.get_one::<String>("TASK") {
+        task.name = task_description.to_string();
+    }
+    if let Some(due_date) = parse_due_date(sub_m.get_one::<String>("DUE_DATE"))? {
+        task.due_date = Some(due_date);
+    }
+    if let Some(category) = sub_m.get_one::<String>("category") {
+        task.category = Some(category.to_string());
+    }
+
+    // Correctly split the tags by comma
+    if let Some(tags) = sub_m.get_many::<String>("tags") {
+        task.tags = tags
+            .flat_map(|v| v.split(',').map(|s| s.trim().to_string()))
+            .collect();
+    }
+
+    if !sub_m.contains_id("from-template")
+        || sub_m.value_source("priority") == Some(ValueSource::CommandLine)
+    {
+        task.priority = *sub_m.get_one::<i32>("priority").unwrap();
+    }
+
+    if let Some(context) = sub_m.get_one::<String>("context") {
+        task.context = Some(context.to_string());
+    }
+    task.parent_id = sub_m.get_one::<i32>("parent").copied();
+    if let Some(start) = parse_due_date(sub_m.get_one::<String>("start"))? {
+        task.start_date = Some(start);
+    }
+    if let Some(rule) = parse_repeat(sub_m.get_one::<String>("repeat"))? {
+        task.repeat = Some(rule);
+    }
+    if let Some(icon) = sub_m.get_one::<String>("icon") {
+        task.icon = Some(icon.to_string());
+    }
+    if let Some(&estimate) = sub_m.get_one::<i64>("estimate") {
+        task.estimate = Some(estimate);
+    }
+    if let Some(who) = sub_m.get_one::<String>("assignee") {
+        task.assignee = Some(who.to_string());
+    }
+    for (name, value) in attribute_args(sub_m, "set", settings)? {
+        if !value.is_empty() {
+            task.attributes.insert(name, value);
+        }
+    }
+    if sub_m.get_flag("interactive") {
+        if let Err(e) = conn
+            .list_categories()
+            .and_then(|categories| prompt::fill_task(&mut task, &categories, &conn.list_tags()?))
+        {
+            return Err(handle_db_error(e));
+        }
+    }
+    task.created_by = settings.identity.as_ref().map(Identity::label);
+    add_new_task(conn, task, settings)
+}
+
+/// Adds a task for each non-blank line of the file or standard input. Lines
+/// are read like `capture` reads its sentence, so they can carry a due date,
+/// #tags, a priority and a category; `--category`, `--tags` and `--priority`
+/// fill in what a line leaves out. All the tasks are undone together.
+fn handle_bulk_add(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let text = match sub_m.get_one::<String>("file") {
+        Some(path) => fs::read_to_string(path)?,
+        None => io::read_to_string(io::stdin())?,
+    };
+    let category = sub_m.get_one::<String>("category");
+    let tags: Vec<String> = sub_m
+        .get_many::<String>("tags")
+        .unwrap_or_default()
+        .flat_map(|v| v.split(',').map(|s| s.trim().to_string()))
+        .collect();
+    let priority = *sub_m.get_one::<i32>("priority").unwrap();
+    let repeat = parse_repeat(sub_m.get_one::<String>("repeat"))?;
+    let start_date = parse_due_date(sub_m.get_one::<String>("start"))?;
+    let attributes = attribute_args(sub_m, "set", settings)?;
+
+    let today = Local::now().date_naive();
+    let categories = conn.list_categories()?;
+    let mut added = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let capture = capture::interpret(line, today, &categories);
+        if capture.name.is_empty() {
+            println!(
+                "Line {}: could not find a task in \"{}\".",
+                number + 1,
+                line.trim()
+            );
+            continue;
+        }
+        let mut task = Task::new(
+            &capture.name,
+            capture
+                .category
+                .or_else(|| category.cloned())
+                .unwrap_or_else(|| "General".to_string()),
+            None,
+            tags.iter()
+                .cloned()
+                .chain(capture.tags.into_iter().filter(|t| !tags.contains(t)))
+                .collect(),
+            if capture.priority > 0 {
+                capture.priority
+            } else {
+                priority
+            },
+        );
+        task.due_date = capture.due_date;
+        task.context = sub_m.get_one::<String>("context").cloned();
+        task.parent_id = sub_m.get_one::<i32>("parent").copied();
+        task.repeat = repeat.clone();
+        task.start_date = start_date;
+        task.icon = sub_m.get_one::<String>("icon").cloned();
+        task.estimate = sub_m.get_one::<i64>("estimate").copied();
+        task.assignee = sub_m.get_one::<String>("assignee").cloned();
+        task.attributes = attributes
+            .iter()
+            .filter(|(_, value)| !value.is_empty())
+            .cloned()
+            .collect();
+        task.created_by = settings.identity.as_ref().map(Identity::label);
+        add_auto_tags(&mut task, settings);
+        match conn.add_task(task) {
+            Ok(id) => added.push(id),
+            Err(e) => println!("Line {}: {}", number + 1, e),
+        }
+    }
+
+    let mut steps = Vec::new();
+    for &id in &added {
+        steps.extend(conn.add_checklist(id)?);
+    }
+    conn.journal("add", &[added.as_slice(), &steps].concat())?;
+    match added.len() {
+        1 => println!("Added 1 task."),
+        count => println!("Added {} tasks.", count),
+    }
+    Ok(())
+}
+
+// Adds a task built by `add` or `template apply` and warns about overloaded days and categories
+fn add_new_task(conn: &Database, mut task: Task, settings: &Settings) -> Result<(), TodoError> {
+    add_auto_tags(&mut task, settings);
+    let category = task.category.clone().unwrap_or_default();
+    let day = task.due_date;
+    if let Err(e) = conn.add_task(task).and_then(|id| {
+        let mut ids = vec![id];
+        ids.extend(conn.add_checklist(id)?);
+        conn.journal("add", &ids)
+    }) {
+        return Err(handle_db_error(e));
+    }
+
+    if let Some(day) = day {
+        warn_if_overloaded(conn, day, settings).map_err(handle_db_error)?;
+    }
+
+    if let Ok(loads) = conn.category_loads() {
+        for load in loads
+            .iter()
+            .filter(|l| l.name == category && l.is_over_limit())
+        {
+            println!(
+                "Warning: {} now has {} open tasks, over its WIP limit of {}.",
+                load.name,
+                load.open_tasks,
+                load.wip_limit.unwrap_or_default()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn handle_template(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    match sub_m.subcommand() {
+        Some(("save", save_m)) => {
+            let template = TaskTemplate {
+                name: save_m.get_one::<String>("NAME").unwrap().to_string(),
+                pattern: save_m.get_one::<String>("TASK").unwrap().to_string(),
+                category: save_m.get_one::<String>("category").cloned(),
+                tags: save_m
+                    .get_many::<String>("tags")
+                    .unwrap_or_default()
+                    .flat_map(|v| v.split(',').map(|s| s.trim().to_string()))
+                    .collect(),
+                priority: *save_m.get_one::<i32>("priority").unwrap(),
+                due_in: save_m.get_one::<i64>("due-in").copied(),
+            };
+            conn.save_template(&template)?;
+            println!("Saved template '{}'.", template.name);
+        }
+        Some(("list", _)) => {
+            let templates = conn.list_templates()?;
+            if templates.is_empty() {
+                println!("No templates yet. Save one with `yawmak template save`.");
+            } else {
+                Display::show_templates(&templates);
+            }
+        }
+        Some(("apply", apply_m)) => {
+            let name = apply_m.get_one::<String>("NAME").unwrap();
+            match conn.get_template(name)? {
+                Some(template) => {
+                    let mut task = template.to_task(Local::now().date_naive());
+                    task.created_by = settings.identity.as_ref().map(Identity::label);
+                    add_new_task(conn, task, settings)?;
+                }
+                None => println!("No template named '{}'.", name),
+            }
+        }
+        Some(("delete", delete_m)) => {
+            let name = delete_m.get_one::<String>("NAME").unwrap();
+            if conn.delete_template(name)? {
+                println!("Deleted template '{}'.", name);
+            } else {
+                println!("No template named '{}'.", name);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_list(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    if settings.run_rules_before_list {
+        run_rules(conn, &settings.rules, false)?;
+    }
+
+    let archived = sub_m.get_flag("archived");
+    // Archived tasks are all done, so they list like `--done-only`
+    let done_only = archived || sub_m.get_flag("done-only");
+    let mut tasks = if archived {
+        conn.get_archived_tasks()?
+    } else {
+        conn.get_tasks(Some(done_only))?
+    };
+
+    // Templated output feeds scripts and status bars, so it carries no notices
+    let template = sub_m.get_one::<String>("template");
+    let notices = template.is_none();
+
+    if !done_only && notices {
+        show_reminders(conn, &tasks, settings)?;
+    }
+
+    let context = match sub_m.get_one::<String>("context") {
+        Some(context) => Some(context.to_string()),
+        None if sub_m.get_flag("all-contexts") => None,
+        None => conn.get_state(ACTIVE_CONTEXT_KEY)?,
+    };
+    if let Some(context) = &context {
+        tasks.retain(|t| t.context.as_ref() == Some(context));
+        if notices {
+            println!("Context: {}", context);
+        }
+    }
+
+    let today = Local::now().date_naive();
+    if sub_m.get_flag("waiting") {
+        tasks.retain(|t| t.waiting_for.is_some());
+    } else if !done_only {
+        tasks.retain(|t| !t.is_waiting(today));
+        for task in tasks.iter().filter(|t| notices && t.needs_follow_up(today)) {
+            println!(
+                "Follow up on task {}: waiting for {}.",
+                task.id,
+                task.waiting_for.as_deref().unwrap_or_default()
+            );
+        }
+    }
+
+    if sub_m.get_flag("startable") {
+        tasks.retain(|t| t.is_startable(today));
+    }
+    if let Some(week) = sub_m.get_one::<String>("due-week") {
+        tasks.retain(|t| {
+            t.due_date
+                .is_some_and(|d| dates::week_label(d, settings.week_start) == *week)
+        });
+    }
+
+    if let Some(who) = sub_m.get_one::<String>("created-by") {
+        tasks.retain(|t| {
+            t.created_by
+                .as_deref()
+                .is_some_and(|by| Identity::refers_to(by, who))
+        });
+    }
+    if let Some(who) = sub_m.get_one::<String>("completed-by") {
+        tasks.retain(|t| {
+            t.completed_by
+                .as_deref()
+                .is_some_and(|by| Identity::refers_to(by, who))
+        });
+    }
+    if let Some(who) = sub_m.get_one::<String>("assignee") {
+        tasks.retain(|t| {
+            t.assignee
+                .as_deref()
+                .is_some_and(|to| Identity::refers_to(to, who))
+        });
+    }
+    for (name, value) in attribute_args(sub_m, "attr", settings)? {
+        tasks.retain(|t| t.attributes.get(&name).map_or("", String::as_str) == value);
+    }
+
+    let over_limit: Vec<CategoryLoad> = conn
+        .category_loads()?
+        .into_iter()
+        .filter(|c| c.is_over_limit())
+        .collect();
+    for load in over_limit.iter().filter(|_| notices) {
+        println!(
+            "{} is over its WIP limit: {} open tasks, limit {}.",
+            load.name,
+            load.open_tasks,
+            load.wip_limit.unwrap_or_default()
+        );
+    }
+
+    if sub_m.get_flag("interactive") {
+        let by = settings.identity.as_ref().map(Identity::label);
+        return Interactive::run(conn, tasks, by.as_deref());
+    }
+    if let Some(template) = template {
+        for task in &tasks {
+            println!("{}", template::render(template, task)?);
+        }
+        return Ok(());
+    }
+
+    let columns = TaskColumns {
+        completion_date: done_only,
+        context: context.is_none() && tasks.iter().any(|t| t.context.is_some()),
+        start_date: tasks.iter().any(|t| t.start_date.is_some()),
+        assignee: tasks.iter().any(|t| t.assignee.is_some()),
+        attributes: settings
+            .attributes
+            .iter()
+            .filter(|name| tasks.iter().any(|t| t.attributes.contains_key(*name)))
+            .cloned()
+            .collect(),
+        created_by: tasks.iter().any(|t| t.created_by.is_some()),
+        completed_by: tasks.iter().any(|t| t.completed_by.is_some()),
+        over_limit_categories: over_limit.into_iter().map(|c| c.name).collect(),
+        age: sub_m.get_flag("age").then_some(settings.stale_after_days),
+        week: sub_m.get_flag("week").then_some(settings.week_start),
+        // Sorting by urgency shows the scores it sorted by
+        urgency: (sub_m.get_flag("urgency")
+            || sub_m.get_one::<String>("sort").map(String::as_str) == Some("urgency"))
+        .then_some(settings.urgency),
+    };
+    let collation = settings.collation;
+    match sub_m.get_one::<String>("sort").map(String::as_str) {
+        Some("name") => collation.sort(&mut tasks, |t| &t.name),
+        Some("category") => {
+            collation.sort(&mut tasks, |t| t.category.as_deref().unwrap_or_default())
+        }
+        Some("due") => tasks.sort_by_key(|t| (t.due_date.is_none(), t.due_date)),
+        Some("priority") => tasks.sort_by_key(|t| std::cmp::Reverse(t.priority)),
+        Some("urgency") => tasks.sort_by(|a, b| {
+            let score = |t: &Task| settings.urgency.score(t, today);
+            score(b).total_cmp(&score(a))
+        }),
+        _ => {}
+    }
+    if sub_m.get_flag("tree") {
+        tasks = tree_order(tasks)
+            .into_iter()
+            .map(|(depth, mut task)| {
+                if depth > 0 {
+                    task.name = format!("{}└ {}", "  ".repeat(depth - 1), task.name);
+                }
+                task
+            })
+            .collect();
+    }
+    if sub_m.get_flag("accessible") {
+        Display::show_tasks_accessible(tasks, &columns);
+    } else {
+        Display::show_tasks(tasks, &columns);
+    }
+    Ok(())
+}
+
+fn show_reminders(conn: &Database, tasks: &[Task], settings: &Settings) -> Result<(), TodoError> {
+    let today = Local::now().date_naive();
+    let last_seen = conn
+        .get_state(LAST_SEEN_KEY)?
+        .and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok());
+    let lead_end = today + Duration::days(settings.reminder_lead_days);
+
+    let pending: Vec<&Task> = tasks.iter().filter(|t| !t.is_waiting(today)).collect();
+    let due_soon: Vec<&Task> = pending
+        .iter()
+        .copied()
+        .filter(|t| t.due_date.is_some_and(|d| d >= today && d <= lead_end))
+        .collect();
+    // Only tasks that went overdue since the last run, so old ones don't nag forever
+    let newly_overdue: Vec<&Task> = pending
+        .iter()
+        .copied()
+        .filter(|t| {
+            t.due_date
+                .is_some_and(|d| d < today && last_seen.is_none_or(|seen| d >= seen))
+        })
+        .collect();
+
+    Display::show_reminders(&due_soon, &newly_overdue);
+    conn.set_state(LAST_SEEN_KEY, Some(&today.format("%Y-%m-%d").to_string()))?;
+    Ok(())
+}
+
+fn handle_done(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let mut ids = parse_ids(sub_m)?;
+    if sub_m.get_flag("with-subtasks") {
+        for id in ids.clone() {
+            match conn.get_subtasks(id) {
+                Ok(subtasks) => ids.extend(
+                    subtasks
+                        .into_iter()
+                        .filter(|t| !t.done && !ids.contains(&t.id))
+                        .map(|t| t.id)
+                        .collect::<Vec<_>>(),
+                ),
+                Err(e) => {
+                    return Err(handle_db_error(e));
+                }
+            }
+        }
+    }
+    let by = settings.identity.as_ref().map(Identity::label);
+    // Tasks finished together are related, to help find the context later
+    match conn
+        .complete_tasks(&ids, by.as_deref())
+        .and_then(|completion| {
+            conn.group_completed(&completion.done)?;
+            Ok(completion)
+        }) {
+        Ok(completion) => {
+            report_ids(&ids, &completion.done, "Marked as done");
+            for task in &completion.next {
+                println!(
+                    "Next \"{}\" added as task {}, due {}.",
+                    task.name,
+                    task.id,
+                    task.due_date.map(|d| d.to_string()).unwrap_or_default()
+                );
+            }
+        }
+        Err(e) => return Err(handle_db_error(e)),
+    }
+    Ok(())
+}
+
+// Prints the IDs that were changed and, separately, the ones that don't exist
+fn report_ids(ids: &[i32], changed: &[i32], what: &str) {
+    let join = |ids: Vec<&i32>| {
+        ids.iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let (found, missing): (Vec<&i32>, Vec<&i32>) = ids.iter().partition(|id| changed.contains(id));
+    if !found.is_empty() {
+        println!("{}: {}.", what, join(found));
+    }
+    if !missing.is_empty() {
+        println!("No tasks found with IDs: {}.", join(missing));
+    }
+}
+
+// Common function to handle updating tasks
+fn parse_id(sub_m: &clap::ArgMatches) -> Result<i32, TodoError> {
+    sub_m
+        .get_one::<String>("ID")
+        .unwrap()
+        .parse::<i32>()
+        .map_err(|_| {
+            TodoError::Custom("The ID you entered doesn't seem to be valid. Please enter a number, like 1 or 2, and try again.".into())
+        })
+}
+
+// The IDs given on the command line, with ranges like 10-15 expanded, in order and without repeats
+fn parse_ids(sub_m: &clap::ArgMatches) -> Result<Vec<i32>, TodoError> {
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    for arg in sub_m.get_many::<String>("IDS").unwrap() {
+        let range = match arg.split_once('-') {
+            Some((first, last)) => first.parse::<i32>().and_then(|f| Ok(f..=last.parse()?)),
+            None => arg.parse::<i32>().map(|id| id..=id),
+        };
+        match range {
+            Ok(range) if !range.is_empty() => {
+                ids.extend(range.filter(|id| seen.insert(*id)));
+            }
+            _ => {
+                return Err(TodoError::Custom(format!("'{}' doesn't seem to be a valid ID. Please enter numbers or ranges, like 1 or 10-15, and try again.", arg)));
+            }
+        }
+    }
+    Ok(ids)
+}
+
+// The recurrence rule given on the command line, checked and written the way it is stored
+fn parse_repeat(rule: Option<&String>) -> Result<Option<String>, TodoError> {
+    rule.map(|rule| {
+        if Recurrence::parse(rule).is_none() {
+            return Err(TodoError::Custom(format!("'{}' isn't a repeat rule yawmak understands. Please use daily, weekly, monthly, yearly, or one like \"every 2 weeks\".", rule)));
+        }
+        Ok(rule.trim().to_lowercase())
+    })
+    .transpose()
+}
+
+// A date given on the command line: YYYY-MM-DD, a phrase like "friday" or "end of month",
+// or an offset like "+3d" from today
+fn parse_due_date(due_date: Option<&String>) -> Result<Option<NaiveDate>, TodoError> {
+    parse_due_date_from(due_date, None)
+}
+
+// Like `parse_due_date`, but offsets count from `base` when there is one
+fn parse_due_date_from(
+    due_date: Option<&String>,
+    base: Option<NaiveDate>,
+) -> Result<Option<NaiveDate>, TodoError> {
+    let today = Local::now().date_naive();
+    due_date
+        .map(|d| {
+            dates::parse(d, today, base.unwrap_or(today)).ok_or_else(|| {
+                TodoError::Custom("Invalid date format. Please use YYYY-MM-DD, a phrase like \"tomorrow\", \"friday\", \"next week\" or \"end of month\", or an offset like \"+3d\" or \"+2w\".".into())
+            })
+        })
+        .transpose()
+}
+
+
+fn handle_update(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    // Bad values are refused before any task is looked at or changed
+    parse_due_date(sub_m.get_one::<String>("DUE_DATE"))?;
+    parse_repeat(sub_m.get_one::<String>("repeat"))?;
+    parse_due_date(sub_m.get_one::<String>("start"))?;
+    attribute_args(sub_m, "set", settings)?;
+
+    let batch = sub_m.get_one::<String>("where");
+    let ids = match batch {
+        Some(filter) => match choose_batch(conn, sub_m, settings, filter)? {
+            Some(ids) => ids,
+            None => return Ok(()),
+        },
+        None => vec![parse_id(sub_m)?],
+    };
+
+    // The tasks change together, and undo together, or not at all
+    let updated = conn.in_transaction(|| {
+        conn.journal("update", &ids)?;
+        ids.iter()
+            .map(|&id| update_from_args(conn, id, sub_m, settings))
+            .collect::<Result<Vec<_>, _>>()
+    });
+    match updated {
+        Ok(due_dates) => {
+            match (batch, ids.len()) {
+                (None, _) => {}
+                (Some(_), 1) => println!("Updated 1 task."),
+                (Some(_), count) => println!("Updated {} tasks.", count),
+            }
+            let days: BTreeSet<NaiveDate> = due_dates.into_iter().flatten().collect();
+            for day in days {
+                warn_if_overloaded(conn, day, settings).map_err(handle_db_error)?;
+            }
+        }
+        Err(e) => return Err(handle_db_error(e)),
+    }
+    Ok(())
+}
+
+/// Lists the tasks matching the `--where` filter of `update` and asks
+/// before changing them, unless `--yes` is given. Returns their IDs, or None
+/// when there are none or the update is called off.
+fn choose_batch(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+    filter: &str,
+) -> Result<Option<Vec<i32>>, TodoError> {
+    let today = Local::now().date_naive();
+    let filter = Filter::parse(filter, today, &settings.attributes).map_err(TodoError::Custom)?;
+    let tasks: Vec<Task> = conn
+        .get_tasks(None)?
+        .into_iter()
+        .filter(|t| filter.matches(t))
+        .collect();
+    if tasks.is_empty() {
+        println!("No tasks match the filter.");
+        return Ok(None);
+    }
+
+    let ids: Vec<i32> = tasks.iter().map(|t| t.id).collect();
+    let count = ids.len();
+    Display::show_tasks(tasks, &TaskColumns::default());
+    let question = match count {
+        1 => "Update this task?".to_string(),
+        count => format!("Update these {} tasks?", count),
+    };
+    if !sub_m.get_flag("yes") && !confirm(&question)? {
+        println!("Nothing was changed.");
+        return Ok(None);
+    }
+    Ok(Some(ids))
+}
+
+// Applies the changes given to `update` to one task and returns its new due
+// date, if it got one. Offsets like +10d move the task's own due date.
+fn update_from_args(
+    conn: &Database,
+    id: i32,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<Option<NaiveDate>, TodoError> {
+    let new_task = sub_m.get_one::<String>("TASK").map(|d| d.to_string());
+    let due_date = sub_m.get_one::<String>("DUE_DATE");
+    let current_due = match due_date {
+        Some(_) => conn.get_task(id)?.and_then(|t| t.due_date),
+        None => None,
+    };
+    let new_due_date = parse_due_date_from(due_date, current_due)?;
+    let new_category = sub_m.get_one::<String>("category").map(|d| d.to_string());
+    let new_tags: Vec<String> = sub_m
+        .get_many::<String>("tags")
+        .unwrap_or_default()
+        .map(|v| v.to_string())
+        .collect();
+    let new_priority = sub_m.get_one::<i32>("priority").copied();
+    let new_context = sub_m.get_one::<String>("context").map(|c| c.to_string());
+    let mark_undone = *sub_m.get_one::<bool>("undone").unwrap_or(&false);
+    let repeat = parse_repeat(sub_m.get_one::<String>("repeat"))?;
+    let start_date = parse_due_date(sub_m.get_one::<String>("start"))?;
+
+    conn.update_task(
+        id,
+        new_task,
+        new_due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+        new_category,
+        new_tags,
+        new_priority,
+        new_context,
+        mark_undone,
+    )?;
+    if start_date.is_some() || sub_m.get_flag("no-start") {
+        conn.set_start_date(id, start_date)?;
+    }
+    if repeat.is_some() || sub_m.get_flag("no-repeat") {
+        conn.set_repeat(id, repeat.as_deref())?;
+    }
+    let icon = sub_m.get_one::<String>("icon");
+    if icon.is_some() || sub_m.get_flag("no-icon") {
+        conn.set_icon(id, icon.map(String::as_str))?;
+    }
+    let estimate = sub_m.get_one::<i64>("estimate").copied();
+    if estimate.is_some() || sub_m.get_flag("no-estimate") {
+        conn.set_estimate(id, estimate)?;
+    }
+    let assignee = sub_m.get_one::<String>("assignee");
+    if assignee.is_some() || sub_m.get_flag("unassign") {
+        conn.set_assignee(id, assignee.map(String::as_str))?;
+    }
+    for (name, value) in attribute_args(sub_m, "set", settings)? {
+        let value = Some(value.as_str()).filter(|v| !v.is_empty());
+        conn.set_attribute(id, &name, value)?;
+    }
+    Ok(new_due_date)
+}
+
+// The `FIELD=VALUE` pairs given to `arg`, whose fields must be named under `attributes` in the config
+fn attribute_args(
+    sub_m: &clap::ArgMatches,
+    arg: &str,
+    settings: &Settings,
+) -> Result<Vec<(String, String)>, TodoError> {
+    let pairs: Vec<(String, String)> = sub_m
+        .get_many::<(String, String)>(arg)
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+    if let Some((name, _)) = pairs
+        .iter()
+        .find(|(name, _)| !settings.attributes.contains(name))
+    {
+        return Err(TodoError::Custom(format!(
+            "'{}' isn't a custom field. Add it to `attributes` in the config to use it.",
+            name
+        )));
+    }
+    Ok(pairs)
+}
+
+// Adds the tags of the auto-tag rules that the task's name matches
+fn add_auto_tags(task: &mut Task, settings: &Settings) {
+    for tag in rules::auto_tags(&settings.auto_tags, &task.name) {
+        if !task.tags.iter().any(|t| t == tag) {
+            task.tags.push(tag.to_string());
+        }
+    }
+}
+
+// Warns when `day` holds more pending tasks than the daily capacity and points at a lighter day
+fn warn_if_overloaded(
+    conn: &Database,
+    day: NaiveDate,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let loads = Planner::day_loads(&conn.get_tasks(Some(false))?);
+    let load = loads.get(&day).copied().unwrap_or(0);
+    if load <= settings.daily_capacity {
+        return Ok(());
+    }
+
+    println!(
+        "Warning: {} now has {} tasks, more than your daily capacity of {}.",
+        day.format("%A, %B %-d"),
+        load,
+        settings.daily_capacity
+    );
+    let today = Local::now().date_naive();
+    if let Some(lighter) = Planner::nearest_lighter_day(&loads, day, today, settings.daily_capacity)
+    {
+        println!(
+            "{} is lighter, with {} tasks.",
+            lighter.format("%A, %B %-d"),
+            loads.get(&lighter).copied().unwrap_or(0)
+        );
+    }
+    Ok(())
+}
+
+fn handle_pin(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let Some(task) = conn.get_task(id)? else {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    };
+    if task.pinned {
+        println!("Task {} is already pinned.", id);
+        return Ok(());
+    }
+    if conn.count_pinned()? >= settings.max_pinned {
+        println!(
+            "You already have {} pinned tasks. Unpin one first to keep your focus list short.",
+            settings.max_pinned
+        );
+        return Ok(());
+    }
+
+    conn.set_pinned(id, true)?;
+    println!("Pinned task {}.", id);
+    Ok(())
+}
+
+fn handle_delete(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let ids = parse_ids(sub_m)?;
+    conn.journal("delete", &ids)?;
+    if sub_m.get_flag("permanent") {
+        let deleted = conn.delete_tasks(&ids)?;
+        report_ids(&ids, &deleted, "Deleted");
+    } else {
+        let trashed = conn.trash_tasks(&ids)?;
+        report_ids(&ids, &trashed, "Moved to the trash");
+    }
+    Ok(())
+}
+
+fn handle_trash(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    match sub_m.subcommand() {
+        Some(("list", _)) => {
+            let tasks = conn.get_trashed_tasks()?;
+            if tasks.is_empty() {
+                println!("The trash is empty.");
+            } else if sub_m.get_flag("accessible") {
+                Display::show_tasks_accessible(tasks, &TaskColumns::default());
+            } else {
+                Display::show_tasks(tasks, &TaskColumns::default());
+            }
+        }
+        Some(("restore", restore_m)) => {
+            let ids = parse_ids(restore_m)?;
+            let restored = conn.restore_from_trash(&ids)?;
+            report_ids(&ids, &restored, "Restored");
+        }
+        Some(("empty", empty_m)) => {
+            if !empty_m.get_flag("yes") && !confirm("Permanently delete everything in the trash?")?
+            {
+                println!("The trash was left as it is.");
+                return Ok(());
+            }
+            match conn.empty_trash()? {
+                1 => println!("Permanently deleted 1 task."),
+                count => println!("Permanently deleted {} tasks.", count),
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_archive(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let before = match sub_m.get_one::<String>("before") {
+        Some(date) => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+            TodoError::Custom(format!("'{}' is not a date like 2024-09-01.", date))
+        })?),
+        None => None,
+    };
+    match conn.archive_tasks(before)? {
+        0 => println!("No completed tasks to archive."),
+        1 => println!("Archived 1 completed task."),
+        count => println!("Archived {} completed tasks.", count),
+    }
+    Ok(())
+}
+
+fn handle_maintain(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let retention = &settings.retention;
+    let dry_run = sub_m.get_flag("dry-run");
+    let today = Local::now().date_naive();
+    let tasks = |count: usize| match count {
+        1 => "1 task".to_string(),
+        count => format!("{} tasks", count),
+    };
+    let mut rules = 0;
+
+    // Purging first leaves the tasks archived by this run alone
+    if let Some(days) = retention.purge_archived_after_days {
+        let before = today - Duration::days(days);
+        let count = conn.purge_shelved("archive", Some(before), dry_run)?;
+        let verb = if dry_run {
+            "Would permanently delete"
+        } else {
+            "Permanently deleted"
+        };
+        println!("{} {} archived before {}.", verb, tasks(count), before);
+        rules += 1;
+    }
+    if let Some(days) = retention.archive_done_after_days {
+        let before = today - Duration::days(days);
+        let count = if dry_run {
+            conn.count_archivable(Some(before))?
+        } else {
+            conn.archive_tasks(Some(before))?
+        };
+        let verb = if dry_run { "Would archive" } else { "Archived" };
+        println!("{} {} completed before {}.", verb, tasks(count), before);
+        rules += 1;
+    }
+    if let Some(days) = retention.purge_trash_after_days {
+        let before = today - Duration::days(days);
+        let count = conn.purge_shelved("trash", Some(before), dry_run)?;
+        let verb = if dry_run {
+            "Would permanently delete"
+        } else {
+            "Permanently deleted"
+        };
+        println!("{} {} trashed before {}.", verb, tasks(count), before);
+        rules += 1;
+    }
+
+    if rules == 0 {
+        println!("No retention rules are set. Add a [retention] section to the config file to use maintain.");
+    }
+    Ok(())
+}
+
+/// Removes the tasks completed more than `--older-than` ago, with their
+/// links, notes, tracked time and the like, or archives them with
+/// `--archive`. Deleting is journaled, so `undo` brings the tasks back with
+/// their comments, notes, annotations, tracked time and reminders, though
+/// not their relations.
+fn handle_purge(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let days = *sub_m.get_one::<i64>("older-than").unwrap();
+    let Some(before) = Local::now()
+        .date_naive()
+        .checked_sub_signed(Duration::days(days))
+    else {
+        return Err(TodoError::Custom(format!(
+            "{} days reaches back past the calendar. Please use a shorter --older-than.",
+            days
+        )));
+    };
+    let tasks = conn.done_before(before)?;
+    if tasks.is_empty() {
+        println!("No tasks were completed before {}.", before);
+        return Ok(());
+    }
+
+    let archive = sub_m.get_flag("archive");
+    let count = match tasks.len() {
+        1 => "1 task".to_string(),
+        count => format!("{} tasks", count),
+    };
+    if sub_m.get_flag("dry-run") {
+        Display::show_tasks(
+            tasks,
+            &TaskColumns {
+                completion_date: true,
+                ..Default::default()
+            },
+        );
+        let verb = if archive {
+            "Would archive"
+        } else {
+            "Would permanently delete"
+        };
+        println!("{} {} completed before {}.", verb, count, before);
+        return Ok(());
+    }
+
+    if archive {
+        conn.archive_tasks(Some(before))?;
+        println!("Archived {} completed before {}.", count, before);
+    } else {
+        let ids: Vec<i32> = tasks.iter().map(|t| t.id).collect();
+        conn.journal("delete", &ids)?;
+        conn.delete_tasks(&ids)?;
+        println!("Permanently deleted {} completed before {}.", count, before);
+    }
+    Ok(())
+}
+
+// The main list's tasks with a suspicious date, one entry per problem
+fn date_issues(tasks: &[Task]) -> Vec<(&Task, audit::DateIssue)> {
+    let today = Local::now().date_naive();
+    tasks
+        .iter()
+        .flat_map(|task| {
+            audit::check_dates(task, today)
+                .into_iter()
+                .map(move |issue| (task, issue))
+        })
+        .collect()
+}
+
+/// Lists the suspicious dates, or with `--fix` asks for a new value for
+/// each: a date sets it, "-" clears it and Enter leaves it. Each change is
+/// journaled, so `undo` takes it back.
+fn handle_audit_dates(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let tasks = conn.get_tasks(None)?;
+    let issues = date_issues(&tasks);
+    if issues.is_empty() {
+        println!("No suspicious dates found.");
+        return Ok(());
+    }
+    if !sub_m.get_flag("fix") {
+        Display::show_date_issues(&issues);
+        println!("Run `yawmak audit dates --fix` to correct them.");
+        return Ok(());
+    }
+
+    let today = Local::now().date_naive();
+    let mut fixed = 0;
+    for task in tasks
+        .iter()
+        .filter(|t| issues.iter().any(|(i, _)| i.id == t.id))
+    {
+        let mut task = task.clone();
+        let mut kept = Vec::new();
+        // A fix can settle or raise other problems, so they are checked again after each
+        while let Some(issue) = audit::check_dates(&task, today)
+            .into_iter()
+            .find(|issue| !kept.contains(&issue.field))
+        {
+            println!("\nTask {}: {} ({})", task.id, task.name, issue.problem);
+            let current = issue
+                .field
+                .get(&task)
+                .map(|d| d.to_string())
+                .unwrap_or_default();
+            let label = format!("New {} (YYYY-MM-DD, - to clear)", issue.field.label());
+            let date = loop {
+                match prompt::ask(&label, &current)? {
+                    None => break None,
+                    Some(answer) if answer == "-" => break Some(None),
+                    Some(answer) => match dates::parse(&answer, today, today) {
+                        Some(date) => break Some(Some(date)),
+                        None => println!("'{}' isn't a date yawmak understands.", answer),
+                    },
+                }
+            };
+            match date {
+                Some(date) => {
+                    conn.journal("update", &[task.id])?;
+                    conn.set_date(task.id, issue.field, date)?;
+                    issue.field.set(&mut task, date);
+                    fixed += 1;
+                }
+                None => kept.push(issue.field),
+            }
+        }
+    }
+    match fixed {
+        1 => println!("\nFixed 1 date."),
+        n => println!("\nFixed {} dates.", n),
+    }
+    Ok(())
+}
+
+// Runs every check and sums up what it found
+fn handle_doctor(conn: &Database) -> Result<(), TodoError> {
+    let tasks = conn.get_tasks(None)?;
+    let issues = date_issues(&tasks);
+    if issues.is_empty() {
+        println!("Dates: ok");
+    } else {
+        println!(
+            "Dates: {} suspicious {}; run `yawmak audit dates` to review them.",
+            issues.len(),
+            if issues.len() == 1 { "date" } else { "dates" }
+        );
+    }
+    Ok(())
+}
+
+fn handle_undo(conn: &Database) -> Result<(), TodoError> {
+    match conn.undo_last()? {
+        Some((action, ids)) => {
+            let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+            let noun = if ids.len() == 1 { "task" } else { "tasks" };
+            println!("Undid {} of {} {}.", action, noun, ids.join(", "));
+        }
+        None => println!("Nothing to undo."),
+    }
+    Ok(())
+}
+
+fn handle_last(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let count = *sub_m.get_one::<usize>("count").unwrap();
+    let operations = conn.recent_operations(count)?;
+    if operations.is_empty() {
+        println!("No commands recorded yet.");
+    } else {
+        Display::show_operations(&operations);
+    }
+    Ok(())
+}
+
+fn handle_history(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = sub_m.get_one::<i32>("ID").copied();
+    // A task's history is shown in full; otherwise only the last few days,
+    // or all of it when they reach back past the calendar
+    let since = match id {
+        Some(_) => None,
+        None => {
+            let days = *sub_m.get_one::<i64>("days").unwrap();
+            Duration::try_days(days - 1)
+                .and_then(|back| Local::now().date_naive().checked_sub_signed(back))
+        }
+    };
+    let entries = conn.history(id, since)?;
+    match (entries.is_empty(), id) {
+        (true, Some(id)) => println!("No changes recorded for task {}.", id),
+        (true, None) => println!("No changes recorded in that time."),
+        (false, _) => Display::show_history(&entries),
+    }
+    Ok(())
+}
+
+fn handle_replay(
+    conn: &Database,
+    config: &Config,
+    sub_m: &clap::ArgMatches,
+) -> Result<(), TodoError> {
+    let operation = match sub_m.get_one::<i64>("ID") {
+        Some(&id) => match conn.get_operation(id)? {
+            Some(operation) => operation,
+            None => {
+                println!("No recorded command with number {}.", id);
+                return Ok(());
+            }
+        },
+        None => match conn.last_interrupted_operation()? {
+            Some(operation) => operation,
+            None => {
+                println!("Nothing to replay: every recorded command finished.");
+                return Ok(());
+            }
+        },
+    };
+
+    println!("Replaying #{}: {}", operation.id, operation.command_line());
+    let mut cmd = build_cli();
+    let matches = cmd
+        .clone()
+        .try_get_matches_from(std::iter::once("yawmak".to_string()).chain(operation.args.clone()))
+        .map_err(|e| {
+            TodoError::Custom(format!(
+                "Command #{} can't be read any more: {}",
+                operation.id,
+                e.kind()
+            ))
+        })?;
+
+    let id = conn.begin_operation(&operation.args)?;
+    let result = dispatch(conn, config, &mut cmd, &matches);
+    conn.finish_operation(id, if result.is_ok() { "done" } else { "failed" })?;
+    if result.is_ok() && operation.status == "running" {
+        conn.finish_operation(operation.id, "replayed")?;
+    }
+    result
+}
+
+fn handle_extend(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    prepend: bool,
+) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let text = sub_m.get_one::<String>("TEXT").unwrap().trim();
+    if text.is_empty() {
+        println!("Nothing to add.");
+        return Ok(());
+    }
+    if conn.get_task(id)?.is_none() {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    }
+    // Checked before journaling, so a refused change leaves nothing to undo
+    conn.check_references(text)?;
+    conn.journal("update", &[id])?;
+    conn.extend_task_name(id, text, prepend)?;
+    println!("Updated task {}.", id);
+    Ok(())
+}
+
+fn handle_edit(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let Some(task) = conn.get_task(id)? else {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    };
+    let before = TaskFields::of(&task);
+    let (text, path) =
+        editor::edit_text(&format!("yawmak-task-{}", id), "toml", &before.to_toml())?;
+    let after = match TaskFields::from_toml(&text) {
+        Ok(after) => after,
+        Err(e) => {
+            println!("{}", e);
+            return Err(reported(&format!(
+                "Nothing was changed; your edits are in {}.",
+                path.display()
+            )));
+        }
+    };
+    fs::remove_file(&path)?;
+    if after == before {
+        println!("Task {} was not changed.", id);
+        return Ok(());
+    }
+
+    fn changed<T: PartialEq + Clone>(after: &T, before: &T) -> Option<T> {
+        (after != before).then(|| after.clone())
+    }
+    conn.journal("update", &[id])?;
+    conn.update_task(
+        id,
+        changed(&after.task, &before.task),
+        changed(&after.due_date, &before.due_date)
+            .flatten()
+            .map(|d| d.format("%Y-%m-%d").to_string()),
+        changed(&after.category, &before.category),
+        changed(&after.tags, &before.tags).unwrap_or_default(),
+        changed(&after.priority, &before.priority),
+        changed(&after.context, &before.context).flatten(),
+        false,
+    )?;
+    conn.clear_due_date_and_context(
+        id,
+        after.due_date.is_none() && before.due_date.is_some(),
+        after.context.is_none() && before.context.is_some(),
+    )?;
+    if after.tags.is_empty() {
+        for tag in &before.tags {
+            conn.remove_task_tag(id, tag)?;
+        }
+    }
+    println!("Updated task {}.", id);
+    Ok(())
+}
+
+fn handle_show(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let Some(task) = conn.get_task(id)? else {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    };
+    let related = conn.get_related_tasks(id)?;
+    let notes = conn.get_notes(id)?;
+    let annotations = conn.get_annotations(id)?;
+    let comments = conn.get_comments(id)?;
+    let history = conn.history(Some(id), None)?;
+    let subtasks = tree_order(conn.get_subtasks(id)?);
+
+    let mut extra = Vec::new();
+    let reminders = conn.get_reminders(id)?;
+    if !reminders.is_empty() {
+        extra.push(("Reminders".to_string(), describe_reminders(&reminders)));
+    }
+    let tracked = conn.tracked_minutes(id)?;
+    match conn.running_timer()? {
+        Some((timed, _)) if timed == id => extra.push((
+            "Tracked".to_string(),
+            format!("{} so far, timer running", dates::describe_minutes(tracked)),
+        )),
+        _ if tracked > 0 => extra.push(("Tracked".to_string(), dates::describe_minutes(tracked))),
+        _ => {}
+    }
+    Display::show_task_detail(&task, &extra);
+
+    if !subtasks.is_empty() {
+        let finished = subtasks.iter().filter(|(_, t)| t.done).count();
+        println!("\nSubtasks ({} of {} done):", finished, subtasks.len());
+        Display::show_checklist(&subtasks);
+    }
+
+    if !notes.is_empty() {
+        println!("\nNotes:");
+        Display::show_notes(&notes);
+    }
+
+    if !annotations.is_empty() {
+        println!("\nAnnotations:");
+        Display::show_annotations(&annotations);
+    }
+
+    if !comments.is_empty() {
+        println!("\nComments:");
+        Display::show_comments(&comments);
+    }
+
+    if !history.is_empty() {
+        println!("\nHistory:");
+        Display::show_task_history(&history);
+    }
+
+    if !related.is_empty() {
+        println!("\nRelated tasks:");
+        if sub_m.get_flag("accessible") {
+            Display::show_tasks_accessible(related, &TaskColumns::default());
+        } else {
+            Display::show_tasks(related, &TaskColumns::default());
+        }
+    }
+    Ok(())
+}
+
+fn handle_open(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let Some(task) = conn.get_task(id)? else {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    };
+    let mut links = task::urls(&task.name);
+    for note in conn.get_notes(id)? {
+        links.extend(task::urls(&note.body));
+    }
+    let mut seen = HashSet::new();
+    links.retain(|link| seen.insert(link.clone()));
+    if links.is_empty() {
+        println!("Task {} has no web links.", id);
+        return Ok(());
+    }
+
+    for link in &links {
+        if sub_m.get_flag("print") {
+            println!("{}", link);
+        } else {
+            println!("Opening {}", link);
+            open_in_browser(link)?;
+        }
+    }
+    Ok(())
+}
+
+// Hands the link to $BROWSER, or to the system's opener: open on macOS,
+// explorer on Windows and xdg-open elsewhere
+fn open_in_browser(link: &str) -> Result<(), TodoError> {
+    let opener = std::env::var("BROWSER").unwrap_or_else(|_| {
+        if cfg!(target_os = "macos") {
+            "open".to_string()
+        } else if cfg!(windows) {
+            "explorer".to_string()
+        } else {
+            "xdg-open".to_string()
+        }
+    });
+    let mut words = opener.split_whitespace();
+    let program = words.next().unwrap_or("xdg-open");
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(link)
+        .status()
+        .map_err(|e| TodoError::Custom(format!("Could not start '{}': {}", opener, e)))?;
+    if !status.success() {
+        return Err(TodoError::Custom(format!(
+            "'{}' could not open {}.",
+            opener, link
+        )));
+    }
+    Ok(())
+}
+
+fn handle_relate(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let ids: Vec<i32> = sub_m.get_many::<i32>("IDS").unwrap().copied().collect();
+    if ids[0] == ids[1] {
+        println!("A task can't be related to itself.");
+        return Ok(());
+    }
+    for &id in &ids {
+        if conn.get_task(id)?.is_none() {
+            println!("No task found with ID {}.", id);
+            return Ok(());
+        }
+    }
+    conn.relate_tasks(&ids)?;
+    println!("Related tasks {} and {}.", ids[0], ids[1]);
+    Ok(())
+}
+
+fn handle_remind(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let Some(task) = conn.get_task(id)? else {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    };
+
+    if sub_m.get_flag("clear") {
+        let removed = conn.clear_reminders(id)?;
+        println!("Removed {} reminder(s) from task {}.", removed, id);
+        return Ok(());
+    }
+
+    let lead_times: Vec<i64> = sub_m
+        .get_many::<String>("before")
+        .unwrap_or_default()
+        .map(|before| {
+            dates::lead_days(before).ok_or_else(|| {
+                TodoError::Custom(format!(
+                    "'{}' isn't a lead time yawmak understands. Please use one like 2d or 1w.",
+                    before
+                ))
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    for &days in &lead_times {
+        conn.add_reminder(id, days)?;
+    }
+
+    let reminders = conn.get_reminders(id)?;
+    if reminders.is_empty() {
+        println!("Task {} has no reminders.", id);
+    } else {
+        println!(
+            "Task {} reminds you {}.",
+            id,
+            describe_reminders(&reminders)
+        );
+    }
+    if task.due_date.is_none() {
+        println!("It has no due date yet, so they won't fire until it gets one.");
+    }
+    Ok(())
+}
+
+// Lead times as read in a sentence, e.g. "1 week and 2 days before it is due"
+fn describe_reminders(days_before: &[i64]) -> String {
+    let leads: Vec<String> = days_before
+        .iter()
+        .map(|&days| match days {
+            0 => "on the day".to_string(),
+            7 => "1 week before".to_string(),
+            1 => "1 day before".to_string(),
+            days if days % 7 == 0 => format!("{} weeks before", days / 7),
+            days => format!("{} days before", days),
+        })
+        .collect();
+    format!("{} it is due", leads.join(" and "))
+}
+
+fn handle_start(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    if conn.get_task(id)?.is_none() {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    }
+    if let Some((timed, _)) = conn.running_timer()? {
+        if timed == id {
+            println!("The timer is already running on task {}.", id);
+            return Ok(());
+        }
+    }
+
+    // Only one timer runs at a time
+    if let Some((timed, minutes)) = conn.stop_timer()? {
+        println!(
+            "Stopped the timer on task {} after {}.",
+            timed,
+            dates::describe_minutes(minutes)
+        );
+    }
+    conn.start_timer(id)?;
+    println!("Started the timer on task {}.", id);
+    Ok(())
+}
+
+fn handle_log(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let duration = sub_m.get_one::<String>("DURATION").unwrap();
+    let Some(minutes) = dates::minutes(duration) else {
+        return Err(TodoError::Custom(format!(
+            "'{}' isn't a length of time yawmak understands. Please use one like 45m, 2h or 1h30m.",
+            duration
+        )));
+    };
+    if !conn.log_time(id, minutes, false)? {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    }
+    println!(
+        "Logged {} on task {}; {} in all.",
+        dates::describe_minutes(minutes),
+        id,
+        dates::describe_minutes(conn.tracked_minutes(id)?)
+    );
+    Ok(())
+}
+
+fn handle_pomodoro(
+    conn: Database,
+    db_path: &str,
+    sub_m: &clap::ArgMatches,
+) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let work = *sub_m.get_one::<u64>("work").unwrap();
+    let rest = *sub_m.get_one::<u64>("break").unwrap();
+    let Some(task) = conn.get_task(id)? else {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    };
+    // The session is logged on its own, so a running timer would count it twice
+    if let Some((timed, _)) = conn.running_timer()? {
+        println!(
+            "The timer is running on task {}. Please `stop` it before starting a pomodoro.",
+            timed
+        );
+        return Ok(());
+    }
+
+    println!("Pomodoro on task {}: {}", id, task.name);
+    drop(conn);
+    Pomodoro::countdown("Work", work)?;
+    let done = {
+        let conn = Database::new(db_path)?;
+        conn.log_time(id, work as i64, true)?;
+        conn.pomodoro_counts()?
+            .into_iter()
+            .find(|count| count.id == id)
+            .map_or(0, |count| count.pomodoros)
+    };
+    println!(
+        "Logged {} on task {}; that makes {} pomodoro(s).",
+        dates::describe_minutes(work as i64),
+        id,
+        done
+    );
+    if rest > 0 {
+        Pomodoro::countdown("Break", rest)?;
+    }
+    Ok(())
+}
+
+fn handle_graph(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let mut tasks = conn.get_tasks((!sub_m.get_flag("all")).then_some(false))?;
+    tasks.sort_by_key(|t| t.id);
+    println!("{}", Graph::mermaid(&tasks, &conn.get_relations()?));
+    Ok(())
+}
 
-fn handle_completion(cmd: &mut Command, sub_m: &clap::ArgMatches) {
-    let shell = sub_m.get_one::<String>("shell").unwrap();
-    match shell.as_str() {
-        "bash" => generate(Bash, cmd, "yawmak", &mut io::stdout()),
-        "zsh" => generate(Zsh, cmd, "yawmak", &mut io::stdout()),
-        "fish" => generate(Fish, cmd, "yawmak", &mut io::stdout()),
-        "powershell" => generate(PowerShell, cmd, "yawmak", &mut io::stdout()),
-        _ => println!("Unsupported shell"),
+fn handle_backlinks(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let tasks = conn.get_backlinks(id)?;
+    if tasks.is_empty() {
+        println!("No tasks refer to task {}.", id);
+    } else if sub_m.get_flag("accessible") {
+        Display::show_tasks_accessible(tasks, &TaskColumns::default());
+    } else {
+        Display::show_tasks(tasks, &TaskColumns::default());
     }
+    Ok(())
 }
 
-fn handle_add(conn: &Database, sub_m: &clap::ArgMatches) {
-    let task_description = sub_m.get_one::<String>("TASK").unwrap();
-    let due_date = sub_m.get_one::<String>("DUE_DATE").map(|d| d.to_string());
-    let category = sub_m
-        .get_one::<String>("category")
-        .unwrap_or(&"General".to_string())
-        .to_string();
+fn handle_clone(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let Some(original) = conn.get_task(id)? else {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    };
+    let due_date = parse_due_date(sub_m.get_one::<String>("due"))?.or(original.due_date);
 
-    // Correctly split the tags by comma
-    let tags: Vec<String> = sub_m
-        .get_many::<String>("tags")
-        .unwrap_or_default()
-        .flat_map(|v| v.split(',').map(|s| s.trim().to_string()))
-        .collect();
+    let mut task = Task::new(
+        &original.name,
+        original.category.unwrap_or_else(|| "General".to_string()),
+        None,
+        original.tags,
+        original.priority,
+    );
+    task.due_date = due_date;
+    task.created_by = settings.identity.as_ref().map(Identity::label);
+    let clone_id = conn.add_task(task)?;
+    conn.journal("add", &[clone_id])?;
+    println!("Copied task {} to task {}.", id, clone_id);
+    if let Some(day) = due_date {
+        warn_if_overloaded(conn, day, settings)?;
+    }
+    Ok(())
+}
 
-    let priority: i32 = sub_m
-        .get_one::<String>("priority")
-        .unwrap()
-        .parse()
-        .unwrap_or_else(|_| {
-            eprintln!("Invalid priority value. Please enter a valid integer.");
-            process::exit(1);
-        });
+fn handle_note(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    match sub_m.subcommand() {
+        Some(("add", add_m)) => {
+            let id = parse_id(add_m)?;
+            let text = match add_m.get_one::<String>("TEXT") {
+                Some(text) => text.clone(),
+                None => io::read_to_string(io::stdin())?,
+            };
+            if text.trim().is_empty() {
+                println!("The note is empty, so nothing was added.");
+                return Ok(());
+            }
+            match conn.add_note(id, text.trim_end())? {
+                Some(note_id) => println!("Added note {} to task {}.", note_id, id),
+                None => println!("No task found with ID {}.", id),
+            }
+        }
+        Some(("list", list_m)) => {
+            let id = parse_id(list_m)?;
+            let notes = conn.get_notes(id)?;
+            if notes.is_empty() {
+                println!("Task {} has no notes.", id);
+            } else {
+                Display::show_notes(&notes);
+            }
+        }
+        Some(("delete", delete_m)) => {
+            let id = parse_id(delete_m)?;
+            if conn.delete_note(id)? {
+                println!("Deleted note {}.", id);
+            } else {
+                println!("No note found with ID {}.", id);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn handle_comment(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    if let Some(text) = sub_m.get_one::<String>("TEXT") {
+        let author = settings.identity.as_ref().map(Identity::label);
+        if conn.add_comment(id, author.as_deref(), text)? {
+            println!("Added a comment to task {}.", id);
+        } else {
+            println!("No task found with ID {}.", id);
+        }
+        return Ok(());
+    }
 
-    let task = Task::new(task_description, category, due_date, tags, priority);
-    if let Err(e) = conn.add_task(task) {
-        handle_db_error(e);
+    let comments = conn.get_comments(id)?;
+    if comments.is_empty() {
+        println!("Task {} has no comments.", id);
+    } else {
+        Display::show_comments(&comments);
     }
+    Ok(())
 }
 
-fn handle_list(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
-    let done_only = *sub_m.get_one::<bool>("done-only").unwrap_or(&false);
-    let tasks = conn.get_tasks(Some(done_only))?;
-    Display::show_tasks(tasks, done_only);
+fn handle_annotate(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let text = sub_m.get_one::<String>("TEXT").unwrap();
+    if text.trim().is_empty() {
+        println!("An annotation needs some text.");
+    } else if conn.add_annotation(id, text.trim())? {
+        println!("Annotated task {}.", id);
+    } else {
+        println!("No task found with ID {}.", id);
+    }
     Ok(())
 }
 
-fn handle_done(conn: &Database, sub_m: &clap::ArgMatches) {
-    let id = parse_id(sub_m);
-    if let Err(e) = conn.mark_task_done(id) {
-        handle_db_error(e);
+fn handle_unpin(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    if conn.set_pinned(id, false)? {
+        println!("Unpinned task {}.", id);
+    } else {
+        println!("No task found with ID {}.", id);
     }
+    Ok(())
 }
 
-// Common function to handle updating tasks
-fn parse_id(sub_m: &clap::ArgMatches) -> i32 {
-    sub_m
-        .get_one::<String>("ID")
-        .unwrap()
-        .parse::<i32>()
-        .unwrap_or_else(|_| {
-            eprintln!("The ID you entered doesn't seem to be valid. Please enter a number, like 1 or 2, and try again.");
-            process::exit(1);
-        })
+fn handle_waiting(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let id = parse_id(sub_m)?;
+    let waiting_for = sub_m.get_one::<String>("for");
+    let follow_up = parse_due_date(sub_m.get_one::<String>("follow-up"))?;
+
+    if !conn.set_waiting(id, waiting_for.map(|w| w.as_str()), follow_up)? {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    }
+
+    match (waiting_for, follow_up) {
+        (Some(what), Some(date)) => println!(
+            "Task {} is waiting for {}. It will reappear on {}.",
+            id, what, date
+        ),
+        (Some(what), None) => println!(
+            "Task {} is waiting for {}. See it with `yawmak list --waiting`.",
+            id, what
+        ),
+        _ => println!("Task {} is no longer waiting.", id),
+    }
+    Ok(())
 }
 
-fn parse_due_date(due_date: Option<&String>) -> Option<String> {
-    due_date.map(|d| {
-        if NaiveDate::parse_from_str(d, "%Y-%m-%d").is_err() {
-            eprintln!("Invalid date format. Please use YYYY-MM-DD.");
-            process::exit(1);
+fn handle_context(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    if sub_m.get_flag("clear") {
+        conn.set_state(ACTIVE_CONTEXT_KEY, None)?;
+        println!("Cleared the active context.");
+    } else if let Some(context) = sub_m.get_one::<String>("CONTEXT") {
+        conn.set_state(ACTIVE_CONTEXT_KEY, Some(context))?;
+        println!("Switched to context: {}", context);
+    } else {
+        match conn.get_state(ACTIVE_CONTEXT_KEY)? {
+            Some(context) => println!("Active context: {}", context),
+            None => println!("No active context."),
         }
-        d.to_string()
-    })
+    }
+    Ok(())
 }
 
-
-fn handle_update(conn: &Database, sub_m: &clap::ArgMatches) {
-    let id = parse_id(sub_m);
-    let new_task = sub_m.get_one::<String>("TASK").map(|d| d.to_string());
-    let new_due_date = parse_due_date(sub_m.get_one::<String>("DUE_DATE"));
-    let new_category = sub_m.get_one::<String>("category").map(|d| d.to_string());
-    let new_tags: Vec<String> = sub_m
-        .get_many::<String>("tags")
-        .unwrap_or_default()
-        .map(|v| v.to_string())
+fn handle_focus(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let tasks: Vec<Task> = conn
+        .get_tasks(Some(false))?
+        .into_iter()
+        .filter(|t| t.pinned)
         .collect();
-    let new_priority = sub_m.get_one::<String>("priority").map(|p| {
-        p.parse::<i32>().unwrap_or_else(|_| {
-            eprintln!("Invalid priority value. Please enter a valid integer.");
-            process::exit(1);
-        })
-    });
-    let mark_undone = *sub_m.get_one::<bool>("undone").unwrap_or(&false);
 
-    if let Err(e) = conn.update_task(
-        id,
-        new_task,
-        new_due_date,
-        new_category,
-        new_tags,
-        new_priority,
-        mark_undone,
-    ) {
-        handle_db_error(e);
+    if tasks.is_empty() {
+        println!("Nothing pinned. Use `yawmak pin <ID>` to choose what to focus on.");
+        return Ok(());
+    }
+
+    let columns = TaskColumns::default();
+    if sub_m.get_flag("accessible") {
+        Display::show_tasks_accessible(tasks, &columns);
+    } else {
+        Display::show_tasks(tasks, &columns);
     }
+    Ok(())
 }
 
 fn handle_search(conn: &Database, sub_m: &clap::ArgMatches) {
     let query = sub_m.get_one::<String>("QUERY").unwrap();
     let results = Search::find_tasks(conn, query);
-    Display::show_tasks(results, true);
+    let columns = TaskColumns {
+        completion_date: true,
+        ..Default::default()
+    };
+    if sub_m.get_flag("accessible") {
+        Display::show_tasks_accessible(results, &columns);
+    } else {
+        Display::show_tasks(results, &columns);
+    }
 }
 
-fn handle_add_category(conn: &Database, sub_m: &clap::ArgMatches) {
+fn handle_add_category(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
     let category_name = sub_m.get_one::<String>("CATEGORY_NAME").unwrap();
-    if let Err(e) = conn.add_category(category_name) {
-        if e.to_string().to_lowercase().contains("constraint") {
-            println!("Error: A category with the same name already exists.");
-        } else {
-            println!("An error occurred while adding the category: {}", e);
+    let wip_limit = sub_m.get_one::<i64>("wip");
+    let icon = sub_m.get_one::<String>("icon");
+    let checklist: Option<Vec<String>> = sub_m.get_many::<String>("checklist").map(|steps| {
+        steps
+            .map(|step| step.trim().to_string())
+            .filter(|step| !step.is_empty())
+            .collect()
+    });
+    let exists = conn
+        .list_categories()
+        .unwrap_or_default()
+        .contains(category_name);
+
+    // Re-adding an existing category is only useful to change its WIP limit, icon or checklist
+    if exists && wip_limit.is_none() && icon.is_none() && checklist.is_none() {
+        return Err(reported(
+            "Error: A category with the same name already exists.",
+        ));
+    }
+    if !exists {
+        if let Err(e) = conn.add_category(category_name) {
+            return Err(if e.to_string().to_lowercase().contains("constraint") {
+                reported("Error: A category with the same name already exists.")
+            } else {
+                reported(&format!(
+                    "An error occurred while adding the category: {}",
+                    e
+                ))
+            });
         }
-    } else {
         println!("Added category: {}", category_name);
     }
+
+    if let Some(&limit) = wip_limit {
+        let limit = (limit > 0).then_some(limit);
+        match conn.set_category_wip_limit(category_name, limit) {
+            Ok(()) => match limit {
+                Some(limit) => println!("Set WIP limit for {} to {}.", category_name, limit),
+                None => println!("Removed the WIP limit for {}.", category_name),
+            },
+            Err(e) => return Err(handle_db_error(e)),
+        }
+    }
+    if let Some(icon) = icon {
+        let icon = (!icon.is_empty()).then_some(icon.as_str());
+        match conn.set_category_icon(category_name, icon) {
+            Ok(()) => match icon {
+                Some(icon) => println!("Set the icon for {} to {}.", category_name, icon),
+                None => println!("Removed the icon for {}.", category_name),
+            },
+            Err(e) => return Err(handle_db_error(e)),
+        }
+    }
+    if let Some(steps) = checklist {
+        match conn.set_category_checklist(category_name, &steps) {
+            Ok(()) if steps.is_empty() => println!("Removed the checklist for {}.", category_name),
+            Ok(()) => println!(
+                "New tasks in {} get the checklist: {}.",
+                category_name,
+                steps.join(", ")
+            ),
+            Err(e) => return Err(handle_db_error(e)),
+        }
+    }
+    Ok(())
 }
 
-fn handle_delete_category(conn: &Database, sub_m: &clap::ArgMatches) {
+fn handle_delete_category(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
     let category_name = sub_m.get_one::<String>("CATEGORY_NAME").unwrap();
     if let Err(e) = conn.delete_category(category_name) {
-        if e.to_string().to_lowercase().contains("foreign key") {
-            println!("Error: Cannot delete category because it is still used by some tasks.");
+        return Err(if e.to_string().to_lowercase().contains("foreign key") {
+            reported("Error: Cannot delete category because it is still used by some tasks.")
         } else {
-            println!("An error occurred while deleting the category: {}", e);
-        }
-    } else {
-        println!("Deleted category: {}", category_name);
+            reported(&format!(
+                "An error occurred while deleting the category: {}",
+                e
+            ))
+        });
     }
+    println!("Deleted category: {}", category_name);
+    Ok(())
 }
 
-fn handle_list_categories(conn: &Database) -> Result<(), TodoError> {
-    let categories = conn.list_categories()?;
-    Display::show_categories(categories);
+fn handle_list_categories(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let mut categories = conn.category_loads()?;
+    settings.collation.sort(&mut categories, |c| &c.name);
+    if sub_m.get_flag("accessible") {
+        let names = categories
+            .iter()
+            .map(|c| match c.wip_limit {
+                Some(limit) => format!("{}, {} open, limit {}", c.name, c.open_tasks, limit),
+                None => format!("{}, {} open", c.name, c.open_tasks),
+            })
+            .collect();
+        Display::show_names_accessible("Category", names);
+    } else {
+        Display::show_categories(categories);
+    }
     Ok(())
 }
 
-fn handle_add_tag(conn: &Database, sub_m: &clap::ArgMatches) {
+fn handle_add_tag(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
     let tag_name = sub_m.get_one::<String>("TAG_NAME").unwrap();
     if let Err(e) = conn.add_tag(tag_name) {
-        if e.to_string().to_lowercase().contains("constraint") {
-            println!("Error: A tag with the same name already exists.");
+        return Err(if e.to_string().to_lowercase().contains("constraint") {
+            reported("Error: A tag with the same name already exists.")
         } else {
-            println!("An error occurred while adding the tag: {}", e);
-        }
-    } else {
-        println!("Added tag: {}", tag_name);
+            reported(&format!("An error occurred while adding the tag: {}", e))
+        });
     }
+    println!("Added tag: {}", tag_name);
+    Ok(())
 }
 
-fn handle_delete_tag(conn: &Database, sub_m: &clap::ArgMatches) {
+fn handle_delete_tag(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
     let tag_name = sub_m.get_one::<String>("TAG_NAME").unwrap();
     if let Err(e) = conn.delete_tag(tag_name) {
-        if e.to_string().to_lowercase().contains("foreign key") {
-            println!("Error: Cannot delete tag because it is still used by some tasks.");
+        return Err(if e.to_string().to_lowercase().contains("foreign key") {
+            reported("Error: Cannot delete tag because it is still used by some tasks.")
         } else {
-            println!("An error occurred while deleting the tag: {}", e);
-        }
-    } else {
-        println!("Deleted tag: {}", tag_name);
+            reported(&format!("An error occurred while deleting the tag: {}", e))
+        });
     }
+    println!("Deleted tag: {}", tag_name);
+    Ok(())
 }
 
-fn handle_list_tags(conn: &Database) -> Result<(), TodoError> {
-    let tags = conn.list_tags()?;
-    Display::show_tags(tags);
+fn handle_list_tags(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let mut tags = conn.list_tags()?;
+    settings.collation.sort(&mut tags, |t| t);
+    if sub_m.get_flag("accessible") {
+        Display::show_names_accessible("Tag", tags);
+    } else {
+        Display::show_tags(tags);
+    }
     Ok(())
 }
 
-fn handle_import(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+fn handle_import(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
     let format = sub_m.get_one::<String>("format").unwrap();
     let file_path = sub_m.get_one::<String>("file").unwrap();
     let strategy = sub_m.get_one::<String>("strategy").unwrap();
 
-    match format.as_str() {
-        "json" => {
-            conn.import_from_json(file_path, strategy)?;
-            println!(
-                "Data imported successfully from JSON with strategy '{}'.",
-                strategy
-            );
+    let strict = sub_m.get_flag("strict");
+    let existing: HashSet<i32> = conn.get_tasks(None)?.iter().map(|t| t.id).collect();
+
+    let report = match format.as_str() {
+        "json" => conn.import_from_json(file_path, strategy, strict)?,
+        "parquet" => conn.import_from_parquet(file_path, strategy, strict)?,
+        "xlsx" => conn.import_from_excel(file_path, strategy, strict)?,
+        "csv" => conn.import_from_csv(file_path, strategy, strict)?,
+        _ => {
+            println!("Unsupported format. Please use json, parquet, xlsx, or csv.");
+            return Ok(());
+        }
+    };
+
+    println!("Imported {} with strategy '{}'.", file_path, strategy);
+    Display::show_import_report(&report, sub_m.get_flag("report"));
+
+    // Only the tasks the file added are auto-tagged, not the ones it updated
+    for task in conn.get_tasks(None)? {
+        if existing.contains(&task.id) {
+            continue;
+        }
+        for tag in rules::auto_tags(&settings.auto_tags, &task.name) {
+            if !task.tags.iter().any(|t| t == tag) {
+                conn.add_task_tag(task.id, tag)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_export(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    if let Some(name) = sub_m.get_one::<String>("preset") {
+        // Recorded for `autobackup status`, whether run by hand or on a schedule
+        return match run_export_preset(conn, settings, name) {
+            Ok(file_path) => {
+                conn.record_export_run(name, true, &file_path)?;
+                println!("Exported preset '{}' to {}.", name, file_path);
+                Ok(())
+            }
+            Err(e) => {
+                conn.record_export_run(name, false, &e.to_string())?;
+                Err(e)
+            }
+        };
+    }
+
+    let format = sub_m.get_one::<String>("format").unwrap();
+    let file_path = sub_m.get_one::<String>("file").unwrap();
+    let scope = sub_m.get_one::<String>("scope").unwrap();
+    let list = |id: &str| -> Vec<String> {
+        sub_m
+            .get_many::<String>(id)
+            .unwrap_or_default()
+            .map(|c| c.trim().to_string())
+            .collect()
+    };
+    let redact = list("redact");
+
+    if sub_m.get_flag("comments") {
+        conn.export_comments(file_path, format, scope, &redact)?;
+        println!("Comments exported successfully to {}.", file_path);
+        return Ok(());
+    }
+    if sub_m.get_flag("annotations") {
+        conn.export_annotations(file_path, format, scope, &redact)?;
+        println!("Annotations exported successfully to {}.", file_path);
+        return Ok(());
+    }
+    if format == "site" {
+        let pages = Site::write(
+            std::path::Path::new(file_path),
+            &conn.export_tasks(scope, &redact)?,
+            Local::now().date_naive(),
+            settings.week_start,
+        )?;
+        println!("Wrote a site of {} pages to {}.", pages, file_path);
+        return Ok(());
+    }
+
+    let name = match format.as_str() {
+        "json" => "JSON",
+        "parquet" => "Parquet",
+        "xlsx" => "Excel",
+        "csv" => "CSV",
+        _ => {
+            println!("Unsupported format. Please use json, parquet, xlsx, csv, or site.");
+            return Ok(());
+        }
+    };
+    conn.export_filtered(file_path, format, scope, &list("fields"), &redact)?;
+    println!("Data exported successfully to {}.", name);
+
+    Ok(())
+}
+
+// Runs the named export preset and returns the path it wrote to
+fn run_export_preset(
+    conn: &Database,
+    settings: &Settings,
+    name: &str,
+) -> Result<String, TodoError> {
+    let preset = settings.export_presets.get(name).ok_or_else(|| {
+        TodoError::Custom(format!(
+            "No export preset named '{}' in the config file.",
+            name
+        ))
+    })?;
+
+    let file_path = preset.resolve_path(Local::now().date_naive());
+    // Remote paths such as s3:// are left to DuckDB
+    if !file_path.contains("://") {
+        if let Some(parent) = std::path::Path::new(&file_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    conn.export_filtered(
+        &file_path,
+        &preset.format,
+        &preset.filter,
+        &preset.columns,
+        &preset.redact,
+    )?;
+    Ok(file_path)
+}
+
+fn handle_capture(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let text = sub_m.get_one::<String>("text").unwrap();
+    let mut capture = capture::interpret(text, Local::now().date_naive(), &conn.list_categories()?);
+    if capture.name.is_empty() {
+        println!("Could not find a task in \"{}\".", text);
+        return Ok(());
+    }
+    for tag in rules::auto_tags(&settings.auto_tags, &capture.name) {
+        if !capture.tags.iter().any(|t| t == tag) {
+            capture.tags.push(tag.to_string());
         }
-        "parquet" => {
-            conn.import_from_parquet(file_path, strategy)?;
+    }
+
+    let category = capture.category.unwrap_or_else(|| "General".to_string());
+    println!("Task:     {}", capture.name);
+    println!(
+        "Due:      {}",
+        capture
+            .due_date
+            .map(|d| d.format("%A, %B %-d, %Y").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    println!("Priority: {}", capture.priority);
+    println!("Category: {}", category);
+    if !capture.tags.is_empty() {
+        println!("Tags:     {}", capture.tags.join(", "));
+    }
+    if !sub_m.get_flag("yes") && !confirm("Add this task?")? {
+        println!("Nothing was added.");
+        return Ok(());
+    }
+
+    let mut task = Task::new(
+        &capture.name,
+        category,
+        capture.due_date.map(|d| d.format("%Y-%m-%d").to_string()),
+        capture.tags,
+        capture.priority,
+    );
+    task.created_by = settings.identity.as_ref().map(Identity::label);
+    let day = task.due_date;
+    let id = conn.add_task(task)?;
+    let mut ids = vec![id];
+    ids.extend(conn.add_checklist(id)?);
+    conn.journal("add", &ids)?;
+    println!("Task added.");
+    if let Some(day) = day {
+        warn_if_overloaded(conn, day, settings)?;
+    }
+    Ok(())
+}
+
+/// Walks through the pending tasks of one category in batches, suggesting a
+/// new category for each from the `organize` hints in the config file. The
+/// confirmed moves of each batch are applied together, and undo as one change.
+fn handle_organize(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    if settings.organize.is_empty() {
+        println!("No keyword hints are set up. Add some to the config file, like:\n\n[[organize]]\nkeywords = \"invoice|tax\"\ncategory = \"Finance\"");
+        return Ok(());
+    }
+    let category = sub_m.get_one::<String>("category").unwrap();
+    let batch_size = *sub_m.get_one::<u64>("batch").unwrap() as usize;
+
+    let mut tasks: Vec<Task> = conn
+        .get_tasks(Some(false))?
+        .into_iter()
+        .filter(|t| t.category.as_deref() == Some(category.as_str()))
+        .collect();
+    tasks.sort_by_key(|t| t.id);
+    let suggestions: Vec<(&Task, &str)> = tasks
+        .iter()
+        .filter_map(|t| organize::suggest(&settings.organize, &t.name, category).map(|c| (t, c)))
+        .collect();
+    if suggestions.is_empty() {
+        println!(
+            "None of the {} pending tasks in {} match a keyword hint.",
+            tasks.len(),
+            category
+        );
+        return Ok(());
+    }
+
+    let batches = suggestions.len().div_ceil(batch_size);
+    let mut moved = 0;
+    for (number, batch) in suggestions.chunks(batch_size).enumerate() {
+        println!("\nBatch {} of {}:", number + 1, batches);
+        for (i, (task, suggested)) in batch.iter().enumerate() {
             println!(
-                "Data imported successfully from Parquet with strategy '{}'.",
-                strategy
+                "  {}. Task {}: {} -> {}",
+                i + 1,
+                task.id,
+                task.name,
+                suggested
             );
         }
-        "xlsx" => {
-            conn.import_from_excel(file_path, strategy)?;
+        let chosen: Vec<&(&Task, &str)> = loop {
+            let answer = prompt::ask("Move them? (y, n, numbers like 1 3, or q to stop)", "y")?;
+            match answer.as_deref().map(str::to_lowercase).as_deref() {
+                None | Some("y" | "yes") => break batch.iter().collect(),
+                Some("n" | "no") => break Vec::new(),
+                Some("q" | "quit") => {
+                    println!("Moved {} task(s) out of {}.", moved, category);
+                    return Ok(());
+                }
+                Some(numbers) => {
+                    let picked: Option<Vec<_>> = numbers
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|n| !n.is_empty())
+                        .map(|n| {
+                            n.parse::<usize>()
+                                .ok()
+                                .and_then(|n| batch.get(n.checked_sub(1)?))
+                        })
+                        .collect();
+                    match picked {
+                        Some(picked) => break picked,
+                        None => println!(
+                            "Please answer y, n, q, or numbers from 1 to {}.",
+                            batch.len()
+                        ),
+                    }
+                }
+            }
+        };
+        if chosen.is_empty() {
+            continue;
+        }
+
+        let ids: Vec<i32> = chosen.iter().map(|(task, _)| task.id).collect();
+        conn.journal("update", &ids)?;
+        for (task, suggested) in &chosen {
+            conn.update_task(
+                task.id,
+                None,
+                None,
+                Some(suggested.to_string()),
+                vec![],
+                None,
+                None,
+                false,
+            )?;
+        }
+        moved += chosen.len();
+        println!("Moved {} task(s).", chosen.len());
+    }
+
+    println!(
+        "\nMoved {} task(s) out of {}; {} had no suggestion.",
+        moved,
+        category,
+        tasks.len() - suggestions.len()
+    );
+    Ok(())
+}
+
+fn handle_rules(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    match sub_m.subcommand() {
+        Some(("run", run_m)) => {
+            let dry_run = run_m.get_flag("dry-run");
+            let changed = run_rules(conn, &settings.rules, dry_run)?;
+            match (changed, dry_run) {
+                (0, _) => println!("No rule applies to any task right now."),
+                (n, true) => println!("{} change(s) would be made.", n),
+                (n, false) => println!("{} change(s) made.", n),
+            }
+        }
+        Some(("list", _)) => {
+            if settings.rules.is_empty() && settings.auto_tags.is_empty() {
+                println!("No rules are defined in the config file.");
+            }
+            for rule in &settings.rules {
+                println!("{}: {}", rule.name, rule.then.describe());
+            }
+            for rule in &settings.auto_tags {
+                println!("Auto-tag {}: names mentioning {}", rule.tag, rule.keywords);
+            }
+        }
+        Some(("test", test_m)) => {
+            let text = test_m.get_one::<String>("text").unwrap();
+            let matching: Vec<_> = settings
+                .auto_tags
+                .iter()
+                .filter(|r| r.matches(text))
+                .collect();
+            if matching.is_empty() {
+                println!("No auto-tag rule matches \"{}\".", text);
+            }
+            for rule in matching {
+                println!("Tags it {} (matches {}).", rule.tag, rule.keywords);
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+// Applies each rule once to every pending task it matches, returning how many changes were made
+fn run_rules(conn: &Database, rules: &[rules::Rule], dry_run: bool) -> Result<usize, TodoError> {
+    let today = Local::now().date_naive();
+    let mut changed = 0;
+    for rule in rules {
+        let applied = conn.rule_applied_to(&rule.name)?;
+        // Reloaded per rule so later rules see what earlier ones changed
+        let tasks = conn.get_tasks(Some(false))?;
+        let matching: Vec<&Task> = tasks
+            .iter()
+            .filter(|t| !applied.contains(&t.id) && rule.matches(t, today))
+            .collect();
+        // Each rule's changes are undone together
+        if !dry_run && !matching.is_empty() {
+            let ids: Vec<i32> = matching.iter().map(|t| t.id).collect();
+            conn.journal("update", &ids)?;
+        }
+        for task in matching {
             println!(
-                "Data imported successfully from Excel with strategy '{}'.",
-                strategy
+                "Rule '{}' on task {} ({}): {}.",
+                rule.name,
+                task.id,
+                task.name,
+                rule.then.describe()
             );
+            changed += 1;
+            if dry_run {
+                continue;
+            }
+
+            if let Some(priority) = rule.then.new_priority(task.priority) {
+                conn.update_task(
+                    task.id,
+                    None,
+                    None,
+                    None,
+                    vec![],
+                    Some(priority),
+                    None,
+                    false,
+                )?;
+            }
+            if let Some(tag) = rule
+                .then
+                .add_tag
+                .as_ref()
+                .filter(|t| !task.tags.contains(t))
+            {
+                conn.add_task_tag(task.id, tag)?;
+            }
+            if let Some(tag) = &rule.then.remove_tag {
+                conn.remove_task_tag(task.id, tag)?;
+            }
+            conn.record_rule_run(&rule.name, task.id, today)?;
         }
-        "csv" => {
-            conn.import_from_csv(file_path, strategy)?;
-            println!(
-                "Data imported successfully from CSV with strategy '{}'.",
-                strategy
+    }
+    Ok(changed)
+}
+
+fn handle_report(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let format = sub_m.get_one::<String>("format").unwrap();
+    let svg = sub_m.get_one::<String>("svg");
+    match sub_m.subcommand() {
+        Some(("pivot", pivot_m)) => {
+            let rows = pivot_m.get_one::<String>("rows").unwrap();
+            let cols = pivot_m.get_one::<String>("cols").unwrap();
+            if rows == cols {
+                return Err(TodoError::Custom(
+                    "Please pick different dimensions for --rows and --cols.".into(),
+                ));
+            }
+            let pivot =
+                conn.pivot_report(rows, cols, Local::now().date_naive(), settings.week_start)?;
+            if pivot.rows.is_empty() {
+                println!("No tasks to report on.");
+            } else if let Some(path) = svg {
+                let bars: Vec<(String, i64)> = pivot
+                    .rows
+                    .iter()
+                    .map(|values| {
+                        let total = values[1..].iter().map(|v| v.parse().unwrap_or(0)).sum();
+                        (values[0].clone(), total)
+                    })
+                    .collect();
+                write_chart(path, &format!("Tasks by {}", rows), &bars)?;
+            } else {
+                Display::show_pivot(&pivot, format);
+            }
+        }
+        Some(("completion", completion_m)) => {
+            let by = completion_m.get_one::<String>("by").unwrap();
+            let times = conn.completion_times(by)?;
+            if times.is_empty() {
+                println!("No finished tasks with a creation date yet.");
+            } else if let Some(path) = svg {
+                let bars: Vec<(String, i64)> = times
+                    .iter()
+                    .map(|t| {
+                        let group = t.group.clone().unwrap_or_else(|| "All".to_string());
+                        (group, t.median.round() as i64)
+                    })
+                    .collect();
+                write_chart(path, &format!("Median days to finish, by {}", by), &bars)?;
+            } else {
+                Display::show_completion_times(by, &times, format);
+            }
+        }
+        Some(("accuracy", _)) => {
+            let tasks = conn.estimate_accuracy()?;
+            if tasks.is_empty() {
+                println!("No estimated tasks have tracked time yet.");
+            } else if let Some(path) = svg {
+                let bars: Vec<(String, i64)> = tasks
+                    .iter()
+                    .map(|t| {
+                        let share = t.tracked * 100 / t.estimate.max(1);
+                        (format!("{} {}", t.id, t.name), share)
+                    })
+                    .collect();
+                write_chart(path, "Tracked time as % of the estimate", &bars)?;
+            } else {
+                Display::show_estimate_accuracy(&tasks, format);
+            }
+        }
+        Some(("pomodoro", _)) => {
+            let counts = conn.pomodoro_counts()?;
+            if counts.is_empty() {
+                println!("No pomodoros finished yet.");
+            } else if let Some(path) = svg {
+                let bars: Vec<(String, i64)> = counts
+                    .iter()
+                    .map(|c| (format!("{} {}", c.id, c.name), c.pomodoros))
+                    .collect();
+                write_chart(path, "Pomodoros per task", &bars)?;
+            } else {
+                Display::show_pomodoros(&counts, format);
+            }
+        }
+        Some(("stakeholder", stakeholder_m)) => {
+            let category = stakeholder_m.get_one::<String>("category").unwrap();
+            let days = *stakeholder_m.get_one::<i64>("days").unwrap();
+            if svg.is_some() {
+                return Err(TodoError::Custom(
+                    "The stakeholder report is plain text and has no chart.".into(),
+                ));
+            }
+            if !conn.list_categories()?.contains(category) {
+                return Err(TodoError::Custom(format!(
+                    "No category named '{}'.",
+                    category
+                )));
+            }
+            let today = Local::now().date_naive();
+            let (from, until) = (
+                today - Duration::days(days - 1),
+                today + Duration::days(days),
+            );
+            let mut tasks: Vec<Task> = conn
+                .get_tasks(None)?
+                .into_iter()
+                .filter(|t| t.category.as_deref() == Some(category.as_str()))
+                .collect();
+            tasks.sort_by_key(|t| (t.completion_date, t.due_date, t.id));
+
+            let completed: Vec<&Task> = tasks
+                .iter()
+                .filter(|t| t.done && t.completion_date.is_some_and(|d| d >= from))
+                .collect();
+            let (upcoming, in_progress): (Vec<&Task>, Vec<&Task>) = tasks
+                .iter()
+                .filter(|t| !t.done)
+                .partition(|t| t.due_date.is_some_and(|d| d >= today && d <= until));
+            Display::show_stakeholder_report(
+                category,
+                (from, today),
+                &completed,
+                &in_progress,
+                &upcoming,
+                format,
             );
         }
-        _ => {
-            println!("Unsupported format. Please use json, parquet, xlsx, or csv.");
+        _ => {}
+    }
+    Ok(())
+}
+
+// Writes a report's values to `path` as an SVG bar chart
+fn write_chart(path: &str, title: &str, bars: &[(String, i64)]) -> Result<(), TodoError> {
+    fs::write(path, chart::bar_chart(title, bars))?;
+    println!("Wrote the chart to {}.", path);
+    Ok(())
+}
+
+fn handle_project(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    let name = sub_m.get_one::<String>("category").unwrap();
+    let today = Local::now().date_naive();
+    let counts = conn
+        .project_counts(name, today)?
+        .ok_or_else(|| TodoError::Custom(format!("No category named '{}'.", name)))?;
+
+    let tasks: Vec<Task> = conn
+        .get_tasks(None)?
+        .into_iter()
+        .filter(|t| t.category.as_deref() == Some(name.as_str()))
+        .collect();
+
+    let mut next_due: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| !t.done && t.due_date.is_some())
+        .collect();
+    next_due.sort_by_key(|t| t.due_date);
+    next_due.truncate(5);
+
+    let blocked: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| !t.done && t.waiting_for.is_some())
+        .collect();
+
+    let since = today - Duration::days(14);
+    let mut recent: Vec<(NaiveDate, &str, &Task)> = Vec::new();
+    for task in &tasks {
+        if let Some(date) = task.completion_date.filter(|d| *d >= since) {
+            recent.push((date, "done", task));
+        }
+        if let Some(date) = task.created_at.filter(|d| *d >= since) {
+            recent.push((date, "added", task));
         }
     }
+    recent.sort_by_key(|(date, _, _)| std::cmp::Reverse(*date));
+    recent.truncate(8);
 
+    // Tasks finished and added on each of the last 30 days, oldest first
+    let daily = |dates: Vec<NaiveDate>| -> Vec<i64> {
+        (0..30)
+            .rev()
+            .map(|ago| {
+                let day = today - Duration::days(ago);
+                dates.iter().filter(|d| **d == day).count() as i64
+            })
+            .collect()
+    };
+    let trends = [
+        (
+            "Done",
+            daily(tasks.iter().filter_map(|t| t.completion_date).collect()),
+        ),
+        (
+            "Added",
+            daily(tasks.iter().filter_map(|t| t.created_at).collect()),
+        ),
+    ];
+
+    Display::show_project(name, &counts, &trends, &next_due, &blocked, &recent);
     Ok(())
 }
 
-fn handle_export(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
-    let format = sub_m.get_one::<String>("format").unwrap();
-    let file_path = sub_m.get_one::<String>("file").unwrap();
+fn handle_workflow(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    match sub_m.subcommand() {
+        Some(("start", start_m)) => {
+            let name = start_m.get_one::<String>("workflow").unwrap();
+            let workflow = settings.workflows.get(name).ok_or_else(|| {
+                TodoError::Custom(format!("No workflow named '{}' in the config file.", name))
+            })?;
+            let start = parse_due_date(start_m.get_one::<String>("start"))?
+                .unwrap_or_else(|| Local::now().date_naive());
+            let title = start_m.get_one::<String>("title").unwrap();
+            let category = start_m.get_one::<String>("category").unwrap();
+
+            println!("Started {} for \"{}\":", name, title);
+            let mut ids = Vec::new();
+            for mut task in workflow.tasks(name, title, category, start) {
+                task.created_by = settings.identity.as_ref().map(Identity::label);
+                let line = format!(
+                    "  {} (due {})",
+                    task.name,
+                    task.due_date.map(|d| d.to_string()).unwrap_or_default()
+                );
+                ids.push(conn.add_task(task)?);
+                println!("{}", line);
+            }
+            conn.journal("add", &ids)?;
+        }
+        Some(("list", _)) => {
+            if settings.workflows.is_empty() {
+                println!("No workflows are defined in the config file.");
+            }
+            for (name, workflow) in &settings.workflows {
+                let steps: Vec<String> = workflow
+                    .steps
+                    .iter()
+                    .map(|step| format!("{} (+{}d)", step.name, step.offset_days))
+                    .collect();
+                println!("{}: {}", name, steps.join(" -> "));
+            }
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
 
-    match format.as_str() {
-        "json" => {
-            conn.export_to_json(file_path)?;
-            println!("Data exported successfully to JSON.");
+fn handle_autobackup(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    match sub_m.subcommand() {
+        Some(("install", install_m)) => {
+            let preset = install_m.get_one::<String>("preset").unwrap();
+            if !settings.export_presets.contains_key(preset) {
+                return Err(TodoError::Custom(format!(
+                    "No export preset named '{}' in the config file.",
+                    preset
+                )));
+            }
+            let every = install_m.get_one::<String>("every").unwrap();
+            let installed = AutoBackup::install(preset, every, install_m.get_flag("cron"))?;
+            println!(
+                "Preset '{}' will be exported {}: {}",
+                preset, every, installed
+            );
         }
-        "parquet" => {
-            conn.export_to_parquet(file_path)?;
-            println!("Data exported successfully to Parquet.");
+        Some(("status", _)) => {
+            match AutoBackup::installed() {
+                Some(job) => println!("Installed: {}", job),
+                None => println!("No automatic backup is installed."),
+            }
+            let runs = conn.last_export_runs()?;
+            if runs.is_empty() {
+                println!("No preset has been exported yet.");
+            } else {
+                Display::show_export_runs(&runs);
+            }
         }
-        "xlsx" => {
-            conn.export_to_excel(file_path)?;
-            println!("Data exported successfully to Excel.");
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+fn handle_goals(conn: &Database, sub_m: &clap::ArgMatches) -> Result<(), TodoError> {
+    match sub_m.subcommand() {
+        Some(("add", add_m)) => {
+            let (kind, name) = match add_m.get_one::<String>("tag") {
+                Some(tag) => ("tag", tag),
+                None => ("category", add_m.get_one::<String>("category").unwrap()),
+            };
+            let target = *add_m.get_one::<i64>("count").unwrap();
+            let period = add_m.get_one::<String>("per").unwrap();
+            let id = conn.add_goal(kind, name, target, period)?;
+            println!("Added goal {}.", id);
         }
-        "csv" => {
-            conn.export_to_csv(file_path)?;
-            println!("Data exported successfully to CSV.");
+        Some(("delete", delete_m)) => {
+            let id = parse_id(delete_m)?;
+            if conn.delete_goal(id)? {
+                println!("Deleted goal {}.", id);
+            } else {
+                println!("No goal found with ID {}.", id);
+            }
         }
         _ => {
-            println!("Unsupported format. Please use json, parquet, xlsx, or csv.");
+            let today = Local::now().date_naive();
+            let goals = conn.list_goals()?;
+
+            if let Some(&periods) = sub_m.get_one::<u32>("history") {
+                let mut history = Vec::new();
+                for goal in goals {
+                    let mut counts = Vec::new();
+                    for periods_ago in 0..periods {
+                        let (start, end) = goal.period_bounds(today, periods_ago);
+                        counts.push(conn.count_completed(&goal, start, end)?);
+                    }
+                    history.push((goal, counts));
+                }
+                Display::show_goal_history(&history);
+                return Ok(());
+            }
+
+            let mut progress = Vec::new();
+            for goal in goals {
+                let (start, end) = goal.period_bounds(today, 0);
+                let done = conn.count_completed(&goal, start, end)?;
+                progress.push((goal, done));
+            }
+            Display::show_goals(&progress, sub_m.get_flag("accessible"));
+        }
+    }
+    Ok(())
+}
+
+fn handle_rollover(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let period = sub_m.get_one::<String>("period").unwrap();
+    let moved = rollover(conn, period, settings.week_start)?;
+    if moved == 0 {
+        println!("Nothing to roll over.");
+    }
+    Ok(())
+}
+
+// Runs a weekly rollover the first time yawmak is used in a new week
+fn auto_rollover(conn: &Database, week_start: Weekday) -> Result<(), TodoError> {
+    let today = Local::now().date_naive();
+    let this_week = dates::first_day_of_week(today, week_start)
+        .format("%Y-%m-%d")
+        .to_string();
+    if conn.get_state(LAST_ROLLOVER_KEY)?.as_deref() == Some(this_week.as_str()) {
+        return Ok(());
+    }
+
+    rollover(conn, "week", week_start)?;
+    conn.set_state(LAST_ROLLOVER_KEY, Some(&this_week))
+}
+
+// Reschedules unfinished tasks from earlier periods to today, bumping their rollover tag.
+// The move is journaled, so `undo` puts the tasks back where they were.
+fn rollover(conn: &Database, period: &str, week_start: Weekday) -> Result<usize, TodoError> {
+    let today = Local::now().date_naive();
+    let period_start = match period {
+        "month" => goal::period_bounds(period, today, 0).0,
+        _ => dates::first_day_of_week(today, week_start),
+    };
+    let tasks: Vec<Task> = conn
+        .get_tasks(Some(false))?
+        .into_iter()
+        .filter(|t| t.scheduled_date.is_some_and(|d| d < period_start))
+        .collect();
+    if !tasks.is_empty() {
+        let ids: Vec<i32> = tasks.iter().map(|t| t.id).collect();
+        conn.journal("update", &ids)?;
+    }
+
+    for task in &tasks {
+        let previous = task.tags.iter().find_map(|tag| {
+            tag.strip_prefix(ROLLOVER_TAG_PREFIX)
+                .and_then(|n| n.parse::<u32>().ok())
+                .map(|n| (tag, n))
+        });
+        let count = match previous {
+            Some((tag, n)) => {
+                conn.remove_task_tag(task.id, tag)?;
+                n + 1
+            }
+            None => 1,
+        };
+
+        conn.set_scheduled_date(task.id, today)?;
+        conn.add_task_tag(task.id, &format!("{}{}", ROLLOVER_TAG_PREFIX, count))?;
+        println!(
+            "Rolled over task {} ({}) to today, {} time(s) so far.",
+            task.id, task.name, count
+        );
+    }
+    Ok(tasks.len())
+}
+
+fn handle_plan(
+    conn: &Database,
+    sub_m: &clap::ArgMatches,
+    settings: &Settings,
+) -> Result<(), TodoError> {
+    let start = Local::now().date_naive();
+    let end = match sub_m.get_one::<String>("PERIOD").unwrap().as_str() {
+        "month" => start + Months::new(1) - Duration::days(1),
+        _ => start + Duration::days(6),
+    };
+
+    let tasks = conn.get_tasks(Some(false))?;
+    let plan = Planner::plan(tasks, start, end, settings.daily_capacity);
+
+    Display::show_agenda(&plan.days);
+    for task in &plan.unplaced {
+        println!(
+            "No room for task {} ({}) before its due date; consider raising daily_capacity.",
+            task.id, task.name
+        );
+    }
+
+    if plan.scheduled.is_empty() {
+        println!("There are no unscheduled tasks due in this period.");
+        return Ok(());
+    }
+
+    if !sub_m.get_flag("yes")
+        && !confirm(&format!(
+            "Schedule {} task(s) as shown?",
+            plan.scheduled.len()
+        ))?
+    {
+        println!("Plan discarded.");
+        return Ok(());
+    }
+
+    conn.journal("update", &plan.scheduled)?;
+    for (day, tasks) in &plan.days {
+        for task in tasks.iter().filter(|t| plan.scheduled.contains(&t.id)) {
+            conn.set_scheduled_date(task.id, *day)?;
         }
     }
+    println!("Scheduled {} task(s).", plan.scheduled.len());
 
     Ok(())
 }
 
-fn handle_db_error(e: TodoError) {
+fn confirm(prompt: &str) -> Result<bool, TodoError> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Explains `e` to the user and hands it back as reported, so the command still ends as failed
+fn handle_db_error(e: TodoError) -> TodoError {
     let error_message = e.to_string().to_lowercase();
 
     if error_message.contains("no such file or directory") {
@@ -563,4 +4592,11 @@ fn handle_db_error(e: TodoError) {
     } else {
         println!("An unexpected error occurred: {}. Please try again or check the documentation for more details.", e);
     }
+    TodoError::Reported(e.to_string())
+}
+
+// Prints why a command failed and returns it as reported
+fn reported(message: &str) -> TodoError {
+    println!("{}", message);
+    TodoError::Reported(message.to_string())
 }
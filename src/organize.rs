@@ -0,0 +1,51 @@
+use crate::rules;
+use serde::Deserialize;
+
+// A category `organize` suggests for tasks whose names mention one of its keywords
+#[derive(Deserialize, Debug, Clone)]
+pub struct CategoryHint {
+    // Words or phrases separated by "|", like "invoice|tax|bank"
+    pub keywords: String,
+    pub category: String,
+}
+
+impl CategoryHint {
+    pub fn matches(&self, name: &str) -> bool {
+        rules::mentions(&self.keywords, name)
+    }
+}
+
+/// The category of the first hint that matches the task name, skipping
+/// hints for the category the task is already in.
+pub fn suggest<'a>(hints: &'a [CategoryHint], name: &str, current: &str) -> Option<&'a str> {
+    hints
+        .iter()
+        .find(|hint| hint.category != current && hint.matches(name))
+        .map(|hint| hint.category.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_takes_the_first_matching_hint() {
+        let hint = |keywords: &str, category: &str| CategoryHint {
+            keywords: keywords.to_string(),
+            category: category.to_string(),
+        };
+        let hints = vec![
+            hint("invoice|tax", "Finance"),
+            hint("plumber | garden", "Home"),
+            hint("send", "General"),
+        ];
+
+        assert_eq!(suggest(&hints, "File TAXES", "General"), Some("Finance"));
+        assert_eq!(suggest(&hints, "Call the plumber", "General"), Some("Home"));
+        assert_eq!(
+            suggest(&hints, "Send invoice for the garden", "General"),
+            Some("Finance")
+        );
+        assert_eq!(suggest(&hints, "Send card", "General"), None);
+    }
+}
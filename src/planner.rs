@@ -0,0 +1,178 @@
+use crate::task::Task;
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+pub struct Planner;
+
+pub struct Plan {
+    // Every day of the period with the tasks scheduled on it, new and existing
+    pub days: BTreeMap<NaiveDate, Vec<Task>>,
+    // IDs of the tasks that were given a scheduled date by this plan
+    pub scheduled: Vec<i32>,
+    // Tasks that did not fit before their due date
+    pub unplaced: Vec<Task>,
+}
+
+impl Planner {
+    /// Spreads unscheduled pending tasks due before `end` over the days from
+    /// `start` to `end`, never putting more than `capacity` tasks on a day.
+    /// Tasks are placed on the earliest day with room that is not after their
+    /// due date, most urgent first.
+    pub fn plan(tasks: Vec<Task>, start: NaiveDate, end: NaiveDate, capacity: usize) -> Plan {
+        let mut days: BTreeMap<NaiveDate, Vec<Task>> = BTreeMap::new();
+        let mut day = start;
+        while day <= end {
+            days.insert(day, Vec::new());
+            day += Duration::days(1);
+        }
+
+        let mut candidates = Vec::new();
+        for task in tasks.into_iter().filter(|t| !t.done) {
+            match task.scheduled_date {
+                Some(date) => {
+                    if let Some(day_tasks) = days.get_mut(&date) {
+                        day_tasks.push(task);
+                    }
+                }
+                None => {
+                    if task.due_date.is_some_and(|d| d <= end) {
+                        candidates.push(task);
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            a.due_date
+                .cmp(&b.due_date)
+                .then(b.priority.cmp(&a.priority))
+        });
+
+        let mut scheduled = Vec::new();
+        let mut unplaced = Vec::new();
+        for mut task in candidates {
+            // Overdue tasks can still only be done from today onwards
+            let latest = task.due_date.unwrap_or(end).max(start);
+            let slot = days
+                .range_mut(start..=latest)
+                .find(|(_, day_tasks)| day_tasks.len() < capacity);
+
+            match slot {
+                Some((date, day_tasks)) => {
+                    task.scheduled_date = Some(*date);
+                    scheduled.push(task.id);
+                    day_tasks.push(task);
+                }
+                None => unplaced.push(task),
+            }
+        }
+
+        Plan {
+            days,
+            scheduled,
+            unplaced,
+        }
+    }
+
+    /// Counts pending tasks per day, placing each on its scheduled date or,
+    /// when it has none, on its due date.
+    pub fn day_loads(tasks: &[Task]) -> BTreeMap<NaiveDate, usize> {
+        let mut loads = BTreeMap::new();
+        for task in tasks.iter().filter(|t| !t.done) {
+            if let Some(day) = task.scheduled_date.or(task.due_date) {
+                *loads.entry(day).or_insert(0) += 1;
+            }
+        }
+        loads
+    }
+
+    /// Finds the day nearest to `day` with fewer than `capacity` tasks, looking
+    /// up to two weeks either side but never before `today`. Earlier days win
+    /// ties, since moving work forward is safer for due dates.
+    pub fn nearest_lighter_day(
+        loads: &BTreeMap<NaiveDate, usize>,
+        day: NaiveDate,
+        today: NaiveDate,
+        capacity: usize,
+    ) -> Option<NaiveDate> {
+        (1..=14)
+            .flat_map(|offset| [day - Duration::days(offset), day + Duration::days(offset)])
+            .filter(|candidate| *candidate >= today)
+            .find(|candidate| loads.get(candidate).copied().unwrap_or(0) < capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: i32, due_date: &str, priority: i32) -> Task {
+        let mut task = Task::new(
+            "Task",
+            "General".to_string(),
+            Some(due_date.to_string()),
+            vec![],
+            priority,
+        );
+        task.id = id;
+        task
+    }
+
+    #[test]
+    fn test_plan_respects_capacity_and_due_dates() {
+        let start = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let end = start + Duration::days(6);
+        let tasks = vec![
+            task(1, "2024-09-02", 1),
+            task(2, "2024-09-02", 3),
+            task(3, "2024-09-04", 0),
+            task(4, "2024-09-30", 0),
+        ];
+
+        let plan = Planner::plan(tasks, start, end, 1);
+
+        // The higher priority task wins the only slot on its due date
+        assert_eq!(plan.days[&start][0].id, 2);
+        assert_eq!(plan.days[&(start + Duration::days(1))][0].id, 3);
+        assert_eq!(plan.scheduled, vec![2, 3]);
+        assert_eq!(plan.unplaced.len(), 1);
+        assert_eq!(plan.unplaced[0].id, 1);
+    }
+
+    #[test]
+    fn test_plan_counts_existing_schedule() {
+        let start = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let end = start + Duration::days(6);
+        let mut busy = task(1, "2024-09-08", 0);
+        busy.scheduled_date = Some(start);
+
+        let plan = Planner::plan(vec![busy, task(2, "2024-09-05", 0)], start, end, 1);
+
+        assert_eq!(plan.days[&start].len(), 1);
+        assert_eq!(plan.days[&(start + Duration::days(1))][0].id, 2);
+        assert_eq!(plan.scheduled, vec![2]);
+    }
+
+    #[test]
+    fn test_nearest_lighter_day() {
+        let today = NaiveDate::from_ymd_opt(2024, 9, 2).unwrap();
+        let busy = today + Duration::days(1);
+        let tasks = vec![
+            task(1, "2024-09-02", 0),
+            task(2, "2024-09-03", 0),
+            task(3, "2024-09-03", 0),
+        ];
+        let loads = Planner::day_loads(&tasks);
+        assert_eq!(loads[&busy], 2);
+
+        // The day before is full too, so the day after is the nearest with room
+        assert_eq!(
+            Planner::nearest_lighter_day(&loads, busy, today, 1),
+            Some(busy + Duration::days(1))
+        );
+        assert_eq!(
+            Planner::nearest_lighter_day(&loads, busy, today, 2),
+            Some(today)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use crate::error::TodoError;
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct Pomodoro;
+
+impl Pomodoro {
+    /// Counts `minutes` down on one line of the terminal, like
+    /// "Task 3: 24:59 left", and rings the bell when time is up. Interrupting
+    /// it with Ctrl-C ends yawmak, so an unfinished session is never recorded.
+    pub fn countdown(label: &str, minutes: u64) -> Result<(), TodoError> {
+        let total = minutes * 60;
+        let start = Instant::now();
+        let mut stdout = io::stdout();
+        loop {
+            let elapsed = start.elapsed().as_secs();
+            if elapsed >= total {
+                break;
+            }
+            print!("\r{}: {} left ", label, clock(total - elapsed));
+            stdout.flush()?;
+            thread::sleep(Duration::from_millis(250));
+        }
+        println!("\r{}: done!      \x07", label);
+        Ok(())
+    }
+}
+
+// Seconds as a countdown clock, like "24:59"
+fn clock(seconds: u64) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock() {
+        assert_eq!(clock(25 * 60), "25:00");
+        assert_eq!(clock(59), "00:59");
+        assert_eq!(clock(100 * 60 + 5), "100:05");
+    }
+}
@@ -0,0 +1,72 @@
+use crate::theme::{self, Role};
+
+// Priority levels by name, in rising order; tasks start at 0
+const LEVELS: &[(&str, i32)] = &[
+    ("none", 0),
+    ("low", 1),
+    ("medium", 2),
+    ("high", 3),
+    ("critical", 4),
+];
+
+// The highest priority a task can be given on the command line
+pub const MAX: i32 = 4;
+
+/// Reads a priority given as a level name or a number from 0 to 4. Used as
+/// clap's `value_parser`, so bad values are rejected with the usage message.
+pub fn parse(text: &str) -> Result<i32, String> {
+    let text = text.trim().to_lowercase();
+    if let Some(&(_, level)) = LEVELS.iter().find(|(name, _)| *name == text) {
+        return Ok(level);
+    }
+    match text.parse::<i32>() {
+        Ok(level) if (0..=MAX).contains(&level) => Ok(level),
+        _ => Err(format!(
+            "use low, medium, high or critical, or a number from 0 to {}",
+            MAX
+        )),
+    }
+}
+
+/// How a priority is shown: its level name, or the number for one raised
+/// past `MAX` by a rule.
+pub fn label(priority: i32) -> String {
+    LEVELS
+        .iter()
+        .find(|(_, level)| *level == priority)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| priority.to_string())
+}
+
+// How a priority is coloured, more urgent levels standing out more
+pub fn role(priority: i32) -> Option<Role> {
+    match priority {
+        p if p > 3 => Some(Role::Critical),
+        3 => Some(Role::High),
+        2 => Some(Role::Medium),
+        1 => Some(Role::Low),
+        _ => None,
+    }
+}
+
+// The table style for a priority in the current theme
+pub fn style(priority: i32) -> &'static str {
+    role(priority).map(theme::style).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_names_and_numbers() {
+        assert_eq!(parse("High"), Ok(3));
+        assert_eq!(parse("critical"), Ok(4));
+        assert_eq!(parse("2"), Ok(2));
+        assert!(parse("5").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("urgent").is_err());
+        assert_eq!(label(1), "low");
+        assert_eq!(label(6), "6");
+    }
+}
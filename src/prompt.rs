@@ -0,0 +1,137 @@
+use crate::error::TodoError;
+use crate::priority;
+use crate::task::Task;
+use chrono::NaiveDate;
+use std::io::{self, Write};
+
+/// Asks for a value on one line, showing `current` in brackets. An empty
+/// answer keeps the current value and gives `None`.
+pub fn ask(label: &str, current: &str) -> Result<Option<String>, TodoError> {
+    print!("{} [{}]: ", label, current);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer)? == 0 {
+        return Err(TodoError::Custom(
+            "The input ended before all answers were given.".into(),
+        ));
+    }
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+// Asks again until `parse` accepts the answer, printing why it didn't
+fn ask_valid<T>(
+    label: &str,
+    current: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<Option<T>, TodoError> {
+    loop {
+        match ask(label, current)? {
+            Some(answer) => match parse(&answer) {
+                Ok(value) => return Ok(Some(value)),
+                Err(problem) => println!("{}", problem),
+            },
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Walks through the fields of a new task one prompt at a time, starting
+/// from the values already in `task`. The category can be picked by number
+/// or name from `categories`, and a category or tag can be typed as the
+/// start of an existing one. Enter "-" to leave the due date or tags empty.
+pub fn fill_task(task: &mut Task, categories: &[String], tags: &[String]) -> Result<(), TodoError> {
+    loop {
+        if let Some(name) = ask("Task", &task.name)? {
+            task.name = name;
+        }
+        if !task.name.is_empty() {
+            break;
+        }
+        println!("Please describe the task.");
+    }
+
+    let due = |date: Option<NaiveDate>| date.map(|d| d.format("%Y-%m-%d").to_string());
+    if let Some(date) = ask_valid(
+        "Due date (YYYY-MM-DD)",
+        &due(task.due_date).unwrap_or_default(),
+        |a| {
+            if a == "-" {
+                return Ok(None);
+            }
+            NaiveDate::parse_from_str(a, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| format!("'{}' is not a date like 2024-09-01.", a))
+        },
+    )? {
+        task.due_date = date;
+    }
+
+    for (i, category) in categories.iter().enumerate() {
+        println!("  {}. {}", i + 1, category);
+    }
+    let current = task
+        .category
+        .clone()
+        .unwrap_or_else(|| "General".to_string());
+    if let Some(answer) = ask("Category (number or name)", &current)? {
+        let category = match answer.parse::<usize>() {
+            Ok(n) if (1..=categories.len()).contains(&n) => categories[n - 1].clone(),
+            _ => complete(&answer, categories),
+        };
+        task.category = Some(category);
+    }
+
+    if !tags.is_empty() {
+        println!("Existing tags: {}", tags.join(", "));
+    }
+    if let Some(answer) = ask("Tags (comma separated)", &task.tags.join(", "))? {
+        task.tags = answer
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty() && *t != "-")
+            .map(|t| complete(t, tags))
+            .collect();
+    }
+
+    if let Some(priority) = ask_valid("Priority", &priority::label(task.priority), |a| {
+        priority::parse(a).map_err(|e| format!("'{}' isn't a priority: {}.", a, e))
+    })? {
+        task.priority = priority;
+    }
+    Ok(())
+}
+
+/// Completes `input` to the option it names, ignoring case: an exact match,
+/// or the only option starting with it. Anything else is kept as typed.
+pub fn complete(input: &str, options: &[String]) -> String {
+    let lower = input.to_lowercase();
+    if let Some(exact) = options.iter().find(|o| o.to_lowercase() == lower) {
+        return exact.clone();
+    }
+    let mut matches = options
+        .iter()
+        .filter(|o| o.to_lowercase().starts_with(&lower));
+    match (matches.next(), matches.next()) {
+        (Some(only), None) => only.clone(),
+        _ => input.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        let options = vec![
+            "Work".to_string(),
+            "Writing".to_string(),
+            "Home".to_string(),
+        ];
+        assert_eq!(complete("ho", &options), "Home");
+        assert_eq!(complete("work", &options), "Work");
+        assert_eq!(complete("w", &options), "w");
+        assert_eq!(complete("Garden", &options), "Garden");
+    }
+}
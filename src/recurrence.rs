@@ -0,0 +1,100 @@
+use chrono::{Duration, Months, NaiveDate};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+// How often a recurring task comes back, like "weekly" or "every 2 weeks"
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Recurrence {
+    every: u32,
+    unit: Unit,
+}
+
+impl Recurrence {
+    /// Reads a rule: daily, weekly, monthly or yearly, or "every N days"
+    /// (weeks, months, years), where "every week" means every 1 week.
+    pub fn parse(rule: &str) -> Option<Self> {
+        let rule = rule.trim().to_lowercase();
+        let named = match rule.as_str() {
+            "daily" => Some(Unit::Day),
+            "weekly" => Some(Unit::Week),
+            "monthly" => Some(Unit::Month),
+            "yearly" => Some(Unit::Year),
+            _ => None,
+        };
+        if let Some(unit) = named {
+            return Some(Recurrence { every: 1, unit });
+        }
+
+        let words: Vec<&str> = rule.split_whitespace().collect();
+        let (every, unit) = match words.as_slice() {
+            ["every", unit] => (1, *unit),
+            ["every", count, unit] => (count.parse().ok()?, *unit),
+            _ => return None,
+        };
+        let unit = match unit.trim_end_matches('s') {
+            "day" => Unit::Day,
+            "week" => Unit::Week,
+            "month" => Unit::Month,
+            "year" => Unit::Year,
+            _ => return None,
+        };
+        (every > 0).then_some(Recurrence { every, unit })
+    }
+
+    /// The date one interval after `date`. Months that are too short for the
+    /// day, like February for the 31st, give their last day.
+    pub fn after(&self, date: NaiveDate) -> NaiveDate {
+        match self.unit {
+            Unit::Day => date + Duration::days(self.every as i64),
+            Unit::Week => date + Duration::weeks(self.every as i64),
+            Unit::Month => date + Months::new(self.every),
+            Unit::Year => date + Months::new(self.every * 12),
+        }
+    }
+
+    /// When the next instance is due: one interval after `due`, or after
+    /// `today` when there was no due date, moved on until it is after today
+    /// so a late completion doesn't leave the next one overdue already.
+    pub fn next_due(&self, due: Option<NaiveDate>, today: NaiveDate) -> NaiveDate {
+        let mut next = self.after(due.unwrap_or(today));
+        while next <= today {
+            next = self.after(next);
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let every = |every, unit| Some(Recurrence { every, unit });
+        assert_eq!(Recurrence::parse("daily"), every(1, Unit::Day));
+        assert_eq!(Recurrence::parse("Weekly"), every(1, Unit::Week));
+        assert_eq!(Recurrence::parse("every 2 weeks"), every(2, Unit::Week));
+        assert_eq!(Recurrence::parse("every month"), every(1, Unit::Month));
+        assert_eq!(Recurrence::parse("every 0 days"), None);
+        assert_eq!(Recurrence::parse("fortnightly"), None);
+    }
+
+    #[test]
+    fn test_next_due() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2025, m, d).unwrap();
+        let today = date(3, 5);
+        let weekly = Recurrence::parse("weekly").unwrap();
+        let monthly = Recurrence::parse("monthly").unwrap();
+
+        assert_eq!(weekly.next_due(Some(date(3, 4)), today), date(3, 11));
+        assert_eq!(weekly.next_due(Some(date(2, 1)), today), date(3, 8));
+        assert_eq!(weekly.next_due(None, today), date(3, 12));
+        assert_eq!(monthly.next_due(Some(date(1, 31)), date(2, 1)), date(2, 28));
+    }
+}
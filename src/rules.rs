@@ -0,0 +1,178 @@
+use crate::task::Task;
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+// A declarative rule from the config file, applied by `rules run`. Each rule
+// changes a task at most once, so re-running never stacks its effect.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default)]
+    pub when: Condition,
+    pub then: Action,
+}
+
+// Every field that is set has to hold; an empty condition matches all pending tasks
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Condition {
+    // Due within this many days, or already overdue
+    pub due_within_days: Option<i64>,
+    pub overdue: Option<bool>,
+    // Added more than this many days ago
+    pub older_than_days: Option<i64>,
+    pub category: Option<String>,
+    pub tag: Option<String>,
+    // Only tasks at or below this priority, to cap escalation
+    pub max_priority: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Action {
+    pub raise_priority: Option<i32>,
+    pub set_priority: Option<i32>,
+    pub add_tag: Option<String>,
+    pub remove_tag: Option<String>,
+}
+
+// Tags a new task whose name mentions one of the keywords, when it is added, captured or imported
+#[derive(Deserialize, Debug, Clone)]
+pub struct AutoTag {
+    // Words or phrases separated by "|", like "call|phone"
+    pub keywords: String,
+    pub tag: String,
+}
+
+impl AutoTag {
+    pub fn matches(&self, name: &str) -> bool {
+        mentions(&self.keywords, name)
+    }
+}
+
+/// Whether `text` contains one of the "|"-separated keywords. Case is
+/// ignored, and a keyword can be part of a longer word, so "tax" finds "taxes".
+pub fn mentions(keywords: &str, text: &str) -> bool {
+    let text = text.to_lowercase();
+    keywords
+        .split('|')
+        .map(|keyword| keyword.trim().to_lowercase())
+        .any(|keyword| !keyword.is_empty() && text.contains(&keyword))
+}
+
+// The tags of the auto-tag rules that `name` matches, in order and without repeats
+pub fn auto_tags<'a>(rules: &'a [AutoTag], name: &str) -> Vec<&'a str> {
+    let mut tags: Vec<&str> = Vec::new();
+    for rule in rules.iter().filter(|r| r.matches(name)) {
+        if !tags.contains(&rule.tag.as_str()) {
+            tags.push(&rule.tag);
+        }
+    }
+    tags
+}
+
+impl Rule {
+    pub fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+        let when = &self.when;
+        let days_until_due = task.due_date.map(|d| (d - today).num_days());
+
+        !task.done
+            && when
+                .due_within_days
+                .is_none_or(|n| days_until_due.is_some_and(|days| days <= n))
+            && when
+                .overdue
+                .is_none_or(|overdue| days_until_due.is_some_and(|days| days < 0) == overdue)
+            && when
+                .older_than_days
+                .is_none_or(|n| task.age_in_days(today).is_some_and(|age| age > n))
+            && when
+                .category
+                .as_ref()
+                .is_none_or(|c| task.category.as_ref() == Some(c))
+            && when.tag.as_ref().is_none_or(|t| task.tags.contains(t))
+            && when.max_priority.is_none_or(|p| task.priority <= p)
+    }
+}
+
+impl Action {
+    // The priority the task should end up with, if this action changes it
+    pub fn new_priority(&self, current: i32) -> Option<i32> {
+        self.set_priority
+            .or(self.raise_priority.map(|n| current + n))
+            .filter(|p| *p != current)
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(n) = self.raise_priority {
+            parts.push(format!("raise priority by {}", n));
+        }
+        if let Some(p) = self.set_priority {
+            parts.push(format!("set priority to {}", p));
+        }
+        if let Some(tag) = &self.add_tag {
+            parts.push(format!("tag {}", tag));
+        }
+        if let Some(tag) = &self.remove_tag {
+            parts.push(format!("untag {}", tag));
+        }
+        parts.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(config: &str) -> Rule {
+        toml::from_str(config).unwrap()
+    }
+
+    #[test]
+    fn test_rule_conditions() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let mut task = Task::new(
+            "Renew passport",
+            "Personal".to_string(),
+            Some("2025-03-04".to_string()),
+            vec![],
+            1,
+        );
+        task.created_at = NaiveDate::from_ymd_opt(2025, 1, 1);
+
+        let escalate = rule(
+            r#"
+            name = "escalate"
+            when = { due_within_days = 2, max_priority = 2 }
+            then = { raise_priority = 1 }
+            "#,
+        );
+        assert!(escalate.matches(&task, today));
+        assert_eq!(escalate.then.new_priority(task.priority), Some(2));
+
+        let stale = rule(
+            r#"
+            name = "stale"
+            when = { older_than_days = 90, category = "Personal" }
+            then = { add_tag = "stale" }
+            "#,
+        );
+        assert!(!stale.matches(&task, today));
+
+        task.priority = 3;
+        assert!(!escalate.matches(&task, today));
+    }
+
+    #[test]
+    fn test_auto_tags() {
+        let rules: Vec<AutoTag> = vec![
+            toml::from_str("keywords = \"call|phone\"\ntag = \"phone\"").unwrap(),
+            toml::from_str("keywords = \"mum | dad\"\ntag = \"family\"").unwrap(),
+            toml::from_str("keywords = \"ring\"\ntag = \"phone\"").unwrap(),
+        ];
+        assert_eq!(auto_tags(&rules, "Call Mum"), vec!["phone", "family"]);
+        assert_eq!(auto_tags(&rules, "Phone and ring the bank"), vec!["phone"]);
+        assert!(auto_tags(&rules, "Buy milk").is_empty());
+    }
+}
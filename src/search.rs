@@ -10,8 +10,9 @@ impl Search {
             .into_iter()
             .filter(|t| {
                 t.name.contains(query)
-                    || t.category.as_deref().map_or(false, |c| c.contains(query))  // Correct usage
+                    || t.category.as_deref().is_some_and(|c| c.contains(query))  // Correct usage
                     || t.tags.iter().any(|tag| tag.contains(query))
+                    || t.context.as_deref().is_some_and(|c| c.contains(query))
             })
             .collect()
     }
@@ -0,0 +1,232 @@
+use crate::chart::{bar_chart, escape};
+use crate::dates;
+use crate::error::TodoError;
+use crate::priority;
+use crate::task::Task;
+use chrono::{Duration, NaiveDate, Weekday};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// How many weeks back the completion chart goes
+const CHART_WEEKS: i64 = 8;
+
+const STYLE: &str =
+    "body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.3em 0.5em; text-align: left; }
+tr.done td { color: #999; text-decoration: line-through; }
+tr.overdue td.due { color: #c00; font-weight: bold; }
+.charts { display: flex; flex-wrap: wrap; gap: 2em; }
+.filters { margin: 1em 0; }
+.filters input, .filters select { margin-right: 1em; }";
+
+// Hides the rows of the task table that don't match the filters
+const SCRIPT: &str = "function applyFilters() {
+  var text = document.getElementById('text').value.toLowerCase();
+  var category = document.getElementById('category').value;
+  var status = document.getElementById('status').value;
+  document.querySelectorAll('#tasks tbody tr').forEach(function (row) {
+    var shown = row.textContent.toLowerCase().indexOf(text) >= 0
+      && (category === '' || row.dataset.category === category)
+      && (status === '' || row.dataset.status === status);
+    row.style.display = shown ? '' : 'none';
+  });
+}";
+
+pub struct Site;
+
+impl Site {
+    /// Writes a read-only dashboard into `dir`: index.html with charts and
+    /// every task, filterable by text, category and status, and a page per
+    /// category under categories/. Returns how many pages were written.
+    pub fn write(
+        dir: &Path,
+        tasks: &[Task],
+        today: NaiveDate,
+        week_start: Weekday,
+    ) -> Result<usize, TodoError> {
+        let mut by_category: BTreeMap<&str, Vec<&Task>> = BTreeMap::new();
+        for task in tasks {
+            by_category
+                .entry(task.category.as_deref().unwrap_or("General"))
+                .or_default()
+                .push(task);
+        }
+
+        let mut used = HashSet::new();
+        let pages: Vec<(&str, String)> = by_category
+            .keys()
+            .map(|category| {
+                let mut name = slug(category);
+                let base = name.clone();
+                for n in 2.. {
+                    if used.insert(name.clone()) {
+                        break;
+                    }
+                    name = format!("{}-{}", base, n);
+                }
+                (*category, format!("categories/{}.html", name))
+            })
+            .collect();
+
+        let open: Vec<(String, i64)> = by_category
+            .iter()
+            .map(|(category, tasks)| {
+                let count = tasks.iter().filter(|t| !t.done).count() as i64;
+                (category.to_string(), count)
+            })
+            .collect();
+        let charts = format!(
+            "<div class=\"charts\">\n{}\n{}\n</div>",
+            bar_chart("Open tasks by category", &open),
+            bar_chart(
+                "Completed per week",
+                &weekly_completions(tasks, today, week_start)
+            )
+        );
+
+        let links: Vec<String> = pages
+            .iter()
+            .map(|(category, path)| {
+                format!(
+                    "<li><a href=\"{}\">{}</a> ({})</li>",
+                    path,
+                    escape(category),
+                    by_category[category].len()
+                )
+            })
+            .collect();
+        let options: Vec<String> = by_category
+            .keys()
+            .map(|c| format!("<option>{}</option>", escape(c)))
+            .collect();
+        let filters = format!(
+            "<div class=\"filters\">\n\
+             <input id=\"text\" placeholder=\"Search\" oninput=\"applyFilters()\">\n\
+             <select id=\"category\" onchange=\"applyFilters()\"><option value=\"\">All categories</option>{}</select>\n\
+             <select id=\"status\" onchange=\"applyFilters()\"><option value=\"\">Any status</option>\
+             <option value=\"open\">Open</option><option value=\"done\">Done</option></select>\n\
+             </div>",
+            options.join("")
+        );
+        let all: Vec<&Task> = tasks.iter().collect();
+        let index = page(
+            "Tasks",
+            today,
+            &format!(
+                "{}\n<h2>Categories</h2>\n<ul>\n{}\n</ul>\n<h2>All tasks</h2>\n{}\n{}\n<script>\n{}\n</script>",
+                charts,
+                links.join("\n"),
+                filters,
+                task_table(&all, today),
+                SCRIPT
+            ),
+            "",
+        );
+
+        fs::create_dir_all(dir.join("categories"))?;
+        fs::write(dir.join("index.html"), index)?;
+        for (category, path) in &pages {
+            let tasks = &by_category[category];
+            let open = tasks.iter().filter(|t| !t.done).count();
+            let body = format!(
+                "<p>{} open, {} done.</p>\n{}",
+                open,
+                tasks.len() - open,
+                task_table(tasks, today)
+            );
+            fs::write(dir.join(path), page(category, today, &body, "../"))?;
+        }
+        Ok(pages.len() + 1)
+    }
+}
+
+// A whole HTML page; `root` leads from the page back to the site's top directory
+fn page(title: &str, today: NaiveDate, body: &str, root: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title} - yawmak</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n\
+         <h1><a href=\"{root}index.html\">yawmak</a>: {title}</h1>\n\
+         <p>Generated on {today}.</p>\n{body}\n</body>\n</html>\n",
+        title = escape(title),
+        today = today,
+        body = body,
+        root = root,
+    )
+}
+
+fn task_table(tasks: &[&Task], today: NaiveDate) -> String {
+    let rows: Vec<String> = tasks
+        .iter()
+        .map(|task| {
+            let status = if task.done { "done" } else { "open" };
+            let overdue = !task.done && task.due_date.is_some_and(|d| d < today);
+            let category = task.category.as_deref().unwrap_or("General");
+            format!(
+                "<tr class=\"{}{}\" data-category=\"{}\" data-status=\"{}\">\
+                 <td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"due\">{}</td><td>{}</td><td>{}</td></tr>",
+                status,
+                if overdue { " overdue" } else { "" },
+                escape(category),
+                status,
+                task.id,
+                escape(&task.name),
+                escape(category),
+                escape(&task.tags.join(", ")),
+                task.due_date.map(|d| d.to_string()).unwrap_or_default(),
+                priority::label(task.priority),
+                task.completion_date
+                    .map(|d| format!("done {}", d))
+                    .unwrap_or_else(|| status.to_string()),
+            )
+        })
+        .collect();
+    format!(
+        "<table id=\"tasks\">\n<thead><tr><th>ID</th><th>Name</th><th>Category</th><th>Tags</th>\
+         <th>Due</th><th>Priority</th><th>Status</th></tr></thead>\n<tbody>\n{}\n</tbody>\n</table>",
+        rows.join("\n")
+    )
+}
+
+// How many tasks were completed in each of the last `CHART_WEEKS` weeks, oldest first
+fn weekly_completions(tasks: &[Task], today: NaiveDate, week_start: Weekday) -> Vec<(String, i64)> {
+    (0..CHART_WEEKS)
+        .rev()
+        .map(|weeks_ago| {
+            let week = dates::week_label(today - Duration::weeks(weeks_ago), week_start);
+            let count = tasks
+                .iter()
+                .filter_map(|t| t.completion_date)
+                .filter(|d| dates::week_label(*d, week_start) == week)
+                .count() as i64;
+            (week, count)
+        })
+        .collect()
+}
+
+// A category name as a file name, like "Side Projects" -> "side-projects"
+fn slug(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    if words.is_empty() {
+        "category".to_string()
+    } else {
+        words.join("-")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Side Projects"), "side-projects");
+        assert_eq!(slug("R&D / 2025"), "r-d-2025");
+        assert_eq!(slug("???"), "category");
+    }
+}
@@ -1,5 +1,6 @@
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Task {
@@ -11,6 +12,38 @@ pub struct Task {
     pub due_date: Option<NaiveDate>,
     pub completion_date: Option<NaiveDate>,
     pub priority: i32,
+    pub scheduled_date: Option<NaiveDate>,
+    pub created_at: Option<NaiveDate>,
+    pub pinned: bool,
+    pub context: Option<String>,
+    pub waiting_for: Option<String>,
+    pub follow_up: Option<NaiveDate>,
+    pub created_by: Option<String>,
+    pub completed_by: Option<String>,
+    // The task this one is a subtask of
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    // How often the task comes back once done, like "weekly" or "every 2 weeks"
+    #[serde(default)]
+    pub repeat: Option<String>,
+    // The task stays out of `list --startable` until this day
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    // The person the task is for, like "Sam" or "Alice <alice@example.com>"
+    #[serde(default)]
+    pub assignee: Option<String>,
+    // How many minutes the task is expected to take
+    #[serde(default)]
+    pub estimate: Option<i64>,
+    // Shown before the name, like "🛒"
+    #[serde(default)]
+    pub icon: Option<String>,
+    // The icon of the task's category, shown when the task has none of its own
+    #[serde(skip)]
+    pub category_icon: Option<String>,
+    // Values of the custom fields named under `attributes` in the config, like client = "ACME"
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
 }
 
 impl Task {
@@ -31,11 +64,177 @@ impl Task {
             due_date: due_date_parsed,
             completion_date: None,
             priority,
+            scheduled_date: None,
+            created_at: None,
+            pinned: false,
+            context: None,
+            waiting_for: None,
+            follow_up: None,
+            created_by: None,
+            completed_by: None,
+            parent_id: None,
+            repeat: None,
+            start_date: None,
+            assignee: None,
+            estimate: None,
+            icon: None,
+            category_icon: None,
+            attributes: BTreeMap::new(),
         }
     }
+
+    // Waiting tasks stay out of the way until their follow-up date arrives
+    pub fn is_waiting(&self, today: NaiveDate) -> bool {
+        self.waiting_for.is_some() && self.follow_up.is_none_or(|date| date > today)
+    }
+
+    pub fn needs_follow_up(&self, today: NaiveDate) -> bool {
+        self.waiting_for.is_some() && self.follow_up.is_some_and(|date| date <= today)
+    }
+
+    pub fn is_startable(&self, today: NaiveDate) -> bool {
+        self.start_date.is_none_or(|date| date <= today)
+    }
+
+    // The name with the task's icon, or its category's, in front
+    pub fn display_name(&self) -> String {
+        match self.icon.as_ref().or(self.category_icon.as_ref()) {
+            Some(icon) => format!("{} {}", icon, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    pub fn age_in_days(&self, today: NaiveDate) -> Option<i64> {
+        self.created_at.map(|created| (today - created).num_days())
+    }
+}
+
+/// The task IDs written as `#<id>` in a task name, in order of appearance.
+/// A `#` followed by anything other than digits (a tag like `#home`, or
+/// `#12b`) is not a reference.
+pub fn references(text: &str) -> Vec<i32> {
+    let mut ids = Vec::new();
+    for (start, _) in text.match_indices('#') {
+        let rest = &text[start + 1..];
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let follows_word = rest[end..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        if end == 0 || follows_word {
+            continue;
+        }
+        if let Ok(id) = rest[..end].parse() {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// The web links in `text`, in order of appearance. A link starts at
+/// `http://` or `https://` and runs to the next space, leaving out trailing
+/// punctuation and a closing bracket that isn't part of the link, as in
+/// "(see https://example.com)."
+pub fn urls(text: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    let mut from = 0;
+    while let Some(found) = ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| text[from..].find(scheme))
+        .min()
+    {
+        let start = from + found;
+        let mut end = text[start..]
+            .find(char::is_whitespace)
+            .map_or(text.len(), |n| start + n);
+        while let Some(last) = text[start..end].chars().last() {
+            let link = &text[start..end];
+            let unbalanced = match last {
+                ')' | ']' | '>' => {
+                    let opener = match last {
+                        ')' => '(',
+                        ']' => '[',
+                        _ => '<',
+                    };
+                    link.matches(last).count() > link.matches(opener).count()
+                }
+                _ => false,
+            };
+            if unbalanced || ".,;:!?'\"".contains(last) {
+                end -= last.len_utf8();
+            } else {
+                break;
+            }
+        }
+        let scheme_end = text[start..].find("://").unwrap() + start + 3;
+        if end > scheme_end {
+            links.push(text[start..end].to_string());
+        }
+        from = end.max(scheme_end);
+    }
+    links
+}
+
+/// Reads a custom field setting written like `client=ACME`. The value may
+/// be empty, which clears the field on `update`.
+pub fn parse_attribute(text: &str) -> Result<(String, String), String> {
+    match text.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err("use a field and value like client=ACME".to_string()),
+    }
+}
+
+/// Puts subtasks right after their parent, paired with how deeply each task
+/// is nested. A task whose parent isn't among `tasks` starts a tree of its
+/// own, and otherwise the order of `tasks` is kept.
+pub fn tree_order(tasks: Vec<Task>) -> Vec<(usize, Task)> {
+    let ids: HashSet<i32> = tasks.iter().map(|t| t.id).collect();
+    let mut placed = vec![false; tasks.len()];
+    let mut order = Vec::new();
+    let mut stack: Vec<(usize, usize)> = tasks
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, t)| t.parent_id.is_none_or(|p| !ids.contains(&p)))
+        .map(|(i, _)| (0, i))
+        .collect();
+    loop {
+        // Tasks caught in a loop of parents have no root; start from the first one left
+        if stack.is_empty() {
+            match placed.iter().position(|p| !p) {
+                Some(i) => stack.push((0, i)),
+                None => break,
+            }
+        }
+        let (depth, i) = stack.pop().unwrap();
+        if placed[i] {
+            continue;
+        }
+        placed[i] = true;
+        order.push((depth, i));
+        let parent = tasks[i].id;
+        stack.extend(
+            tasks
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(j, t)| t.parent_id == Some(parent) && !placed[*j])
+                .map(|(j, _)| (depth + 1, j)),
+        );
+    }
+
+    let mut tasks: Vec<Option<Task>> = tasks.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|(depth, i)| (depth, tasks[i].take().unwrap()))
+        .collect()
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     use chrono::NaiveDate;
@@ -92,4 +291,108 @@ mod tests {
         assert_eq!(task.priority, priority);
         assert!(task.completion_date.is_none());
     }
+
+    #[test]
+    fn test_waiting_task_resurfaces_on_follow_up() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let mut task = Task::new("Test Task", "Work".to_string(), None, vec![], 0);
+        assert!(!task.is_waiting(today));
+
+        task.waiting_for = Some("Bob's reply".to_string());
+        assert!(task.is_waiting(today));
+        assert!(!task.needs_follow_up(today));
+
+        task.follow_up = Some(today);
+        assert!(!task.is_waiting(today));
+        assert!(task.needs_follow_up(today));
+    }
+
+    #[test]
+    fn test_task_is_startable_from_its_start_date() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let mut task = Task::new("Test Task", "Work".to_string(), None, vec![], 0);
+        assert!(task.is_startable(today));
+
+        task.start_date = NaiveDate::from_ymd_opt(2025, 4, 1);
+        assert!(!task.is_startable(today));
+        assert!(task.is_startable(NaiveDate::from_ymd_opt(2025, 4, 1).unwrap()));
+    }
+
+    #[test]
+    fn test_display_name_prefers_the_task_icon() {
+        let mut task = Task::new("Buy milk", "Errands".to_string(), None, vec![], 0);
+        assert_eq!(task.display_name(), "Buy milk");
+
+        task.category_icon = Some("🛒".to_string());
+        assert_eq!(task.display_name(), "🛒 Buy milk");
+
+        task.icon = Some("🥛".to_string());
+        assert_eq!(task.display_name(), "🥛 Buy milk");
+    }
+
+    #[test]
+    fn test_references() {
+        assert_eq!(
+            references("Review #12 after #3, then #12 again"),
+            vec![12, 3]
+        );
+        assert!(references("Buy milk #home #12b #").is_empty());
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            urls("Review https://example.com/pr/12, then (see http://wiki.example/Foo_(bar))."),
+            vec!["https://example.com/pr/12", "http://wiki.example/Foo_(bar)"]
+        );
+        assert_eq!(urls("<https://a.example/x>"), vec!["https://a.example/x"]);
+        assert!(urls("Call https:// about http").is_empty());
+    }
+
+    #[test]
+    fn test_parse_attribute() {
+        assert_eq!(
+            parse_attribute("client = ACME Corp"),
+            Ok(("client".to_string(), "ACME Corp".to_string()))
+        );
+        assert_eq!(
+            parse_attribute("ticket=a=b"),
+            Ok(("ticket".to_string(), "a=b".to_string()))
+        );
+        assert_eq!(
+            parse_attribute("client="),
+            Ok(("client".to_string(), String::new()))
+        );
+        assert!(parse_attribute("client").is_err());
+        assert!(parse_attribute("=ACME").is_err());
+    }
+
+    #[test]
+    fn test_tree_order_nests_subtasks_under_their_parent() {
+        let task = |id: i32, parent_id: Option<i32>| {
+            let mut task = Task::new("Test Task", "Work".to_string(), None, vec![], 0);
+            task.id = id;
+            task.parent_id = parent_id;
+            task
+        };
+        let tasks = vec![
+            task(1, None),
+            task(2, None),
+            task(3, Some(1)),
+            task(4, Some(3)),
+            task(5, Some(9)),
+            task(6, Some(7)),
+            task(7, Some(6)),
+        ];
+
+        let order: Vec<(usize, i32)> = tree_order(tasks)
+            .into_iter()
+            .map(|(depth, t)| (depth, t.id))
+            .collect();
+
+        assert_eq!(
+            order,
+            vec![(0, 1), (1, 3), (2, 4), (0, 2), (0, 5), (0, 6), (1, 7)]
+        );
+    }
 }
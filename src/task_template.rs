@@ -0,0 +1,60 @@
+use crate::task::Task;
+use chrono::{Duration, NaiveDate};
+
+// A named task definition saved with `template save` and expanded by `add --from-template`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskTemplate {
+    pub name: String,
+    // The task name, where {{date}} stands for the day the task is added
+    pub pattern: String,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    pub priority: i32,
+    // Days after the day the task is added that it is due
+    pub due_in: Option<i64>,
+}
+
+impl TaskTemplate {
+    /// Builds the task this template describes when added on `today`.
+    pub fn to_task(&self, today: NaiveDate) -> Task {
+        let mut task = Task::new(
+            &self
+                .pattern
+                .replace("{{date}}", &today.format("%Y-%m-%d").to_string()),
+            self.category
+                .clone()
+                .unwrap_or_else(|| "General".to_string()),
+            None,
+            self.tags.clone(),
+            self.priority,
+        );
+        task.due_date = self.due_in.map(|days| today + Duration::days(days));
+        task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_task_fills_in_date_and_due_offset() {
+        let template = TaskTemplate {
+            name: "invoice".to_string(),
+            pattern: "Send invoice for {{date}}".to_string(),
+            category: None,
+            tags: vec!["billing".to_string()],
+            priority: 2,
+            due_in: Some(7),
+        };
+        let today = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+
+        let task = template.to_task(today);
+
+        assert_eq!(task.name, "Send invoice for 2025-03-05");
+        assert_eq!(task.category, Some("General".to_string()));
+        assert_eq!(task.tags, vec!["billing"]);
+        assert_eq!(task.priority, 2);
+        assert_eq!(task.due_date, NaiveDate::from_ymd_opt(2025, 3, 12));
+    }
+}
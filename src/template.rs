@@ -0,0 +1,110 @@
+use crate::error::TodoError;
+use crate::task::Task;
+
+// Placeholders understood by `list --template`
+const FIELDS: &[&str] = &[
+    "id",
+    "name",
+    "category",
+    "tags",
+    "due",
+    "done",
+    "priority",
+    "completed",
+    "scheduled",
+    "created",
+    "pinned",
+    "context",
+    "waiting_for",
+    "follow_up",
+    "created_by",
+    "completed_by",
+    "repeat",
+    "start",
+    "assignee",
+];
+
+/// Fills `{{field}}` placeholders in `template` with the task's values.
+/// Missing values become empty strings, dates are written as 2024-09-01 and
+/// tags are joined with commas. Unknown fields are an error, so typos don't
+/// silently print nothing.
+pub fn render(template: &str, task: &Task) -> Result<String, TodoError> {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| {
+            TodoError::Custom(format!("Unclosed placeholder in template: {}", template))
+        })?;
+        output.push_str(&field(task, after[..end].trim())?);
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn field(task: &Task, name: &str) -> Result<String, TodoError> {
+    let date = |date: Option<chrono::NaiveDate>| {
+        date.map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    };
+    let text = |text: &Option<String>| text.clone().unwrap_or_default();
+
+    Ok(match name {
+        "id" => task.id.to_string(),
+        "name" => task.name.clone(),
+        "category" => text(&task.category),
+        "tags" => task.tags.join(","),
+        "due" => date(task.due_date),
+        "done" => task.done.to_string(),
+        "priority" => task.priority.to_string(),
+        "completed" => date(task.completion_date),
+        "scheduled" => date(task.scheduled_date),
+        "created" => date(task.created_at),
+        "pinned" => task.pinned.to_string(),
+        "context" => text(&task.context),
+        "waiting_for" => text(&task.waiting_for),
+        "follow_up" => date(task.follow_up),
+        "created_by" => text(&task.created_by),
+        "completed_by" => text(&task.completed_by),
+        "repeat" => text(&task.repeat),
+        "start" => date(task.start_date),
+        "assignee" => text(&task.assignee),
+        _ => {
+            return Err(TodoError::Custom(format!(
+                "Unknown template field '{}'. Available fields: {}.",
+                name,
+                FIELDS.join(", ")
+            )))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_fields() {
+        let mut task = Task::new(
+            "Pay rent",
+            "Home".to_string(),
+            Some("2024-09-01".to_string()),
+            vec!["bills".to_string(), "monthly".to_string()],
+            2,
+        );
+        task.id = 7;
+
+        assert_eq!(
+            render("{{id}} {{name}} ({{due}})", &task).unwrap(),
+            "7 Pay rent (2024-09-01)"
+        );
+        assert_eq!(
+            render("[{{ category }}] {{tags}} {{context}}|", &task).unwrap(),
+            "[Home] bills,monthly |"
+        );
+        assert!(render("{{nope}}", &task).is_err());
+        assert!(render("{{id", &task).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use crossterm::style::Color;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+/// The colours yawmak prints with, picked with `theme` in the config file.
+/// `colorblind-safe` never relies on telling red from green, using blue for
+/// good news and yellow with bold for bad; `high-contrast` uses bold, bright
+/// colours only; `solarized` fits terminals set to the Solarized palette.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Default,
+    Solarized,
+    HighContrast,
+    ColorblindSafe,
+}
+
+// What a colour says about the text it is on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    Good,
+    Bad,
+    Warning,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+// Chooses the theme for the rest of the run; the first call wins
+pub fn set(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+pub fn current() -> Theme {
+    THEME.get().copied().unwrap_or_default()
+}
+
+/// The prettytable style spec for a role in the current theme, like "Fr"
+/// for red or "bFY" for bold bright yellow.
+pub fn style(role: Role) -> &'static str {
+    current().style(role)
+}
+
+/// The colour for a role in the interactive list, taken from the same style
+/// spec as the tables so the two always agree.
+pub fn terminal_color(role: Role) -> Option<Color> {
+    let spec = style(role);
+    let letter = spec.chars().skip_while(|c| *c != 'F').nth(1)?;
+    Some(match letter {
+        'r' => Color::DarkRed,
+        'R' => Color::Red,
+        'g' => Color::DarkGreen,
+        'G' => Color::Green,
+        'y' => Color::DarkYellow,
+        'Y' => Color::Yellow,
+        'b' => Color::DarkBlue,
+        'B' => Color::Blue,
+        'm' => Color::DarkMagenta,
+        'M' => Color::Magenta,
+        'c' => Color::DarkCyan,
+        'C' => Color::Cyan,
+        'w' => Color::Grey,
+        'W' => Color::White,
+        'd' => Color::Black,
+        _ => Color::DarkGrey,
+    })
+}
+
+// The Mermaid style given to done tasks in `graph`
+pub fn chart_done_style() -> &'static str {
+    match current() {
+        Theme::Default | Theme::ColorblindSafe => "fill:#eee,color:#888",
+        Theme::Solarized => "fill:#eee8d5,color:#93a1a1",
+        Theme::HighContrast => "fill:#fff,stroke:#000,stroke-dasharray:4,color:#000",
+    }
+}
+
+// The fill of the bars in SVG charts from `report --svg` and `export site`
+pub fn chart_bar_color() -> &'static str {
+    match current() {
+        Theme::Default => "#4a7ab5",
+        Theme::Solarized => "#268bd2",
+        Theme::HighContrast => "#000",
+        Theme::ColorblindSafe => "#0072b2",
+    }
+}
+
+impl Theme {
+    fn style(self, role: Role) -> &'static str {
+        match (self, role) {
+            (Theme::Default, Role::Good) => "Fg",
+            (Theme::Default, Role::Bad) => "Fr",
+            (Theme::Default, Role::Warning) => "Fy",
+            (Theme::Default, Role::Low) => "Fc",
+            (Theme::Default, Role::Medium) => "Fy",
+            (Theme::Default, Role::High) => "Fr",
+            (Theme::Default, Role::Critical) => "bFr",
+
+            (Theme::Solarized, Role::Good) => "Fg",
+            (Theme::Solarized, Role::Bad) => "Fr",
+            (Theme::Solarized, Role::Warning) => "Fy",
+            (Theme::Solarized, Role::Low) => "Fb",
+            (Theme::Solarized, Role::Medium) => "Fc",
+            // Solarized's orange sits on bright red
+            (Theme::Solarized, Role::High) => "FR",
+            (Theme::Solarized, Role::Critical) => "bFm",
+
+            (Theme::HighContrast, Role::Good) => "bFG",
+            (Theme::HighContrast, Role::Bad) => "bFR",
+            (Theme::HighContrast, Role::Warning) => "bFY",
+            (Theme::HighContrast, Role::Low) => "bFW",
+            (Theme::HighContrast, Role::Medium) => "bFY",
+            (Theme::HighContrast, Role::High) => "bFR",
+            (Theme::HighContrast, Role::Critical) => "buFR",
+
+            (Theme::ColorblindSafe, Role::Good) => "FB",
+            (Theme::ColorblindSafe, Role::Bad) => "bFY",
+            (Theme::ColorblindSafe, Role::Warning) => "FM",
+            (Theme::ColorblindSafe, Role::Low) => "FC",
+            (Theme::ColorblindSafe, Role::Medium) => "FB",
+            (Theme::ColorblindSafe, Role::High) => "bFY",
+            (Theme::ColorblindSafe, Role::Critical) => "buFY",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colorblind_safe_avoids_red_and_green() {
+        let roles = [
+            Role::Good,
+            Role::Bad,
+            Role::Warning,
+            Role::Low,
+            Role::Medium,
+            Role::High,
+            Role::Critical,
+        ];
+        for role in roles {
+            let spec = Theme::ColorblindSafe.style(role);
+            assert!(!spec.contains(['r', 'R', 'g', 'G']), "{:?}", role);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::priority;
+use crate::task::Task;
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+/// How much each part of a task counts towards its urgency, read from the
+/// `[urgency]` table of the config file. Each part is scaled to between 0
+/// and 1 before it is weighted, the way Taskwarrior does it.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct Urgency {
+    // Given in full to critical tasks, in part to lower priorities
+    pub priority: f64,
+    // Given in full a week after the due date, a fifth of it two weeks or more before
+    pub due: f64,
+    // Given in full once a task is `max_age_days` old
+    pub age: f64,
+    pub max_age_days: i64,
+    // Given mostly for one tag and in full for three or more
+    pub tags: f64,
+}
+
+impl Default for Urgency {
+    fn default() -> Self {
+        Urgency {
+            priority: 6.0,
+            due: 12.0,
+            age: 2.0,
+            max_age_days: 365,
+            tags: 1.0,
+        }
+    }
+}
+
+impl Urgency {
+    /// The urgency of a task on `today`; done tasks have none.
+    pub fn score(&self, task: &Task, today: NaiveDate) -> f64 {
+        if task.done {
+            return 0.0;
+        }
+
+        let priority = (task.priority as f64 / priority::MAX as f64).min(1.0);
+        let due = task.due_date.map_or(0.0, |due| {
+            let overdue = (today - due).num_days() as f64;
+            if overdue >= 7.0 {
+                1.0
+            } else if overdue >= -14.0 {
+                (overdue + 14.0) * 0.8 / 21.0 + 0.2
+            } else {
+                0.2
+            }
+        });
+        let age = task.age_in_days(today).map_or(0.0, |days| {
+            (days.max(0) as f64 / self.max_age_days.max(1) as f64).min(1.0)
+        });
+        let tags = match task.tags.len() {
+            0 => 0.0,
+            1 => 0.8,
+            2 => 0.9,
+            _ => 1.0,
+        };
+
+        self.priority * priority + self.due * due + self.age * age + self.tags * tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_weights_each_part() {
+        let today = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let mut task = Task::new("Renew passport", "General".to_string(), None, vec![], 4);
+        task.created_at = Some(today);
+        let urgency = Urgency::default();
+
+        assert_eq!(urgency.score(&task, today), 6.0);
+
+        task.due_date = NaiveDate::from_ymd_opt(2025, 2, 26);
+        task.tags = vec!["travel".to_string()];
+        assert!((urgency.score(&task, today) - 18.8).abs() < 1e-9);
+
+        task.done = true;
+        assert_eq!(urgency.score(&task, today), 0.0);
+    }
+}
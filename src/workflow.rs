@@ -0,0 +1,78 @@
+use crate::task::Task;
+use chrono::{Duration, NaiveDate};
+use serde::Deserialize;
+
+// A named chain of steps from the config file, created together by `workflow start`
+#[derive(Deserialize, Debug, Clone)]
+pub struct Workflow {
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WorkflowStep {
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Days after the start date that the step is due
+    #[serde(default)]
+    pub offset_days: i64,
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl Workflow {
+    /// Builds one task per step, named "<step>: <title>" and due `offset_days`
+    /// after `start`. Every task is also tagged with the workflow's name so
+    /// the chain can be found again with `search`.
+    pub fn tasks(
+        &self,
+        workflow: &str,
+        title: &str,
+        category: &str,
+        start: NaiveDate,
+    ) -> Vec<Task> {
+        self.steps
+            .iter()
+            .map(|step| {
+                let mut tags = step.tags.clone();
+                tags.push(workflow.to_string());
+                let due_date = start + Duration::days(step.offset_days);
+                Task::new(
+                    &format!("{}: {}", step.name, title),
+                    category.to_string(),
+                    Some(due_date.format("%Y-%m-%d").to_string()),
+                    tags,
+                    step.priority,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_become_dated_tagged_tasks() {
+        let workflow: Workflow = toml::from_str(
+            r#"
+            steps = [
+                { name = "Draft", tags = ["writing"] },
+                { name = "Review", offset_days = 3 },
+                { name = "Publish", offset_days = 5, priority = 2 },
+            ]
+            "#,
+        )
+        .unwrap();
+        let start = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+
+        let tasks = workflow.tasks("blog-post", "DuckDB tips", "Writing", start);
+
+        assert_eq!(tasks.len(), 3);
+        assert_eq!(tasks[0].name, "Draft: DuckDB tips");
+        assert_eq!(tasks[0].tags, vec!["writing", "blog-post"]);
+        assert_eq!(tasks[1].due_date, NaiveDate::from_ymd_opt(2025, 3, 6));
+        assert_eq!(tasks[2].priority, 2);
+    }
+}